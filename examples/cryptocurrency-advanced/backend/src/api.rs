@@ -30,6 +30,10 @@ use crate::{wallet::Wallet, Schema, CRYPTOCURRENCY_SERVICE_ID};
 pub struct WalletQuery {
     /// Public key of the queried wallet.
     pub pub_key: PublicKey,
+    /// Height of the block after which the wallet state is queried. The latest state
+    /// is returned if not specified; historical states are available only if the node
+    /// runs in the archive mode.
+    pub height: Option<Height>,
 }
 
 /// Proof of existence for specific wallet.
@@ -68,15 +72,16 @@ pub struct PublicApi;
 impl PublicApi {
     /// Endpoint for getting a single wallet.
     pub fn wallet_info(state: &ServiceApiState, query: WalletQuery) -> api::Result<WalletInfo> {
-        let snapshot = state.snapshot();
+        let snapshot = match query.height {
+            Some(height) => state.snapshot_at(height)?,
+            None => state.snapshot(),
+        };
         let general_schema = blockchain::Schema::new(&snapshot);
         let currency_schema = Schema::new(&snapshot);
 
-        let max_height = general_schema.block_hashes_by_height().len() - 1;
+        let max_height = general_schema.height();
 
-        let block_proof = general_schema
-            .block_and_precommits(Height(max_height))
-            .unwrap();
+        let block_proof = general_schema.block_and_precommits(max_height).unwrap();
 
         let to_table: MapProof<Hash, Hash> =
             general_schema.get_proof_to_service_table(CRYPTOCURRENCY_SERVICE_ID, 0);
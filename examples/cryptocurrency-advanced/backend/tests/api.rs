@@ -234,7 +234,10 @@ impl CryptocurrencyApi {
         let wallet_info = self
             .inner
             .public(ApiKind::Service("cryptocurrency"))
-            .query(&WalletQuery { pub_key })
+            .query(&WalletQuery {
+                pub_key,
+                height: None,
+            })
             .get::<WalletInfo>("v1/wallets/info")
             .unwrap();
 
@@ -264,7 +267,10 @@ impl CryptocurrencyApi {
         let wallet_info: WalletInfo = self
             .inner
             .public(ApiKind::Service("cryptocurrency"))
-            .query(&WalletQuery { pub_key })
+            .query(&WalletQuery {
+                pub_key,
+                height: None,
+            })
             .get("v1/wallets/info")
             .unwrap();
 
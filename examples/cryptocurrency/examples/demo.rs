@@ -52,6 +52,7 @@ fn node_config() -> NodeConfig {
         services_configs: Default::default(),
         database: Default::default(),
         thread_pool_size: Default::default(),
+        state_sync: Default::default(),
     }
 }
 
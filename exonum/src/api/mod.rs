@@ -32,7 +32,7 @@ pub mod backends;
 pub mod error;
 pub mod node;
 mod state;
-pub(crate) mod websocket;
+pub mod websocket;
 mod with;
 
 /// Defines an object that could be used as an API backend.
@@ -158,6 +158,7 @@ impl ServiceApiScope {
 /// use exonum::api::{self, ServiceApiBuilder, ServiceApiState};
 /// use exonum::blockchain::{Schema};
 /// use exonum::crypto::{Hash, PublicKey};
+/// use exonum::helpers::Height;
 ///
 /// // Declares a type which describes an API specification and implementation.
 /// pub struct MyApi;
@@ -181,8 +182,7 @@ impl ServiceApiScope {
 ///     // Immutable handler, which returns a hash of the block at the given height.
 ///     pub fn block_hash(state: &ServiceApiState, query: MyQuery) -> api::Result<Option<BlockInfo>> {
 ///         let schema = Schema::new(state.snapshot());
-///         Ok(schema.block_hashes_by_height()
-///             .get(query.block_height)
+///         Ok(schema.block_hash_by_height(Height(query.block_height))
 ///             .map(|hash| BlockInfo { hash })
 ///         )
 ///     }
@@ -204,7 +204,7 @@ impl ServiceApiScope {
 ///         let blockchain = state.blockchain().clone();
 ///         Box::new(futures::lazy(move || {
 ///             let schema = Schema::new(blockchain.snapshot());
-///             Ok(schema.block_hashes_by_height().get(query.block_height))
+///             Ok(schema.block_hash_by_height(Height(query.block_height)))
 ///         }))
 ///     }
 /// }
@@ -17,14 +17,18 @@
 //! Private API includes requests that are available only to the blockchain
 //! administrators, e.g. view the list of services on the current node.
 
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap, net::SocketAddr, sync::mpsc, time::Duration};
 
 use crate::api::{Error as ApiError, ServiceApiScope, ServiceApiState};
-use crate::blockchain::{Service, SharedNodeState};
-use crate::crypto::PublicKey;
+use crate::blockchain::{Equivocation, Schema, Service, SharedNodeState};
+use crate::crypto::{Hash, PublicKey};
+use crate::helpers::{Height, Milliseconds, Round, ValidatorId};
 use crate::messages::PROTOCOL_MAJOR_VERSION;
 use crate::node::{ConnectInfo, ExternalMessage};
 
+/// Time to wait for the node to respond with a dump of its consensus state.
+const CONSENSUS_STATE_TIMEOUT: Milliseconds = 5_000;
+
 /// Short information about the service.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ServiceInfo {
@@ -101,6 +105,30 @@ struct ConsensusEnabledQuery {
     enabled: bool,
 }
 
+/// Query for creating a backup of the node database.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BackupQuery {
+    /// Path to the backup directory on the node machine. The directory should not exist.
+    pub path: String,
+}
+
+/// Information about the recorded evidence of an equivocation.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EquivocationInfo {
+    /// Hash of the evidence.
+    pub hash: Hash,
+    /// Identifier of the validator which has signed the conflicting messages.
+    pub validator: ValidatorId,
+    /// Consensus public key of the validator.
+    pub author: PublicKey,
+    /// Height of the conflicting messages.
+    pub height: Height,
+    /// Round of the conflicting messages.
+    pub round: Round,
+    /// The conflicting messages.
+    pub evidence: Equivocation,
+}
+
 /// Private system API.
 #[derive(Clone, Debug)]
 pub struct SystemApi {
@@ -125,7 +153,10 @@ impl SystemApi {
             .handle_is_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_set_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_shutdown("v1/shutdown", api_scope)
-            .handle_rebroadcast("v1/rebroadcast", api_scope);
+            .handle_rebroadcast("v1/rebroadcast", api_scope)
+            .handle_backup("v1/backup", api_scope)
+            .handle_equivocations("v1/equivocations", api_scope)
+            .handle_consensus_state("v1/consensus_state", api_scope);
         api_scope
     }
 
@@ -229,4 +260,54 @@ impl SystemApi {
         });
         self
     }
+
+    fn handle_backup(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint_mut(name, move |state: &ServiceApiState, query: BackupQuery| {
+            state
+                .blockchain()
+                .create_backup(&query.path)
+                .map_err(ApiError::InternalError)
+        });
+        self
+    }
+
+    fn handle_equivocations(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            let snapshot = state.snapshot();
+            let equivocations = Schema::new(&snapshot)
+                .equivocations()
+                .iter()
+                .map(|(hash, evidence)| EquivocationInfo {
+                    hash,
+                    validator: evidence.validator(),
+                    author: evidence.author(),
+                    height: evidence.height(),
+                    round: evidence.round(),
+                    evidence,
+                })
+                .collect::<Vec<_>>();
+            Ok(equivocations)
+        });
+        self
+    }
+
+    fn handle_consensus_state(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            let (sender, receiver) = mpsc::channel();
+            state
+                .sender()
+                .send_external_message(ExternalMessage::ConsensusState(sender))
+                .map_err(ApiError::from)?;
+            // The dump is taken by the node when it handles the request, so it is up to date.
+            receiver
+                .recv_timeout(Duration::from_millis(CONSENSUS_STATE_TIMEOUT))
+                .map_err(|e| {
+                    ApiError::InternalError(format_err!(
+                        "Node has not sent its consensus state: {}",
+                        e
+                    ))
+                })
+        });
+        self
+    }
 }
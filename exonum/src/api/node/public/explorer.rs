@@ -13,12 +13,17 @@
 // limitations under the License.
 
 //! Exonum blockchain explorer API.
+//!
+//! Queries with the optional `height` parameter are answered using the blockchain state
+//! right after the block at this height was committed, or the latest state if the parameter
+//! is not specified. Historical states are available only if the node runs in the archive mode.
 
-use actix::Arbiter;
+use actix::{Addr, Arbiter};
 use actix_web::{http, ws};
 use chrono::{DateTime, Utc};
 use futures::IntoFuture;
 
+use std::cmp;
 use std::ops::Range;
 use std::sync::{Arc, Mutex};
 use std::time::UNIX_EPOCH;
@@ -28,12 +33,12 @@ use crate::{
         backends::actix::{
             self as actix_backend, FutureResponse, HttpRequest, RawHandler, RequestHandler,
         },
-        websocket::{Server, Session},
+        websocket::{Server, Session, SubscriptionType},
         Error as ApiError, ServiceApiBackend, ServiceApiScope, ServiceApiState,
     },
     blockchain::{Block, SharedNodeState},
     crypto::Hash,
-    explorer::{self, BlockchainExplorer, TransactionInfo},
+    explorer::{self, BlockchainExplorer, EventInfo, TransactionInfo},
     helpers::Height,
     messages::{Message, Precommit, RawTransaction, Signed, SignedMessage},
 };
@@ -82,6 +87,8 @@ pub struct BlocksQuery {
     /// corresponding blocks precommits.
     #[serde(default)]
     pub add_blocks_time: bool,
+    /// Height of the queried blockchain state, see the [module docs](index.html).
+    pub height: Option<Height>,
 }
 
 /// Block query parameters.
@@ -117,15 +124,34 @@ pub struct TransactionResponse {
 pub struct TransactionQuery {
     /// The hash of the transaction to be searched.
     pub hash: Hash,
+    /// Height of the queried blockchain state, see the [module docs](index.html).
+    pub height: Option<Height>,
 }
 
 impl TransactionQuery {
-    /// Creates a new transaction query with the given height.
+    /// Creates a new transaction query with the given hash for the latest state.
     pub fn new(hash: Hash) -> Self {
-        Self { hash }
+        Self { hash, height: None }
     }
 }
 
+/// Events query parameters.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EventsQuery {
+    /// The hash of the transaction which has emitted the events. If specified,
+    /// the height range is ignored.
+    pub tx_hash: Option<Hash>,
+    /// The minimum height of the blocks with the events. The default value is zero.
+    pub from: Option<Height>,
+    /// The maximum height of the blocks with the events, inclusive. The default value
+    /// is the height of the latest block in the blockchain.
+    pub to: Option<Height>,
+    /// If specified, only the events with the given topic are returned.
+    pub topic: Option<String>,
+    /// Height of the queried blockchain state, see the [module docs](index.html).
+    pub height: Option<Height>,
+}
+
 /// Exonum blockchain explorer API.
 #[derive(Debug, Clone, Copy)]
 pub struct ExplorerApi;
@@ -137,7 +163,7 @@ impl ExplorerApi {
     ///
     /// [`BlocksQuery`]: struct.BlocksQuery.html
     pub fn blocks(state: &ServiceApiState, query: BlocksQuery) -> Result<BlocksRange, ApiError> {
-        let explorer = BlockchainExplorer::new(state.blockchain());
+        let explorer = explorer_at(state, query.height)?;
         if query.count > MAX_BLOCKS_PER_REQUEST {
             return Err(ApiError::BadRequest(format!(
                 "Max block count per request exceeded ({})",
@@ -165,10 +191,11 @@ impl ExplorerApi {
             .map(|block| block.into_header())
             .collect();
 
+        let first_height = explorer.first_block_height();
         let height = if blocks.len() < query.count {
-            Height(0)
+            first_height
         } else {
-            blocks.last().map_or(Height(0), |block| block.height())
+            blocks.last().map_or(first_height, |block| block.height())
         };
 
         Ok(BlocksRange {
@@ -184,7 +211,15 @@ impl ExplorerApi {
 
     /// Returns the content for a block at a specific height.
     pub fn block(state: &ServiceApiState, query: BlockQuery) -> Result<BlockInfo, ApiError> {
-        BlockchainExplorer::new(state.blockchain())
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        if explorer.is_block_pruned(query.height) {
+            return Err(pruned_error(&format!(
+                "Block for height: {} has been pruned",
+                query.height
+            )));
+        }
+
+        explorer
             .block(query.height)
             .map(From::from)
             .ok_or_else(|| {
@@ -197,14 +232,45 @@ impl ExplorerApi {
         state: &ServiceApiState,
         query: TransactionQuery,
     ) -> Result<TransactionInfo, ApiError> {
-        BlockchainExplorer::new(state.blockchain())
-            .transaction(&query.hash)
-            .ok_or_else(|| {
-                let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
-                debug!("{}", description);
-                ApiError::NotFound(description)
-            })
+        let explorer = explorer_at(state, query.height)?;
+        explorer.transaction(&query.hash).ok_or_else(|| {
+            if explorer.is_transaction_pruned(&query.hash) {
+                return pruned_error(&format!("Transaction {:?} has been pruned", query.hash));
+            }
+
+            let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
+            debug!("{}", description);
+            ApiError::NotFound(description)
+        })
     }
+
+    /// Returns the events emitted either by the specified transaction or by transactions
+    /// of the blocks in the specified range of heights, optionally filtered by the topic.
+    pub fn events(state: &ServiceApiState, query: EventsQuery) -> Result<Vec<EventInfo>, ApiError> {
+        let explorer = explorer_at(state, query.height)?;
+        let mut events = if let Some(tx_hash) = query.tx_hash {
+            explorer.transaction_events(&tx_hash)
+        } else {
+            let from = query.from.unwrap_or_else(Height::zero);
+            let height = explorer.height();
+            let to = cmp::min(query.to.unwrap_or(height), height);
+            if to.0.saturating_sub(from.0) >= MAX_BLOCKS_PER_REQUEST as u64 {
+                return Err(ApiError::BadRequest(format!(
+                    "Max block count per request exceeded ({})",
+                    MAX_BLOCKS_PER_REQUEST
+                )));
+            }
+            (from.0..=to.0)
+                .flat_map(|height| explorer.block_events(Height(height)))
+                .collect()
+        };
+
+        if let Some(ref topic) = query.topic {
+            events.retain(|info| info.event.topic() == topic);
+        }
+        Ok(events)
+    }
+
     /// Adds transaction into unconfirmed tx pool, and broadcast transaction to other nodes.
     pub fn add_transaction(
         state: &ServiceApiState,
@@ -232,7 +298,26 @@ impl ExplorerApi {
         service_api_state: ServiceApiState,
         shared_node_state: SharedNodeState,
     ) {
-        let server = Arc::new(Mutex::new(None));
+        Self::handle_ws(
+            name,
+            backend,
+            Arc::new(Mutex::new(None)),
+            service_api_state,
+            shared_node_state,
+            vec![SubscriptionType::Blocks],
+        );
+    }
+
+    /// Opens WebSocket sessions with the given initial subscriptions. Sessions opened
+    /// through the different endpoints share the same server.
+    fn handle_ws(
+        name: &'static str,
+        backend: &mut actix_backend::ApiBuilder,
+        server: Arc<Mutex<Option<Addr<Server>>>>,
+        service_api_state: ServiceApiState,
+        shared_node_state: SharedNodeState,
+        subscriptions: Vec<SubscriptionType>,
+    ) {
         let service_api_state = Arc::new(service_api_state);
 
         let index = move |req: HttpRequest| -> FutureResponse {
@@ -245,7 +330,8 @@ impl ExplorerApi {
                 shared_node_state.set_broadcast_server_address(address.to_owned().unwrap());
             }
 
-            Box::new(ws::start(&req, Session::new(address.to_owned().unwrap())).into_future())
+            let session = Session::new(address.to_owned().unwrap(), subscriptions.clone());
+            Box::new(ws::start(&req, session).into_future())
         };
 
         backend.raw_handler(RequestHandler {
@@ -261,16 +347,36 @@ impl ExplorerApi {
         service_api_state: ServiceApiState,
         shared_node_state: SharedNodeState,
     ) -> &mut ServiceApiScope {
-        Self::handle_subscribe(
+        let server = Arc::new(Mutex::new(None));
+        Self::handle_ws(
             "v1/blocks/subscribe",
             api_scope.web_backend(),
+            server.clone(),
+            service_api_state.clone(),
+            shared_node_state.clone(),
+            vec![SubscriptionType::Blocks],
+        );
+        Self::handle_ws(
+            "v1/events/subscribe",
+            api_scope.web_backend(),
+            server.clone(),
+            service_api_state.clone(),
+            shared_node_state.clone(),
+            vec![SubscriptionType::Events { topic: None }],
+        );
+        Self::handle_ws(
+            "v1/ws",
+            api_scope.web_backend(),
+            server,
             service_api_state,
             shared_node_state,
+            Vec::new(),
         );
         api_scope
             .endpoint("v1/blocks", Self::blocks)
             .endpoint("v1/block", Self::block)
             .endpoint("v1/transactions", Self::transaction_info)
+            .endpoint("v1/events", Self::events)
             .endpoint_mut("v1/transactions", Self::add_transaction)
     }
 }
@@ -286,6 +392,33 @@ impl<'a> From<explorer::BlockInfo<'a>> for BlockInfo {
     }
 }
 
+/// Creates an error for data removed from the storage by pruning.
+fn pruned_error(description: &str) -> ApiError {
+    let description = serde_json::to_string(&json!({
+        "type": "pruned",
+        "description": description,
+    }))
+    .unwrap();
+    debug!("{}", description);
+    ApiError::NotFound(description)
+}
+
+/// Creates an explorer for the latest state or, if the height is specified, for the state
+/// right after the block at this height was committed.
+fn explorer_at(
+    state: &ServiceApiState,
+    height: Option<Height>,
+) -> Result<BlockchainExplorer, ApiError> {
+    match height {
+        Some(height) => {
+            BlockchainExplorer::at_height(state.blockchain(), height).ok_or_else(|| {
+                ApiError::NotFound(format!("State at height {} is not available", height))
+            })
+        }
+        None => Ok(BlockchainExplorer::new(state.blockchain())),
+    }
+}
+
 fn median_precommits_time(precommits: &[Signed<Precommit>]) -> DateTime<Utc> {
     if precommits.is_empty() {
         UNIX_EPOCH.into()
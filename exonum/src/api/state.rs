@@ -12,8 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::{Error as ApiError, Result};
 use crate::blockchain::Blockchain;
 use crate::crypto::{PublicKey, SecretKey};
+use crate::helpers::Height;
 use crate::node::ApiSender;
 use crate::storage::Snapshot;
 
@@ -44,6 +46,18 @@ impl ServiceApiState {
         self.blockchain.snapshot()
     }
 
+    /// Creates a read-only snapshot of the blockchain state right after the block
+    /// at the given height was committed. Service endpoints may use this method to
+    /// serve queries with the `height` parameter.
+    ///
+    /// Returns `NotFound` error if the state at this height is not available,
+    /// e.g., if the node does not run in the archive mode.
+    pub fn snapshot_at(&self, height: Height) -> Result<Box<dyn Snapshot>> {
+        self.blockchain.snapshot_at(height).ok_or_else(|| {
+            ApiError::NotFound(format!("State at height {} is not available", height))
+        })
+    }
+
     /// Returns the public key of the current node.
     pub fn public_key(&self) -> &PublicKey {
         &self.blockchain.service_keypair.0
@@ -13,17 +13,162 @@
 // limitations under the License.
 
 //! WebSocket API.
+//!
+//! A client sends [`IncomingMessage`]s encoded as JSON to manage the subscriptions of its
+//! session and to submit transactions. Headers of the committed blocks are sent as plain JSON
+//! objects; notifications for other subscriptions, including the events emitted in
+//! the committed blocks, are sent as [`Notification`]s.
+//!
+//! [`IncomingMessage`]: enum.IncomingMessage.html
+//! [`Notification`]: enum.Notification.html
 
 use actix::*;
 use actix_web::ws;
 
 use rand::{rngs::ThreadRng, Rng};
 
-use std::{cell::RefCell, collections::HashMap, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use crate::api::ServiceApiState;
-use crate::blockchain::Schema;
+use crate::blockchain::{Event, Schema, TransactionResult, TxLocation};
 use crate::crypto::Hash;
+use crate::events::error::into_failure;
+use crate::explorer::{BlockchainExplorer, TxStatus};
+use crate::helpers::Height;
+use crate::messages::{
+    Message as ExonumMessage, ProtocolMessage, RawTransaction, Signed, SignedMessage,
+};
+use crate::storage::Snapshot;
+
+/// Type of the notifications a WebSocket session is subscribed to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubscriptionType {
+    /// Headers of the committed blocks.
+    Blocks,
+    /// Events emitted by the transactions of the committed blocks. An unspecified topic
+    /// matches all events.
+    Events {
+        /// Topic of the events.
+        #[serde(default)]
+        topic: Option<String>,
+    },
+    /// Commit of the transaction with the given hash. The subscription is removed
+    /// after the notification is sent.
+    Transaction {
+        /// Hash of the transaction.
+        hash: Hash,
+    },
+    /// Commits of the transactions with the given service and message identifiers.
+    /// An unspecified identifier matches all transactions.
+    Transactions {
+        /// Identifier of the service.
+        #[serde(default)]
+        service_id: Option<u16>,
+        /// Identifier of the transaction within the service.
+        #[serde(default)]
+        message_id: Option<u16>,
+    },
+    /// Admission of transactions into the pool of the node.
+    Pool,
+}
+
+impl SubscriptionType {
+    fn matches(&self, tx_hash: &Hash, raw: &RawTransaction) -> bool {
+        match *self {
+            SubscriptionType::Blocks | SubscriptionType::Events { .. } | SubscriptionType::Pool => {
+                false
+            }
+            SubscriptionType::Transaction { ref hash } => hash == tx_hash,
+            SubscriptionType::Transactions {
+                service_id,
+                message_id,
+            } => {
+                service_id.map_or(true, |id| id == raw.service_id())
+                    && message_id.map_or(true, |id| id == raw.transaction_id())
+            }
+        }
+    }
+}
+
+/// Message sent by a client over the WebSocket session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomingMessage {
+    /// Adds the subscription to the session.
+    Subscribe(SubscriptionType),
+    /// Removes the subscription from the session.
+    Unsubscribe(SubscriptionType),
+    /// Submits the signed transaction to the node. The session is notified when
+    /// the transaction is added into the pool and when it is committed, or when
+    /// the transaction is rejected by the node.
+    Transaction {
+        /// The hex value of the signed transaction.
+        tx_body: String,
+    },
+}
+
+/// Notification sent to a client over the WebSocket session.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    /// The transaction has been committed.
+    TransactionCommitted {
+        /// Hash of the transaction.
+        tx_hash: Hash,
+        /// Identifier of the service.
+        service_id: u16,
+        /// Identifier of the transaction within the service.
+        message_id: u16,
+        /// Location of the transaction in the blockchain.
+        location: TxLocation,
+        /// Result of the transaction execution.
+        #[serde(with = "TxStatus")]
+        status: TransactionResult,
+    },
+    /// The transaction has been added into the pool of the node.
+    TransactionInPool {
+        /// Hash of the transaction.
+        tx_hash: Hash,
+        /// Identifier of the service.
+        service_id: u16,
+        /// Identifier of the transaction within the service.
+        message_id: u16,
+    },
+    /// The event has been emitted by a transaction of the committed block.
+    Event {
+        /// Height of the block.
+        height: Height,
+        /// Position of the event in the list of events of the block.
+        position: u64,
+        /// The event itself.
+        event: Event,
+    },
+    /// The transaction submitted by the session has been rejected by the node, e.g.,
+    /// because it belongs to an inactive service, has an outdated nonce or
+    /// the transaction pool is full.
+    TransactionRejected {
+        /// Hash of the transaction.
+        tx_hash: Hash,
+        /// Description of the reason.
+        description: String,
+    },
+    /// The message sent by the client cannot be processed.
+    Error {
+        /// Description of the error.
+        description: String,
+    },
+}
+
+impl Notification {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
 
 /// WebSocket message for communication between clients(`Session`) and server(`Server`).
 #[derive(Message, Debug)]
@@ -33,6 +178,7 @@ pub(crate) struct Message(pub String);
 #[rtype(usize)]
 pub(crate) struct Subscribe {
     pub address: Recipient<Message>,
+    pub subscriptions: Vec<SubscriptionType>,
 }
 
 #[derive(Message)]
@@ -40,13 +186,54 @@ pub(crate) struct Unsubscribe {
     pub id: usize,
 }
 
+#[derive(Message)]
+pub(crate) struct UpdateSubscriptions {
+    pub id: usize,
+    pub subscription: SubscriptionType,
+    pub remove: bool,
+}
+
+#[derive(Message)]
+pub(crate) struct WatchTransaction {
+    pub id: usize,
+    pub tx_hash: Hash,
+}
+
 #[derive(Message)]
 pub(crate) struct Broadcast {
     pub block_hash: Hash,
 }
 
+#[derive(Message)]
+pub(crate) struct TransactionAdded {
+    pub tx_hash: Hash,
+}
+
+#[derive(Message)]
+pub(crate) struct TransactionRejected {
+    pub tx_hash: Hash,
+    pub description: String,
+}
+
+struct Subscriber {
+    address: Recipient<Message>,
+    subscriptions: HashSet<SubscriptionType>,
+    // Transactions submitted by the session which are not yet added into the pool.
+    submitted_transactions: HashSet<Hash>,
+}
+
+impl Subscriber {
+    fn is_subscribed(&self, subscription: &SubscriptionType) -> bool {
+        self.subscriptions.contains(subscription)
+    }
+
+    fn send(&self, message: String) {
+        let _ = self.address.do_send(Message(message));
+    }
+}
+
 pub(crate) struct Server {
-    pub subscribers: HashMap<usize, Recipient<Message>>,
+    subscribers: HashMap<usize, Subscriber>,
     service_api_state: Arc<ServiceApiState>,
     rng: RefCell<ThreadRng>,
 }
@@ -59,6 +246,170 @@ impl Server {
             rng: RefCell::new(rand::thread_rng()),
         }
     }
+
+    fn broadcast_block_header(&self, block_hash: &Hash) {
+        let snapshot = self.service_api_state.snapshot();
+        let schema = Schema::new(snapshot);
+        let block_header = schema.blocks().get(block_hash);
+        let block_header_json = serde_json::to_value(block_header).unwrap().to_string();
+        for subscriber in self.subscribers.values() {
+            if subscriber.is_subscribed(&SubscriptionType::Blocks) {
+                subscriber.send(block_header_json.clone());
+            }
+        }
+    }
+
+    fn broadcast_block_events(&self, block_hash: &Hash) {
+        let snapshot = self.service_api_state.snapshot();
+        let height = match Schema::new(snapshot).blocks().get(block_hash) {
+            Some(block) => block.height(),
+            None => return,
+        };
+
+        let explorer = BlockchainExplorer::new(self.service_api_state.blockchain());
+        for info in explorer.block_events(height) {
+            let subscriptions = [
+                SubscriptionType::Events { topic: None },
+                SubscriptionType::Events {
+                    topic: Some(info.event.topic().to_owned()),
+                },
+            ];
+            let notification = Notification::Event {
+                height: info.height,
+                position: info.position,
+                event: info.event,
+            }
+            .to_json();
+            for subscriber in self.subscribers.values() {
+                if subscriptions
+                    .iter()
+                    .any(|subscription| subscriber.is_subscribed(subscription))
+                {
+                    subscriber.send(notification.clone());
+                }
+            }
+        }
+    }
+
+    fn broadcast_committed_transactions(&mut self, block_hash: &Hash) {
+        let snapshot = self.service_api_state.snapshot();
+        let schema = Schema::new(snapshot);
+        let height = match schema.blocks().get(block_hash) {
+            Some(block) => block.height(),
+            None => return,
+        };
+
+        for tx_hash in schema.block_transactions(height).iter() {
+            let tx = match schema.transactions().get(&tx_hash) {
+                Some(tx) => tx,
+                None => continue,
+            };
+            let mut notification = None;
+            for subscriber in self.subscribers.values_mut() {
+                let matched = subscriber
+                    .subscriptions
+                    .iter()
+                    .filter(|subscription| subscription.matches(&tx_hash, tx.payload()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if matched.is_empty() {
+                    continue;
+                }
+                // Subscriptions to a single transaction are fulfilled.
+                subscriber
+                    .subscriptions
+                    .remove(&SubscriptionType::Transaction { hash: tx_hash });
+
+                subscriber.submitted_transactions.remove(&tx_hash);
+
+                let notification = notification
+                    .get_or_insert_with(|| committed_notification(&schema, tx_hash, &tx));
+                subscriber.send(notification.clone());
+            }
+        }
+    }
+
+    /// Subscribes the session to the notifications about the transaction submitted by it.
+    fn watch_transaction(&mut self, id: usize, tx_hash: Hash) {
+        let subscriber = match self.subscribers.get_mut(&id) {
+            Some(subscriber) => subscriber,
+            None => return,
+        };
+
+        // The transaction may be already known to the node.
+        let snapshot = self.service_api_state.snapshot();
+        let schema = Schema::new(snapshot);
+        if let Some(tx) = schema.transactions().get(&tx_hash) {
+            if schema.transactions_locations().contains(&tx_hash) {
+                subscriber.send(committed_notification(&schema, tx_hash, &tx));
+                return;
+            }
+            if schema.transactions_pool().contains(&tx_hash) {
+                subscriber.send(pool_notification(tx_hash, &tx));
+                subscriber
+                    .subscriptions
+                    .insert(SubscriptionType::Transaction { hash: tx_hash });
+                return;
+            }
+        }
+
+        subscriber.submitted_transactions.insert(tx_hash);
+        subscriber
+            .subscriptions
+            .insert(SubscriptionType::Transaction { hash: tx_hash });
+    }
+
+    /// Notifies the sessions which have submitted the transaction that it has been rejected
+    /// and removes the corresponding subscriptions.
+    fn reject_transaction(&mut self, tx_hash: Hash, description: String) {
+        let notification = Notification::TransactionRejected {
+            tx_hash,
+            description,
+        }
+        .to_json();
+        for subscriber in self.subscribers.values_mut() {
+            if subscriber.submitted_transactions.remove(&tx_hash) {
+                subscriber
+                    .subscriptions
+                    .remove(&SubscriptionType::Transaction { hash: tx_hash });
+                subscriber.send(notification.clone());
+            }
+        }
+    }
+
+    fn broadcast_pool_transaction(&mut self, tx_hash: &Hash, tx: &Signed<RawTransaction>) {
+        let notification = pool_notification(*tx_hash, tx);
+        for subscriber in self.subscribers.values_mut() {
+            let submitted = subscriber.submitted_transactions.remove(tx_hash);
+            if submitted || subscriber.is_subscribed(&SubscriptionType::Pool) {
+                subscriber.send(notification.clone());
+            }
+        }
+    }
+}
+
+fn committed_notification<T: AsRef<dyn Snapshot>>(
+    schema: &Schema<T>,
+    tx_hash: Hash,
+    tx: &Signed<RawTransaction>,
+) -> String {
+    Notification::TransactionCommitted {
+        tx_hash,
+        service_id: tx.payload().service_id(),
+        message_id: tx.payload().transaction_id(),
+        location: schema.transactions_locations().get(&tx_hash).unwrap(),
+        status: schema.transaction_results().get(&tx_hash).unwrap(),
+    }
+    .to_json()
+}
+
+fn pool_notification(tx_hash: Hash, tx: &Signed<RawTransaction>) -> String {
+    Notification::TransactionInPool {
+        tx_hash,
+        service_id: tx.payload().service_id(),
+        message_id: tx.payload().transaction_id(),
+    }
+    .to_json()
 }
 
 impl Actor for Server {
@@ -68,9 +419,21 @@ impl Actor for Server {
 impl Handler<Subscribe> for Server {
     type Result = usize;
 
-    fn handle(&mut self, Subscribe { address }: Subscribe, _ctx: &mut Self::Context) -> usize {
+    fn handle(
+        &mut self,
+        Subscribe {
+            address,
+            subscriptions,
+        }: Subscribe,
+        _ctx: &mut Self::Context,
+    ) -> usize {
         let id = self.rng.borrow_mut().gen::<usize>();
-        self.subscribers.insert(id, address);
+        let subscriber = Subscriber {
+            address,
+            subscriptions: subscriptions.into_iter().collect(),
+            submitted_transactions: HashSet::new(),
+        };
+        self.subscribers.insert(id, subscriber);
 
         id
     }
@@ -84,16 +447,72 @@ impl Handler<Unsubscribe> for Server {
     }
 }
 
+impl Handler<UpdateSubscriptions> for Server {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        UpdateSubscriptions {
+            id,
+            subscription,
+            remove,
+        }: UpdateSubscriptions,
+        _ctx: &mut Self::Context,
+    ) {
+        if let Some(subscriber) = self.subscribers.get_mut(&id) {
+            if remove {
+                subscriber.subscriptions.remove(&subscription);
+            } else {
+                subscriber.subscriptions.insert(subscription);
+            }
+        }
+    }
+}
+
+impl Handler<WatchTransaction> for Server {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        WatchTransaction { id, tx_hash }: WatchTransaction,
+        _ctx: &mut Self::Context,
+    ) {
+        self.watch_transaction(id, tx_hash);
+    }
+}
+
 impl Handler<Broadcast> for Server {
     type Result = ();
 
     fn handle(&mut self, Broadcast { block_hash }: Broadcast, _ctx: &mut Self::Context) {
+        self.broadcast_block_header(&block_hash);
+        self.broadcast_block_events(&block_hash);
+        self.broadcast_committed_transactions(&block_hash);
+    }
+}
+
+impl Handler<TransactionRejected> for Server {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        TransactionRejected {
+            tx_hash,
+            description,
+        }: TransactionRejected,
+        _ctx: &mut Self::Context,
+    ) {
+        self.reject_transaction(tx_hash, description);
+    }
+}
+
+impl Handler<TransactionAdded> for Server {
+    type Result = ();
+
+    fn handle(&mut self, TransactionAdded { tx_hash }: TransactionAdded, _ctx: &mut Self::Context) {
         let snapshot = self.service_api_state.snapshot();
-        let schema = Schema::new(snapshot);
-        let block_header = schema.blocks().get(&block_hash);
-        let block_header_json = serde_json::to_value(block_header).unwrap().to_string();
-        for address in self.subscribers.values() {
-            let _ = address.do_send(Message(block_header_json.clone()));
+        if let Some(tx) = Schema::new(snapshot).transactions().get(&tx_hash) {
+            self.broadcast_pool_transaction(&tx_hash, &tx);
         }
     }
 }
@@ -101,15 +520,79 @@ impl Handler<Broadcast> for Server {
 pub(crate) struct Session {
     pub id: usize,
     pub server_address: Addr<Server>,
+    subscriptions: Vec<SubscriptionType>,
 }
 
 impl Session {
-    pub fn new(server_address: Addr<Server>) -> Self {
+    pub fn new(server_address: Addr<Server>, subscriptions: Vec<SubscriptionType>) -> Self {
         Self {
             id: 0,
             server_address,
+            subscriptions,
         }
     }
+
+    fn handle_text(&mut self, text: &str, ctx: &mut <Self as Actor>::Context) {
+        let result = serde_json::from_str::<IncomingMessage>(text)
+            .map_err(|e| format_err!("Cannot parse message: {}", e))
+            .and_then(|message| match message {
+                IncomingMessage::Subscribe(subscription) => {
+                    self.update_subscriptions(subscription, false);
+                    Ok(())
+                }
+                IncomingMessage::Unsubscribe(subscription) => {
+                    self.update_subscriptions(subscription, true);
+                    Ok(())
+                }
+                IncomingMessage::Transaction { tx_body } => {
+                    self.submit_transaction(ctx.state(), &tx_body)
+                }
+            });
+
+        if let Err(e) = result {
+            let notification = Notification::Error {
+                description: e.to_string(),
+            };
+            ctx.text(notification.to_json());
+        }
+    }
+
+    fn update_subscriptions(&self, subscription: SubscriptionType, remove: bool) {
+        self.server_address.do_send(UpdateSubscriptions {
+            id: self.id,
+            subscription,
+            remove,
+        });
+    }
+
+    /// Validates the transaction and broadcasts it to the node. The server is asked
+    /// to watch the transaction before broadcasting, so that the notification about
+    /// its admission into the pool or its rejection cannot be missed. A failure to
+    /// broadcast the transaction is reported as its rejection.
+    fn submit_transaction(
+        &self,
+        state: &ServiceApiState,
+        tx_body: &str,
+    ) -> Result<(), failure::Error> {
+        let buf: Vec<u8> = hex::decode(tx_body).map_err(into_failure)?;
+        let signed = SignedMessage::from_raw_buffer(buf)?;
+        let tx_hash = signed.hash();
+        let tx = RawTransaction::try_from(ExonumMessage::deserialize(signed)?)
+            .map_err(|_| format_err!("Couldn't deserialize transaction message."))?;
+        state.blockchain().tx_from_raw(tx.payload().clone())?;
+
+        self.server_address.do_send(WatchTransaction {
+            id: self.id,
+            tx_hash,
+        });
+        if let Err(e) = state.sender().broadcast_transaction(tx) {
+            self.server_address.do_send(TransactionRejected {
+                tx_hash,
+                description: e.to_string(),
+            });
+        }
+        Ok(())
+    }
 }
 
 impl Actor for Session {
@@ -117,8 +600,12 @@ impl Actor for Session {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         let address: Recipient<_> = ctx.address().recipient();
+        let subscriptions = self.subscriptions.clone();
         self.server_address
-            .send(Subscribe { address })
+            .send(Subscribe {
+                address,
+                subscriptions,
+            })
             .into_actor(self)
             .then(|response, actor, context| {
                 match response {
@@ -150,6 +637,7 @@ impl StreamHandler<ws::Message, ws::ProtocolError> for Session {
     fn handle(&mut self, msg: ws::Message, ctx: &mut Self::Context) {
         match msg {
             ws::Message::Ping(msg) => ctx.pong(&msg),
+            ws::Message::Text(text) => self.handle_text(&text, ctx),
             ws::Message::Close(_) => {
                 ctx.stop();
             }
@@ -157,3 +645,404 @@ impl StreamHandler<ws::Message, ws::ProtocolError> for Session {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::{future, sync::mpsc};
+
+    use std::{iter, mem};
+
+    use super::*;
+    use crate::blockchain::{Blockchain, Service};
+    use crate::crypto::gen_keypair;
+    use crate::helpers::{self, ValidatorId};
+    use crate::messages::ServiceTransaction;
+    use crate::node::ApiSender;
+    use crate::sandbox::timestamping::{
+        TimestampingService, TimestampingTxGenerator, DATA_SIZE, TIMESTAMPING_SERVICE,
+    };
+    use crate::storage::MemoryDB;
+
+    /// Collects the messages sent to a subscriber.
+    #[derive(Default)]
+    struct Collector(Vec<String>);
+
+    impl Actor for Collector {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Message> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, msg: Message, _ctx: &mut Self::Context) {
+            self.0.push(msg.0);
+        }
+    }
+
+    struct TakeMessages;
+
+    impl actix::Message for TakeMessages {
+        type Result = Vec<String>;
+    }
+
+    impl Handler<TakeMessages> for Collector {
+        type Result = MessageResult<TakeMessages>;
+
+        fn handle(&mut self, _msg: TakeMessages, _ctx: &mut Self::Context) -> Self::Result {
+            MessageResult(mem::replace(&mut self.0, Vec::new()))
+        }
+    }
+
+    struct TestServer {
+        system: SystemRunner,
+        blockchain: Blockchain,
+        server: Server,
+    }
+
+    impl TestServer {
+        fn new() -> Self {
+            let service_keypair = gen_keypair();
+            let api_channel = mpsc::channel(1);
+            let mut blockchain = Blockchain::new(
+                MemoryDB::new(),
+                vec![Box::new(TimestampingService::new()) as Box<dyn Service>],
+                service_keypair.0,
+                service_keypair.1,
+                ApiSender::new(api_channel.0),
+            );
+            let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+            blockchain.initialize(genesis).unwrap();
+
+            let service_api_state = Arc::new(ServiceApiState::new(blockchain.clone()));
+            Self {
+                system: System::new("websocket-test"),
+                blockchain,
+                server: Server::new(service_api_state),
+            }
+        }
+
+        fn subscribe(
+            &mut self,
+            id: usize,
+            subscriptions: Vec<SubscriptionType>,
+        ) -> Addr<Collector> {
+            let collector = self
+                .system
+                .block_on(future::lazy(|| Ok::<_, ()>(Collector::default().start())))
+                .unwrap();
+            self.server.subscribers.insert(
+                id,
+                Subscriber {
+                    address: collector.clone().recipient(),
+                    subscriptions: subscriptions.into_iter().collect(),
+                    submitted_transactions: HashSet::new(),
+                },
+            );
+            collector
+        }
+
+        fn received(&mut self, collector: &Addr<Collector>) -> Vec<String> {
+            self.system.block_on(collector.send(TakeMessages)).unwrap()
+        }
+
+        /// Commits a block with the given transactions and broadcasts it to the subscribers.
+        fn commit_block(&mut self, txs: &[Signed<RawTransaction>]) {
+            let mut fork = self.blockchain.fork();
+            {
+                let mut schema = Schema::new(&mut fork);
+                for tx in txs {
+                    schema.add_transaction_into_pool(tx.clone());
+                }
+            }
+            self.blockchain.merge(fork.into_patch()).unwrap();
+
+            let height = Schema::new(&self.blockchain.snapshot()).height().next();
+            let tx_hashes = txs.iter().map(Signed::hash).collect::<Vec<_>>();
+            let (block_hash, patch) =
+                self.blockchain
+                    .create_patch(ValidatorId::zero(), height, &tx_hashes);
+            self.blockchain
+                .commit(&patch, block_hash, iter::empty())
+                .unwrap();
+
+            self.server.broadcast_block_header(&block_hash);
+            self.server.broadcast_block_events(&block_hash);
+            self.server.broadcast_committed_transactions(&block_hash);
+        }
+    }
+
+    fn notification(message: &str) -> Notification {
+        serde_json::from_str(message).unwrap()
+    }
+
+    fn raw_transaction(service_id: u16, message_id: u16) -> RawTransaction {
+        RawTransaction::new(
+            service_id,
+            ServiceTransaction::from_raw_unchecked(message_id, vec![1, 2, 3]),
+        )
+    }
+
+    #[test]
+    fn subscription_matches() {
+        let raw = raw_transaction(1, 2);
+        let tx_hash = crate::crypto::hash(&[1, 2, 3]);
+        let other_hash = crate::crypto::hash(&[4, 5, 6]);
+        let transactions = |service_id, message_id| SubscriptionType::Transactions {
+            service_id,
+            message_id,
+        };
+
+        assert!(SubscriptionType::Transaction { hash: tx_hash }.matches(&tx_hash, &raw));
+        assert!(!SubscriptionType::Transaction { hash: other_hash }.matches(&tx_hash, &raw));
+        assert!(transactions(None, None).matches(&tx_hash, &raw));
+        assert!(transactions(Some(1), None).matches(&tx_hash, &raw));
+        assert!(transactions(Some(1), Some(2)).matches(&tx_hash, &raw));
+        assert!(transactions(None, Some(2)).matches(&tx_hash, &raw));
+        assert!(!transactions(Some(2), None).matches(&tx_hash, &raw));
+        assert!(!transactions(Some(1), Some(3)).matches(&tx_hash, &raw));
+        assert!(!transactions(None, Some(3)).matches(&tx_hash, &raw));
+        // Subscriptions which are not related to transaction commits.
+        assert!(!SubscriptionType::Blocks.matches(&tx_hash, &raw));
+        assert!(!SubscriptionType::Events { topic: None }.matches(&tx_hash, &raw));
+        assert!(!SubscriptionType::Pool.matches(&tx_hash, &raw));
+    }
+
+    #[test]
+    fn commit_notifications_reach_matching_sessions() {
+        let mut server = TestServer::new();
+        let txs = TimestampingTxGenerator::new(DATA_SIZE)
+            .take(2)
+            .collect::<Vec<_>>();
+
+        let single = server.subscribe(
+            1,
+            vec![SubscriptionType::Transaction {
+                hash: txs[0].hash(),
+            }],
+        );
+        let service = server.subscribe(
+            2,
+            vec![SubscriptionType::Transactions {
+                service_id: Some(TIMESTAMPING_SERVICE),
+                message_id: None,
+            }],
+        );
+        let other_service = server.subscribe(
+            3,
+            vec![SubscriptionType::Transactions {
+                service_id: Some(TIMESTAMPING_SERVICE + 1),
+                message_id: None,
+            }],
+        );
+        let blocks = server.subscribe(4, vec![SubscriptionType::Blocks]);
+        let pool = server.subscribe(5, vec![SubscriptionType::Pool]);
+
+        server.commit_block(&txs);
+
+        let committed_hashes = |messages: Vec<String>| {
+            messages
+                .iter()
+                .map(|message| match notification(message) {
+                    Notification::TransactionCommitted {
+                        tx_hash, status, ..
+                    } => {
+                        assert!(status.0.is_ok());
+                        tx_hash
+                    }
+                    other => panic!("Unexpected notification: {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            committed_hashes(server.received(&single)),
+            vec![txs[0].hash()]
+        );
+        assert_eq!(
+            committed_hashes(server.received(&service)),
+            vec![txs[0].hash(), txs[1].hash()]
+        );
+        assert!(server.received(&other_service).is_empty());
+        assert!(server.received(&pool).is_empty());
+
+        let headers = server.received(&blocks);
+        assert_eq!(headers.len(), 1);
+        let header: serde_json::Value = serde_json::from_str(&headers[0]).unwrap();
+        assert_eq!(header["height"], json!(1));
+
+        // The subscription to a single transaction is removed after the notification.
+        assert!(server.server.subscribers[&1].subscriptions.is_empty());
+        server.commit_block(&[]);
+        assert!(server.received(&single).is_empty());
+        assert!(server.received(&service).is_empty());
+        assert_eq!(server.received(&blocks).len(), 1);
+    }
+
+    #[test]
+    fn pool_notifications_reach_matching_sessions() {
+        let mut server = TestServer::new();
+        let tx = TimestampingTxGenerator::new(DATA_SIZE).next().unwrap();
+        let tx_hash = tx.hash();
+
+        let pool = server.subscribe(1, vec![SubscriptionType::Pool]);
+        let submitter = server.subscribe(2, Vec::new());
+        server
+            .server
+            .subscribers
+            .get_mut(&2)
+            .unwrap()
+            .submitted_transactions
+            .insert(tx_hash);
+        let blocks = server.subscribe(3, vec![SubscriptionType::Blocks]);
+        let transactions = server.subscribe(
+            4,
+            vec![SubscriptionType::Transactions {
+                service_id: None,
+                message_id: None,
+            }],
+        );
+
+        server.server.broadcast_pool_transaction(&tx_hash, &tx);
+
+        for collector in &[pool, submitter] {
+            let messages = server.received(collector);
+            assert_eq!(messages.len(), 1);
+            match notification(&messages[0]) {
+                Notification::TransactionInPool {
+                    tx_hash: hash,
+                    service_id,
+                    message_id,
+                } => {
+                    assert_eq!(hash, tx_hash);
+                    assert_eq!(service_id, TIMESTAMPING_SERVICE);
+                    assert_eq!(message_id, 0);
+                }
+                other => panic!("Unexpected notification: {:?}", other),
+            }
+        }
+        assert!(server.received(&blocks).is_empty());
+        assert!(server.received(&transactions).is_empty());
+        assert!(server.server.subscribers[&2]
+            .submitted_transactions
+            .is_empty());
+    }
+
+    #[test]
+    fn rejected_transaction_is_reported_to_submitter() {
+        let mut server = TestServer::new();
+        let tx = TimestampingTxGenerator::new(DATA_SIZE).next().unwrap();
+        let tx_hash = tx.hash();
+
+        let submitter = server.subscribe(1, Vec::new());
+        let pool = server.subscribe(2, vec![SubscriptionType::Pool]);
+        server.server.watch_transaction(1, tx_hash);
+        server
+            .server
+            .reject_transaction(tx_hash, "Transaction pool is full".to_owned());
+
+        let messages = server.received(&submitter);
+        assert_eq!(messages.len(), 1);
+        match notification(&messages[0]) {
+            Notification::TransactionRejected {
+                tx_hash: hash,
+                description,
+            } => {
+                assert_eq!(hash, tx_hash);
+                assert_eq!(description, "Transaction pool is full");
+            }
+            other => panic!("Unexpected notification: {:?}", other),
+        }
+        assert!(server.received(&pool).is_empty());
+
+        // The watch of the rejected transaction is removed.
+        let subscriber = &server.server.subscribers[&1];
+        assert!(subscriber.subscriptions.is_empty());
+        assert!(subscriber.submitted_transactions.is_empty());
+        server.commit_block(&[tx]);
+        assert!(server.received(&submitter).is_empty());
+    }
+
+    #[test]
+    fn submitted_transaction_is_tracked_until_commit() {
+        let mut server = TestServer::new();
+        let tx = TimestampingTxGenerator::new(DATA_SIZE).next().unwrap();
+        let tx_hash = tx.hash();
+
+        let submitter = server.subscribe(1, Vec::new());
+        server.server.watch_transaction(1, tx_hash);
+        server.server.broadcast_pool_transaction(&tx_hash, &tx);
+        // A later rejection of the same transaction, e.g., submitted once again by another
+        // session, does not affect the session, since the transaction is already in the pool.
+        server
+            .server
+            .reject_transaction(tx_hash, "Already processed".to_owned());
+        server.commit_block(&[tx]);
+
+        let messages = server.received(&submitter);
+        assert_eq!(messages.len(), 2);
+        match notification(&messages[0]) {
+            Notification::TransactionInPool { tx_hash: hash, .. } => assert_eq!(hash, tx_hash),
+            other => panic!("Unexpected notification: {:?}", other),
+        }
+        match notification(&messages[1]) {
+            Notification::TransactionCommitted { tx_hash: hash, .. } => assert_eq!(hash, tx_hash),
+            other => panic!("Unexpected notification: {:?}", other),
+        }
+        assert!(server.server.subscribers[&1].subscriptions.is_empty());
+
+        // The committed transaction is reported immediately.
+        server.server.watch_transaction(1, tx_hash);
+        let messages = server.received(&submitter);
+        assert_eq!(messages.len(), 1);
+        match notification(&messages[0]) {
+            Notification::TransactionCommitted { tx_hash: hash, .. } => assert_eq!(hash, tx_hash),
+            other => panic!("Unexpected notification: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn incoming_message_format() {
+        let hash = crate::crypto::hash(&[1, 2, 3]);
+        let messages = vec![
+            (
+                json!({ "subscribe": { "type": "blocks" } }),
+                IncomingMessage::Subscribe(SubscriptionType::Blocks),
+            ),
+            (
+                json!({ "subscribe": { "type": "events" } }),
+                IncomingMessage::Subscribe(SubscriptionType::Events { topic: None }),
+            ),
+            (
+                json!({ "subscribe": { "type": "events", "topic": "transfer" } }),
+                IncomingMessage::Subscribe(SubscriptionType::Events {
+                    topic: Some("transfer".to_owned()),
+                }),
+            ),
+            (
+                json!({ "subscribe": { "type": "transaction", "hash": hash } }),
+                IncomingMessage::Subscribe(SubscriptionType::Transaction { hash }),
+            ),
+            (
+                json!({ "unsubscribe": { "type": "transactions", "service_id": 1 } }),
+                IncomingMessage::Unsubscribe(SubscriptionType::Transactions {
+                    service_id: Some(1),
+                    message_id: None,
+                }),
+            ),
+            (
+                json!({ "unsubscribe": { "type": "pool" } }),
+                IncomingMessage::Unsubscribe(SubscriptionType::Pool),
+            ),
+            (
+                json!({ "transaction": { "tx_body": "0a0b" } }),
+                IncomingMessage::Transaction {
+                    tx_body: "0a0b".to_owned(),
+                },
+            ),
+        ];
+
+        for (json, expected) in messages {
+            let message: IncomingMessage = serde_json::from_value(json).unwrap();
+            assert_eq!(message, expected);
+        }
+    }
+}
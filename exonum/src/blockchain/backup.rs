@@ -0,0 +1,141 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backups of the node database based on `RocksDB` checkpoints.
+//!
+//! A backup is a directory containing the database checkpoint in the `db` subdirectory
+//! and the `backup.json` file with the information about the last block in the checkpoint.
+
+use std::{fs, path::Path};
+
+use super::Schema;
+use crate::crypto::Hash;
+use crate::helpers::Height;
+use crate::storage::{Database, DbOptions, RocksDB};
+
+const BACKUP_DB_DIR: &str = "db";
+const BACKUP_INFO_FILE: &str = "backup.json";
+
+/// Information about the latest block stored in a backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Height of the latest block in the backup.
+    pub height: Height,
+    /// Hash of the latest block in the backup.
+    pub block_hash: Hash,
+}
+
+impl BackupInfo {
+    // Reads information about the latest block from the database.
+    fn from_database(db: &dyn Database) -> Result<Self, failure::Error> {
+        let snapshot = db.snapshot();
+        let schema = Schema::new(&snapshot);
+        let block_hash = schema
+            .block_hashes_by_height()
+            .last()
+            .ok_or_else(|| format_err!("Blockchain is not initialized"))?;
+        Ok(Self {
+            height: schema.height(),
+            block_hash,
+        })
+    }
+}
+
+/// Creates a backup of the database in the given directory, which should not exist.
+///
+/// The directory is removed if the backup cannot be created.
+pub(crate) fn create_backup(db: &dyn Database, path: &Path) -> Result<BackupInfo, failure::Error> {
+    ensure!(
+        !path.exists(),
+        "Backup directory {} already exists",
+        path.display()
+    );
+    fs::create_dir_all(path)?;
+    write_backup(db, path).map_err(|e| remove_dir_on_error(path, e))
+}
+
+fn write_backup(db: &dyn Database, path: &Path) -> Result<BackupInfo, failure::Error> {
+    let db_path = path.join(BACKUP_DB_DIR);
+    db.create_checkpoint(&db_path)?;
+
+    // The checkpoint is opened to find out the exact height it corresponds to,
+    // as blocks may be committed while the checkpoint is being created.
+    let info = {
+        let checkpoint = RocksDB::open(&db_path, &DbOptions::default())?;
+        BackupInfo::from_database(&checkpoint)?
+    };
+    fs::write(
+        path.join(BACKUP_INFO_FILE),
+        serde_json::to_string_pretty(&info)?,
+    )?;
+    Ok(info)
+}
+
+/// Restores the database from the backup into the given directory, which should not exist.
+///
+/// The backup is copied into the directory first, so that the backup itself is not modified.
+/// The latest block of the copy is checked against the information recorded during
+/// the backup creation and verified with [`Schema::verify_block`]. If the verification
+/// fails, the directory is removed.
+///
+/// [`Schema::verify_block`]: struct.Schema.html#method.verify_block
+pub(crate) fn restore_backup(path: &Path, db_path: &Path) -> Result<BackupInfo, failure::Error> {
+    ensure!(
+        !db_path.exists(),
+        "Database directory {} already exists",
+        db_path.display()
+    );
+    let info: BackupInfo = serde_json::from_slice(&fs::read(path.join(BACKUP_INFO_FILE))?)?;
+
+    fs::create_dir_all(db_path)?;
+    copy_and_verify(&path.join(BACKUP_DB_DIR), db_path, &info)
+        .map_err(|e| remove_dir_on_error(db_path, e))?;
+    Ok(info)
+}
+
+fn copy_and_verify(
+    checkpoint_path: &Path,
+    db_path: &Path,
+    info: &BackupInfo,
+) -> Result<(), failure::Error> {
+    for entry in fs::read_dir(checkpoint_path)? {
+        let entry = entry?;
+        fs::copy(entry.path(), db_path.join(entry.file_name()))?;
+    }
+
+    let options = DbOptions {
+        create_if_missing: false,
+        ..DbOptions::default()
+    };
+    let db = RocksDB::open(db_path, &options)?;
+    let actual_info = BackupInfo::from_database(&db)?;
+    ensure!(
+        actual_info == *info,
+        "Latest block in the backup {:?} does not match the recorded one {:?}",
+        actual_info,
+        info
+    );
+    let snapshot = db.snapshot();
+    Schema::new(&snapshot)
+        .verify_block(info.height)
+        .map_err(|e| format_err!("Latest block in the backup is not verified: {}", e))
+}
+
+// Removes the partially written directory, keeping the original error.
+fn remove_dir_on_error(path: &Path, error: failure::Error) -> failure::Error {
+    if let Err(e) = fs::remove_dir_all(path) {
+        warn!("Cannot remove directory {}: {}", path.display(), e);
+    }
+    error
+}
@@ -12,9 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::crypto::Hash;
+use chrono::{DateTime, Utc};
+
+use std::collections::HashSet;
+
+use super::config::ValidatorKeys;
+use crate::crypto::{CryptoHash, Hash};
 use crate::helpers::{Height, ValidatorId};
 use crate::messages::{Precommit, Signed};
+use crate::node::State;
 use crate::proto;
 
 /// Exonum block header data structure.
@@ -41,6 +47,12 @@ pub struct Block {
     tx_hash: Hash,
     /// Hash of the blockchain state after applying transactions in the block.
     state_hash: Hash,
+    /// Root hash of the Merkle tree of events emitted by transactions in this block.
+    events_hash: Hash,
+    /// Time of the block. See [`block_time`] for details.
+    ///
+    /// [`block_time`]: fn.block_time.html
+    time: DateTime<Utc>,
 }
 
 impl Block {
@@ -52,6 +64,8 @@ impl Block {
         prev_hash: &Hash,
         tx_hash: &Hash,
         state_hash: &Hash,
+        events_hash: &Hash,
+        time: DateTime<Utc>,
     ) -> Self {
         Self {
             proposer_id,
@@ -60,6 +74,8 @@ impl Block {
             prev_hash: *prev_hash,
             tx_hash: *tx_hash,
             state_hash: *state_hash,
+            events_hash: *events_hash,
+            time,
         }
     }
     /// Identifier of the leader node which has proposed the block.
@@ -87,6 +103,33 @@ impl Block {
     pub fn state_hash(&self) -> &Hash {
         &self.state_hash
     }
+    /// Root hash of the Merkle tree of events emitted by transactions in this block.
+    pub fn events_hash(&self) -> &Hash {
+        &self.events_hash
+    }
+    /// Time of the block.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+}
+
+/// Computes the time of a block from the pre-commits for the previous block, which are
+/// included into the proposal of the block.
+///
+/// The time is the median of the pre-commit times. As the pre-commits are signed by
+/// a supermajority of validators, the median lies between the times reported by honest
+/// validators if at most one third of validators are Byzantine. The block time is never
+/// less than the time of the previous block `prev_time`, which is used if there are
+/// no pre-commits (e.g., for the block following the genesis block).
+pub fn block_time(precommits: &[Signed<Precommit>], prev_time: DateTime<Utc>) -> DateTime<Utc> {
+    let mut times = precommits
+        .iter()
+        .map(|precommit| precommit.time())
+        .collect::<Vec<_>>();
+    times.sort();
+    times
+        .get(times.len().saturating_sub(1) / 2)
+        .map_or(prev_time, |&median| median.max(prev_time))
 }
 
 /// Block with its `Precommit` messages.
@@ -104,10 +147,56 @@ pub struct BlockProof {
     pub precommits: Vec<Signed<Precommit>>,
 }
 
+impl BlockProof {
+    /// Checks that the block is authorized by a supermajority of the given validators.
+    ///
+    /// Each precommit must be authored by the validator with the corresponding identifier
+    /// and refer to this block. Signatures of the precommits are checked when the messages
+    /// are deserialized, so they are not verified again here.
+    pub fn verify(&self, validator_keys: &[ValidatorKeys]) -> Result<(), failure::Error> {
+        let majority_count = State::byzantine_majority_count(validator_keys.len());
+        ensure!(
+            self.precommits.len() >= majority_count,
+            "Block has not enough precommits: {}, required: {}",
+            self.precommits.len(),
+            majority_count
+        );
+
+        let block_hash = self.block.hash();
+        let mut validators = HashSet::with_capacity(self.precommits.len());
+        for precommit in &self.precommits {
+            let validator = precommit.validator();
+            let keys = validator_keys
+                .get(usize::from(validator))
+                .ok_or_else(|| format_err!("Precommit from unknown validator {}", validator))?;
+            ensure!(
+                keys.consensus_key == precommit.author(),
+                "Precommit from validator {} is signed with a wrong key",
+                validator
+            );
+            ensure!(
+                validators.insert(validator),
+                "Several precommits from validator {}",
+                validator
+            );
+            ensure!(
+                precommit.block_hash() == &block_hash && precommit.height() == self.block.height,
+                "Precommit from validator {} refers to another block",
+                validator
+            );
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use chrono::TimeZone;
+
     use super::*;
-    use crate::crypto::hash;
+    use crate::crypto::{gen_keypair, hash};
+    use crate::helpers::Round;
+    use crate::messages::Message;
 
     #[test]
     fn test_block() {
@@ -118,6 +207,8 @@ mod tests {
         let tx_hash = hash(&txs);
         let tx_count = txs.len() as u32;
         let state_hash = hash(&[7, 8, 9]);
+        let events_hash = hash(&[10, 11]);
+        let time = Utc::now();
         let block = Block::new(
             proposer_id,
             height,
@@ -125,6 +216,8 @@ mod tests {
             &prev_hash,
             &tx_hash,
             &state_hash,
+            &events_hash,
+            time,
         );
 
         assert_eq!(block.proposer_id(), proposer_id);
@@ -133,8 +226,41 @@ mod tests {
         assert_eq!(block.prev_hash(), &prev_hash);
         assert_eq!(block.tx_hash(), &tx_hash);
         assert_eq!(block.state_hash(), &state_hash);
+        assert_eq!(block.events_hash(), &events_hash);
+        assert_eq!(block.time(), time);
         let json_str = ::serde_json::to_string(&block).unwrap();
         let block1: Block = ::serde_json::from_str(&json_str).unwrap();
         assert_eq!(block1, block);
     }
+
+    #[test]
+    fn test_block_time() {
+        let (public_key, secret_key) = gen_keypair();
+        let precommits = [3, 1, 2, 10]
+            .iter()
+            .map(|&secs| {
+                Message::concrete(
+                    Precommit::new(
+                        ValidatorId(0),
+                        Height(1),
+                        Round(1),
+                        &hash(&[1]),
+                        &hash(&[2]),
+                        Utc.timestamp(secs, 0),
+                    ),
+                    public_key,
+                    &secret_key,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let prev_time = Utc.timestamp(0, 0);
+        assert_eq!(block_time(&precommits, prev_time), Utc.timestamp(2, 0));
+        assert_eq!(block_time(&precommits[..3], prev_time), Utc.timestamp(2, 0));
+        assert_eq!(
+            block_time(&precommits, Utc.timestamp(5, 0)),
+            Utc.timestamp(5, 0)
+        );
+        assert_eq!(block_time(&[], prev_time), prev_time);
+    }
 }
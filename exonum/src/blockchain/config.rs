@@ -26,6 +26,7 @@ use serde_json::Error as JsonError;
 
 use std::collections::{BTreeMap, HashSet};
 
+use super::LeaderElectionStrategy;
 use crate::crypto::{hash, CryptoHash, Hash, PublicKey};
 use crate::helpers::{Height, Milliseconds};
 use crate::messages::EMPTY_SIGNED_MESSAGE_SIZE;
@@ -68,6 +69,10 @@ pub struct StoredConfiguration {
     /// Keys are `service_name` from the `Service` trait and values are the serialized JSON.
     #[serde(default)]
     pub services: BTreeMap<String, serde_json::Value>,
+    /// Schema versions of the service data, which are reached by the service migrations
+    /// when this configuration becomes actual. Keys are `service_name` from the `Service` trait.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub service_versions: BTreeMap<String, u32>,
 }
 
 /// Consensus algorithm parameters.
@@ -121,6 +126,24 @@ pub struct ConsensusConfig {
     /// in a block if the transaction pool is almost empty, and create blocks faster when there are
     /// enough transactions in the pool.
     pub propose_timeout_threshold: u32,
+    /// Strategy of electing the leader of a consensus round.
+    ///
+    /// Defaults to the round-robin election, in which all validators are leaders in turn.
+    #[serde(
+        default,
+        skip_serializing_if = "LeaderElectionStrategy::is_round_robin"
+    )]
+    pub leader_election: LeaderElectionStrategy,
+    /// Maximal interval between blocks if the transaction pool is empty.
+    ///
+    /// If set, the leader of a round does not propose a block while its transaction pool
+    /// is empty, and nodes hold the current round instead of moving to the next one until
+    /// a transaction is received. An empty "heartbeat" block is still proposed once
+    /// the specified interval has passed since the start of the current height.
+    ///
+    /// Defaults to `None`, in which case empty blocks are proposed in every round.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_empty_block_interval: Option<Milliseconds>,
 }
 
 impl ConsensusConfig {
@@ -176,6 +199,8 @@ impl Default for ConsensusConfig {
             min_propose_timeout: 10,
             max_propose_timeout: 200,
             propose_timeout_threshold: 500,
+            leader_election: LeaderElectionStrategy::default(),
+            max_empty_block_interval: None,
         }
     }
 }
@@ -377,6 +402,7 @@ mod tests {
             validator_keys,
             consensus: ConsensusConfig::default(),
             services: BTreeMap::new(),
+            service_versions: BTreeMap::new(),
         }
     }
 
@@ -0,0 +1,271 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evidence of validators signing conflicting consensus messages.
+
+use protobuf::Message as ProtobufMessage;
+
+use std::borrow::Cow;
+
+use crate::blockchain::{
+    ExecutionError, ExecutionResult, Schema, Transaction, TransactionContext, CORE_SERVICE,
+};
+use crate::crypto::{self, CryptoHash, Hash, PublicKey, SecretKey};
+use crate::helpers::{Height, Round, ValidatorId};
+use crate::messages::{
+    Consensus, Message, Precommit, Prevote, ProtocolMessage, RawTransaction, Signed, SignedMessage,
+};
+use crate::proto::{self, ProtobufConvert};
+use crate::storage::StorageValue;
+
+/// Evidence that a validator has signed two conflicting consensus messages
+/// for the same height and round.
+///
+/// Prevotes conflict if they vote for different proposals. Precommits conflict
+/// if they commit different proposals or different blocks. The messages are ordered by their hashes,
+/// so the same pair of messages always produces the same evidence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Equivocation {
+    /// Two conflicting prevotes.
+    Prevotes {
+        /// The first of the conflicting prevotes.
+        first: Signed<Prevote>,
+        /// The second of the conflicting prevotes.
+        second: Signed<Prevote>,
+    },
+    /// Two conflicting precommits.
+    Precommits {
+        /// The first of the conflicting precommits.
+        first: Signed<Precommit>,
+        /// The second of the conflicting precommits.
+        second: Signed<Precommit>,
+    },
+}
+
+impl Equivocation {
+    /// Creates evidence from two prevotes. Returns `None` if the prevotes do not conflict.
+    pub fn from_prevotes(a: Signed<Prevote>, b: Signed<Prevote>) -> Option<Self> {
+        let conflicting = a.author() == b.author()
+            && a.validator() == b.validator()
+            && a.height() == b.height()
+            && a.round() == b.round()
+            && a.propose_hash() != b.propose_hash();
+        if !conflicting {
+            return None;
+        }
+        let (first, second) = ordered(a, b);
+        Some(Equivocation::Prevotes { first, second })
+    }
+
+    /// Creates evidence from two precommits. Returns `None` if the precommits do not conflict.
+    pub fn from_precommits(a: Signed<Precommit>, b: Signed<Precommit>) -> Option<Self> {
+        let conflicting = a.author() == b.author()
+            && a.validator() == b.validator()
+            && a.height() == b.height()
+            && a.round() == b.round()
+            && (a.propose_hash() != b.propose_hash() || a.block_hash() != b.block_hash());
+        if !conflicting {
+            return None;
+        }
+        let (first, second) = ordered(a, b);
+        Some(Equivocation::Precommits { first, second })
+    }
+
+    /// Returns the identifier of the validator which has signed the conflicting messages.
+    pub fn validator(&self) -> ValidatorId {
+        match *self {
+            Equivocation::Prevotes { ref first, .. } => first.validator(),
+            Equivocation::Precommits { ref first, .. } => first.validator(),
+        }
+    }
+
+    /// Returns the consensus public key of the validator which has signed
+    /// the conflicting messages.
+    pub fn author(&self) -> PublicKey {
+        match *self {
+            Equivocation::Prevotes { ref first, .. } => first.author(),
+            Equivocation::Precommits { ref first, .. } => first.author(),
+        }
+    }
+
+    /// Returns the height of the conflicting messages.
+    pub fn height(&self) -> Height {
+        match *self {
+            Equivocation::Prevotes { ref first, .. } => first.height(),
+            Equivocation::Precommits { ref first, .. } => first.height(),
+        }
+    }
+
+    /// Returns the round of the conflicting messages.
+    pub fn round(&self) -> Round {
+        match *self {
+            Equivocation::Prevotes { ref first, .. } => first.round(),
+            Equivocation::Precommits { ref first, .. } => first.round(),
+        }
+    }
+
+    /// Returns the conflicting messages.
+    pub fn messages(&self) -> (Message, Message) {
+        match self.clone() {
+            Equivocation::Prevotes { first, second } => (
+                Message::Consensus(Consensus::Prevote(first)),
+                Message::Consensus(Consensus::Prevote(second)),
+            ),
+            Equivocation::Precommits { first, second } => (
+                Message::Consensus(Consensus::Precommit(first)),
+                Message::Consensus(Consensus::Precommit(second)),
+            ),
+        }
+    }
+}
+
+fn ordered<T: ProtocolMessage>(a: Signed<T>, b: Signed<T>) -> (Signed<T>, Signed<T>) {
+    if a.hash() <= b.hash() {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl ProtobufConvert for Equivocation {
+    type ProtoStruct = proto::Equivocation;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        let (first, second) = self.messages();
+        let mut proto = <Self as ProtobufConvert>::ProtoStruct::new();
+        proto.set_first(first.signed_message().raw().to_vec());
+        proto.set_second(second.signed_message().raw().to_vec());
+        proto
+    }
+
+    fn from_pb(mut pb: Self::ProtoStruct) -> Result<Self, failure::Error> {
+        let first = Message::deserialize(SignedMessage::from_raw_buffer(pb.take_first())?)?;
+        let second = Message::deserialize(SignedMessage::from_raw_buffer(pb.take_second())?)?;
+        let equivocation = match (first, second) {
+            (
+                Message::Consensus(Consensus::Prevote(first)),
+                Message::Consensus(Consensus::Prevote(second)),
+            ) => Self::from_prevotes(first, second),
+            (
+                Message::Consensus(Consensus::Precommit(first)),
+                Message::Consensus(Consensus::Precommit(second)),
+            ) => Self::from_precommits(first, second),
+            _ => bail!("Equivocation evidence should consist of two prevotes or precommits"),
+        };
+        equivocation.ok_or_else(|| format_err!("Messages in the evidence do not conflict"))
+    }
+}
+
+impl StorageValue for Equivocation {
+    fn into_bytes(self) -> Vec<u8> {
+        self.to_pb()
+            .write_to_bytes()
+            .expect("Failed to serialize Equivocation to protobuf.")
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let mut pb = <Self as ProtobufConvert>::ProtoStruct::new();
+        pb.merge_from_bytes(bytes.as_ref())
+            .expect("Failed to parse Equivocation from protobuf.");
+        ProtobufConvert::from_pb(pb).expect("Failed to convert Equivocation from protobuf.")
+    }
+}
+
+impl CryptoHash for Equivocation {
+    fn hash(&self) -> Hash {
+        let (first, second) = self.messages();
+        crypto::hash(&[first.signed_message().raw(), second.signed_message().raw()].concat())
+    }
+}
+
+/// Errors emitted by the `ReportEquivocation` transaction during execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Fail)]
+#[repr(u8)]
+pub enum ReportEquivocationError {
+    /// The transaction is not authored by an active validator.
+    #[fail(display = "Not authored by a validator")]
+    UnknownReporter = 0,
+    /// The conflicting messages are not signed by the validator with the specified identifier.
+    #[fail(display = "Conflicting messages are not signed by a validator")]
+    UnknownOffender = 1,
+    /// The evidence has already been recorded.
+    #[fail(display = "Evidence has already been recorded")]
+    AlreadyRecorded = 2,
+}
+
+impl From<ReportEquivocationError> for ExecutionError {
+    fn from(value: ReportEquivocationError) -> ExecutionError {
+        let description = value.to_string();
+        ExecutionError::with_description(value as u8, description)
+    }
+}
+
+/// Core transaction recording the evidence of an equivocation in the blockchain,
+/// see [`equivocation_evidence`] for details.
+///
+/// Validators submit the transaction once they detect an equivocation. The transaction
+/// belongs to the core and is identified by the `CORE_SERVICE` service identifier.
+///
+/// [`equivocation_evidence`]: struct.Schema.html#method.equivocation_evidence
+#[derive(Debug, Clone, Serialize, Deserialize, ProtobufConvert)]
+#[exonum(pb = "proto::ReportEquivocation", crate = "crate")]
+pub struct ReportEquivocation {
+    /// Evidence of the equivocation.
+    pub evidence: Equivocation,
+}
+
+impl ReportEquivocation {
+    /// Creates a signed transaction reporting the given evidence.
+    pub fn sign(
+        evidence: Equivocation,
+        public_key: &PublicKey,
+        secret_key: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(Self { evidence }, CORE_SERVICE, *public_key, secret_key)
+    }
+}
+
+impl Transaction for ReportEquivocation {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        let author = context.author();
+        let mut schema = Schema::new(context.fork());
+
+        let reporters = schema.actual_configuration().validator_keys;
+        if !reporters.iter().any(|keys| keys.service_key == author) {
+            Err(ReportEquivocationError::UnknownReporter)?
+        }
+
+        let offenders = schema
+            .configuration_by_height(self.evidence.height())
+            .validator_keys;
+        let offender = offenders.get(self.evidence.validator().0 as usize);
+        if offender.map(|keys| keys.consensus_key) != Some(self.evidence.author()) {
+            Err(ReportEquivocationError::UnknownOffender)?
+        }
+
+        if !schema.add_equivocation_evidence(self.evidence.clone()) {
+            Err(ReportEquivocationError::AlreadyRecorded)?
+        }
+        Ok(())
+    }
+}
+
+/// Transactions of the core.
+#[derive(Debug, Clone, Serialize, Deserialize, TransactionSet)]
+#[exonum(crate = "crate")]
+pub(crate) enum CoreTransactions {
+    /// Records the evidence of an equivocation.
+    ReportEquivocation(ReportEquivocation),
+}
@@ -0,0 +1,75 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::crypto::Hash;
+use crate::messages::{BinaryForm, HexStringRepresentation};
+use crate::proto;
+
+/// Event emitted by a transaction during its execution.
+///
+/// Events of the successfully executed transactions are stored in the blockchain
+/// in the order of emission; the root hash of the events of a block is recorded
+/// in the block header. See [`TransactionContext::emit_event`] for details.
+///
+/// [`TransactionContext::emit_event`]: struct.TransactionContext.html#method.emit_event
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, ProtobufConvert)]
+#[exonum(pb = "proto::Event", crate = "crate")]
+pub struct Event {
+    /// Hash of the transaction which has emitted the event.
+    tx_hash: Hash,
+    /// Identifier of the service which the transaction belongs to.
+    service_id: u16,
+    /// Topic of the event.
+    topic: String,
+    /// Payload of the event serialized with protobuf.
+    #[serde(with = "HexStringRepresentation")]
+    payload: Vec<u8>,
+}
+
+impl Event {
+    /// Creates a new event.
+    pub fn new(tx_hash: Hash, service_id: u16, topic: &str, payload: Vec<u8>) -> Self {
+        Self {
+            tx_hash,
+            service_id,
+            topic: topic.to_owned(),
+            payload,
+        }
+    }
+
+    /// Returns the hash of the transaction which has emitted the event.
+    pub fn tx_hash(&self) -> &Hash {
+        &self.tx_hash
+    }
+
+    /// Returns the identifier of the service which the transaction belongs to.
+    pub fn service_id(&self) -> u16 {
+        self.service_id
+    }
+
+    /// Returns the topic of the event.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the serialized payload of the event.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Deserializes the payload of the event.
+    pub fn payload_as<T: BinaryForm>(&self) -> Result<T, failure::Error> {
+        T::decode(&self.payload)
+    }
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use chrono::{DateTime, Utc};
+
 use super::config::{ConsensusConfig, ValidatorKeys};
 
 /// The initial configuration which is committed into the genesis block.
@@ -23,27 +25,37 @@ use super::config::{ConsensusConfig, ValidatorKeys};
 /// `GenesisConfig` includes consensus related configuration and the public keys of validators.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct GenesisConfig {
+    /// Names of the services which are not active at the genesis block. Such services
+    /// can be activated later by adding them to the actual configuration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inactive_services: Vec<String>,
     /// Consensus configuration.
     pub consensus: ConsensusConfig,
     /// List of public keys of validators.
     pub validator_keys: Vec<ValidatorKeys>,
+    /// Creation time of the configuration, which is used as the time of the genesis block.
+    pub time: DateTime<Utc>,
 }
 
 impl GenesisConfig {
     /// Creates a default configuration from the given list of public keys.
+    /// The configuration is timestamped with the current time.
     pub fn new<I: Iterator<Item = ValidatorKeys>>(validators: I) -> Self {
         Self::new_with_consensus(ConsensusConfig::default(), validators)
     }
 
     /// Creates a configuration from the given consensus configuration and list of public keys.
+    /// The configuration is timestamped with the current time.
     pub fn new_with_consensus<I>(consensus: ConsensusConfig, validator_keys: I) -> Self
     where
         I: Iterator<Item = ValidatorKeys>,
     {
         consensus.warn_if_nonoptimal();
         Self {
+            inactive_services: Vec::new(),
             consensus,
             validator_keys: validator_keys.collect(),
+            time: Utc::now(),
         }
     }
 }
@@ -0,0 +1,154 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strategies of electing the leader, i.e., the validator proposing a block in a round.
+
+use std::fmt;
+
+use super::Schema;
+use crate::helpers::ValidatorId;
+use crate::storage::Snapshot;
+
+/// Strategy of electing the leader of a consensus round.
+///
+/// The strategy chooses validators eligible to be leaders at the height following
+/// the latest committed block. Leaders of the rounds at this height are taken from
+/// the eligible validators in the round-robin order: the leader of `round` at `height`
+/// is the eligible validator with the index `(height + round) % eligible.len()`.
+///
+/// The choice must depend only on the state covered by the state hash of the latest
+/// committed block, so that all nodes agree on the leader, including the nodes which
+/// obtained the state with the state sync and do not have the headers of the previous blocks.
+pub trait LeaderElection: fmt::Debug + Send + Sync {
+    /// Returns identifiers of the validators eligible to be leaders at the height following
+    /// the latest committed block, in the increasing order. The returned list should
+    /// not be empty.
+    fn eligible_leaders(&self, snapshot: &dyn Snapshot) -> Vec<ValidatorId>;
+}
+
+/// Leader election strategy, in which all validators are leaders in turn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinElection;
+
+impl LeaderElection for RoundRobinElection {
+    fn eligible_leaders(&self, snapshot: &dyn Snapshot) -> Vec<ValidatorId> {
+        let config = Schema::new(snapshot).actual_configuration();
+        all_validators(config.validator_keys.len())
+    }
+}
+
+/// Leader election strategy, which skips validators that have missed their last
+/// `missed_proposals` turns to propose a block.
+///
+/// A validator is considered inactive if it has not proposed any of the last
+/// `missed_proposals * n` committed blocks, where `n` is the number of validators. Inactive
+/// validators are not elected, except for the probe heights, at which all validators are
+/// leaders in turn, so that the validators which went back online become active again.
+/// Every `(missed_proposals + 1) * n` heights contain `n` consecutive probe heights.
+///
+/// The activity of the validators is determined from the [`last_proposals`] index, which is
+/// a part of the state hash. All validators are eligible if there are not enough committed
+/// blocks since the activation of the actual configuration, or if all validators are inactive.
+///
+/// [`last_proposals`]: struct.Schema.html#method.last_proposals
+#[derive(Debug, Clone, Copy)]
+pub struct SkipInactiveElection {
+    missed_proposals: u16,
+}
+
+impl SkipInactiveElection {
+    /// Creates a strategy skipping validators that missed the given number of proposals.
+    pub fn new(missed_proposals: u16) -> Self {
+        Self { missed_proposals }
+    }
+}
+
+impl LeaderElection for SkipInactiveElection {
+    fn eligible_leaders(&self, snapshot: &dyn Snapshot) -> Vec<ValidatorId> {
+        let schema = Schema::new(snapshot);
+        let height = schema.height().next();
+        let config = schema.configuration_by_height(height);
+        let validators = all_validators(config.validator_keys.len());
+
+        let validators_count = validators.len() as u64;
+        let window = u64::from(self.missed_proposals) * validators_count;
+        let is_probe_height = height.0 % (window + validators_count) >= window;
+        if window == 0 || is_probe_height || height.0 < config.actual_from.0 + window {
+            return validators;
+        }
+
+        let last_proposals = schema.last_proposals();
+        let active = validators
+            .iter()
+            .cloned()
+            .filter(|id| {
+                let consensus_key = &config.validator_keys[id.0 as usize].consensus_key;
+                last_proposals
+                    .get(consensus_key)
+                    .map_or(false, |last_proposal| last_proposal >= height.0 - window)
+            })
+            .collect::<Vec<_>>();
+        if active.is_empty() {
+            validators
+        } else {
+            active
+        }
+    }
+}
+
+/// Leader election strategy specified in the consensus configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LeaderElectionStrategy {
+    /// All validators are leaders in turn. See [`RoundRobinElection`] for details.
+    ///
+    /// [`RoundRobinElection`]: struct.RoundRobinElection.html
+    RoundRobin,
+    /// Validators which missed their last turns are skipped. See [`SkipInactiveElection`]
+    /// for details.
+    ///
+    /// [`SkipInactiveElection`]: struct.SkipInactiveElection.html
+    SkipInactive {
+        /// Number of missed proposals, after which a validator is skipped.
+        missed_proposals: u16,
+    },
+}
+
+impl LeaderElectionStrategy {
+    /// Returns `true` if the strategy is round-robin.
+    pub fn is_round_robin(&self) -> bool {
+        *self == LeaderElectionStrategy::RoundRobin
+    }
+}
+
+impl Default for LeaderElectionStrategy {
+    fn default() -> Self {
+        LeaderElectionStrategy::RoundRobin
+    }
+}
+
+impl LeaderElection for LeaderElectionStrategy {
+    fn eligible_leaders(&self, snapshot: &dyn Snapshot) -> Vec<ValidatorId> {
+        match *self {
+            LeaderElectionStrategy::RoundRobin => RoundRobinElection.eligible_leaders(snapshot),
+            LeaderElectionStrategy::SkipInactive { missed_proposals } => {
+                SkipInactiveElection::new(missed_proposals).eligible_leaders(snapshot)
+            }
+        }
+    }
+}
+
+fn all_validators(count: usize) -> Vec<ValidatorId> {
+    (0..count).map(|id| ValidatorId(id as u16)).collect()
+}
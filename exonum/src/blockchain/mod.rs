@@ -32,11 +32,17 @@
 //! [doc:create-service]: https://exonum.com/doc/get-started/create-service
 
 pub use self::{
-    block::{Block, BlockProof},
+    backup::BackupInfo,
+    block::{block_time, Block, BlockProof},
     config::{ConsensusConfig, StoredConfiguration, ValidatorKeys},
+    equivocation::{Equivocation, ReportEquivocation, ReportEquivocationError},
+    event::Event,
     genesis::GenesisConfig,
+    leader_election::{
+        LeaderElection, LeaderElectionStrategy, RoundRobinElection, SkipInactiveElection,
+    },
     schema::{Schema, TxLocation},
-    service::{Service, ServiceContext, SharedNodeState},
+    service::{Migration, Service, ServiceContext, SharedNodeState},
     transaction::{
         ExecutionError, ExecutionResult, Transaction, TransactionContext, TransactionError,
         TransactionErrorType, TransactionMessage, TransactionResult, TransactionSet,
@@ -46,10 +52,12 @@ pub use self::{
 pub mod config;
 
 use byteorder::{ByteOrder, LittleEndian};
+use chrono::{DateTime, Utc};
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt, iter, mem, panic,
+    path::Path,
     sync::Arc,
 };
 
@@ -59,8 +67,17 @@ use crate::messages::{Connect, Message, Precommit, ProtocolMessage, RawTransacti
 use crate::node::ApiSender;
 use crate::storage::{self, Database, Error, Fork, Patch, Snapshot};
 
+pub(crate) use self::backup::{create_backup, restore_backup};
+
+use self::equivocation::CoreTransactions;
+use self::schema::ArchivedChange;
+
+mod backup;
 mod block;
+mod equivocation;
+mod event;
 mod genesis;
+mod leader_election;
 mod schema;
 mod service;
 #[macro_use]
@@ -68,7 +85,8 @@ mod transaction;
 #[cfg(test)]
 mod tests;
 
-/// Id of core service table family.
+/// Id of core service table family. Core transactions, such as `ReportEquivocation`,
+/// use this identifier as well.
 pub const CORE_SERVICE: u16 = 0;
 
 /// Exonum blockchain instance with a certain services set and data storage.
@@ -81,6 +99,8 @@ pub struct Blockchain {
     #[doc(hidden)]
     pub service_keypair: (PublicKey, SecretKey),
     pub(crate) api_sender: ApiSender,
+    keep_blocks: Option<u64>,
+    archive: bool,
 }
 
 impl Blockchain {
@@ -109,9 +129,46 @@ impl Blockchain {
             service_map: Arc::new(service_map),
             service_keypair: (service_public_key, service_secret_key),
             api_sender,
+            keep_blocks: None,
+            archive: false,
         }
     }
 
+    /// Enables pruning of the old blocks data.
+    ///
+    /// After each commit, transaction bodies, lists of block transactions and precommits
+    /// are removed for all blocks except the latest `keep_blocks` ones. `None` disables
+    /// pruning, which is the default behavior.
+    ///
+    /// See [`DbOptions::keep_blocks`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `keep_blocks` is `Some(0)`, as pruning requires to keep at least
+    /// the latest block.
+    ///
+    /// [`DbOptions::keep_blocks`]: ../storage/struct.DbOptions.html#structfield.keep_blocks
+    pub fn set_keep_blocks(&mut self, keep_blocks: Option<u64>) -> Result<(), failure::Error> {
+        ensure!(
+            keep_blocks != Some(0),
+            "Pruning requires to keep at least one block"
+        );
+        self.keep_blocks = keep_blocks;
+        Ok(())
+    }
+
+    /// Enables the archive mode, in which the previous values of the keys changed by
+    /// each committed block are kept, so that the state at a past height can be obtained
+    /// with [`snapshot_at`]. The archive mode is disabled by default.
+    ///
+    /// See [`DbOptions::archive`] for details.
+    ///
+    /// [`snapshot_at`]: #method.snapshot_at
+    /// [`DbOptions::archive`]: ../storage/struct.DbOptions.html#structfield.archive
+    pub fn set_archive(&mut self, archive: bool) {
+        self.archive = archive;
+    }
+
     /// Recreates the blockchain to reuse with a sandbox.
     #[doc(hidden)]
     pub fn clone_with_api_sender(&self, api_sender: ApiSender) -> Self {
@@ -132,6 +189,33 @@ impl Blockchain {
         self.db.snapshot()
     }
 
+    /// Creates a read-only snapshot of the storage state right after the block at the given
+    /// height was committed.
+    ///
+    /// Returns `None` if the block at `height` is not committed yet, or if any block after it
+    /// was committed without the archive mode. Changes made outside of block commits,
+    /// such as additions to the transaction pool, are not archived. The snapshot is
+    /// reconstructed from the archived changes of all the subsequent blocks, so
+    /// its creation time grows with the distance from the latest block.
+    pub fn snapshot_at(&self, height: Height) -> Option<Box<dyn Snapshot>> {
+        let mut fork = self.fork();
+        if Schema::new(&mut fork).revert_blocks_above(height) {
+            Some(Box::new(fork))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a backup of the current storage state in the given directory, which should
+    /// not exist, without stopping the node. Backups are supported only for the `RocksDB`
+    /// storage.
+    ///
+    /// The backup consists of a database checkpoint and the information about the latest
+    /// block in it, which is returned by this method.
+    pub fn create_backup<P: AsRef<Path>>(&self, path: P) -> Result<BackupInfo, failure::Error> {
+        create_backup(self.db.as_ref(), path.as_ref())
+    }
+
     /// Creates a snapshot of the current storage state that can be later committed into the storage
     /// via the `merge` method.
     pub fn fork(&self) -> Fork {
@@ -142,9 +226,13 @@ impl Blockchain {
     /// A raw message can be converted into a `Transaction` object only
     /// if the following conditions are met:
     ///
-    /// - Blockchain has a service with the `service_id` of the given raw message.
+    /// - Blockchain has a service with the `service_id` of the given raw message,
+    ///   or the message is a core transaction with the `CORE_SERVICE` identifier.
     /// - Service can deserialize the given raw message.
     pub fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        if raw.service_id() == CORE_SERVICE {
+            return Ok(CoreTransactions::tx_from_raw(raw)?.into());
+        }
         let service = self
             .service_map
             .get(&raw.service_id())
@@ -152,6 +240,160 @@ impl Blockchain {
         service.tx_from_raw(raw)
     }
 
+    /// Returns the services which are active at the given height, i.e., the services mentioned
+    /// in the configuration actual at this height. All services are considered active until
+    /// the genesis configuration is committed.
+    pub(crate) fn active_services(
+        &self,
+        snapshot: &dyn Snapshot,
+        height: Height,
+    ) -> Vec<&dyn Service> {
+        let schema = Schema::new(snapshot);
+        if schema.configs_actual_from().is_empty() {
+            return self.service_map.values().map(AsRef::as_ref).collect();
+        }
+        let config = schema.configuration_by_height(height);
+        self.service_map
+            .values()
+            .filter(|service| config.services.contains_key(service.service_name()))
+            .map(AsRef::as_ref)
+            .collect()
+    }
+
+    /// Returns `true` if the service with the given identifier is active at the given height.
+    /// Core transactions are always active.
+    pub(crate) fn is_service_active(
+        &self,
+        snapshot: &dyn Snapshot,
+        service_id: u16,
+        height: Height,
+    ) -> bool {
+        service_id == CORE_SERVICE
+            || self
+                .active_services(snapshot, height)
+                .iter()
+                .any(|service| service.service_id() == service_id)
+    }
+
+    /// Returns the services which become active at the given height.
+    fn activated_services(&self, snapshot: &dyn Snapshot, height: Height) -> Vec<&dyn Service> {
+        if height == Height::zero() {
+            return Vec::new();
+        }
+        let previous = self
+            .active_services(snapshot, height.previous())
+            .iter()
+            .map(|service| service.service_id())
+            .collect::<HashSet<_>>();
+        self.active_services(snapshot, height)
+            .into_iter()
+            .filter(|service| !previous.contains(&service.service_id()))
+            .collect()
+    }
+
+    /// Runs migrations of the active services, for which the configuration actual at
+    /// the given height specifies a schema version exceeding the recorded one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the service does not support the configured schema version or does
+    /// not provide a migration to one of the intermediate versions. The configuration
+    /// service rejects proposals of such configurations, see `check_service_versions`.
+    fn migrate_services(&self, fork: &mut Fork, height: Height) {
+        let config = Schema::new(&*fork).configuration_by_height(height);
+        for service in self.active_services(&*fork, height) {
+            let name = service.service_name();
+            let target = match config.service_versions.get(name) {
+                Some(version) => *version,
+                None => continue,
+            };
+            let migrations = Self::pending_migrations(service, &*fork, target)
+                .unwrap_or_else(|e| panic!("{}", e));
+            if migrations.is_empty() {
+                continue;
+            }
+
+            for migration in migrations {
+                info!(
+                    "Migrating service <{}> to schema version {} at height {}",
+                    name,
+                    migration.version(),
+                    height
+                );
+                migration.run(fork);
+            }
+            Schema::new(&mut *fork).set_service_schema_version(name, target);
+        }
+    }
+
+    /// Checks that the services support the schema versions specified in the `service_versions`
+    /// of the given configuration and provide migrations to them from the recorded versions.
+    pub(crate) fn check_service_versions(
+        &self,
+        snapshot: &dyn Snapshot,
+        config: &StoredConfiguration,
+    ) -> Result<(), failure::Error> {
+        for (name, version) in &config.service_versions {
+            let service = self
+                .service_map
+                .values()
+                .find(|service| service.service_name() == name)
+                .ok_or_else(|| format_err!("Service <{}> is not found", name))?;
+            Self::pending_migrations(service.as_ref(), snapshot, *version)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the migrations of the service from the recorded schema version to the given one
+    /// in the increasing order of versions.
+    fn pending_migrations(
+        service: &dyn Service,
+        snapshot: &dyn Snapshot,
+        target: u32,
+    ) -> Result<Vec<Migration>, failure::Error> {
+        let name = service.service_name();
+        let current = Schema::new(snapshot)
+            .service_schema_version(name)
+            .unwrap_or(0);
+        if target <= current {
+            return Ok(Vec::new());
+        }
+        ensure!(
+            target <= service.schema_version(),
+            "Service <{}> does not support schema version {}, the latest supported \
+             version is {}",
+            name,
+            target,
+            service.schema_version()
+        );
+
+        let mut migrations = service.migrations();
+        if let Some(version) = (current + 1..=target).find(|version| {
+            migrations
+                .iter()
+                .all(|migration| migration.version() != *version)
+        }) {
+            bail!(
+                "Migration of service <{}> to schema version {} is not found",
+                name,
+                version
+            );
+        }
+        migrations
+            .retain(|migration| migration.version() > current && migration.version() <= target);
+        migrations.sort_by_key(Migration::version);
+        migrations.dedup_by_key(|migration| migration.version());
+        Ok(migrations)
+    }
+
+    /// Returns the priority of the transaction in the transaction pool as assigned
+    /// by the service which the transaction belongs to.
+    pub(crate) fn tx_priority(&self, snapshot: &dyn Snapshot, tx: &Signed<RawTransaction>) -> u64 {
+        self.service_map
+            .get(&tx.payload().service_id())
+            .map_or(0, |service| service.tx_priority(snapshot, tx))
+    }
+
     /// Commits changes from the patch to the blockchain storage.
     /// See [`Fork`](../storage/struct.Fork.html) for details.
     pub fn merge(&mut self, patch: Patch) -> Result<(), Error> {
@@ -233,17 +475,34 @@ impl Blockchain {
 
         let patch = {
             let mut fork = self.fork();
+            for name in &cfg.inactive_services {
+                if !self
+                    .service_map
+                    .values()
+                    .any(|service| service.service_name() == name)
+                {
+                    panic!("Inactive service '{}' is not found", name);
+                }
+            }
             // Update service tables
             for (_, service) in self.service_map.iter() {
-                let cfg = service.initialize(&mut fork);
                 let name = service.service_name();
+                if cfg
+                    .inactive_services
+                    .iter()
+                    .any(|inactive| inactive == name)
+                {
+                    continue;
+                }
+                let service_cfg = service.initialize(&mut fork);
+                Schema::new(&mut fork).set_service_schema_version(name, service.schema_version());
                 if config_propose.services.contains_key(name) {
                     panic!(
                         "Services already contain service with '{}' name, please change it",
                         name
                     );
                 }
-                config_propose.services.insert(name.into(), cfg);
+                config_propose.services.insert(name.into(), service_cfg);
             }
             // Commit actual configuration
             {
@@ -255,7 +514,7 @@ impl Blockchain {
                 schema.commit_configuration(config_propose);
             };
             self.merge(fork.into_patch())?;
-            self.create_patch(ValidatorId::zero(), Height::zero(), &[])
+            self.create_patch_with_time(ValidatorId::zero(), Height::zero(), &[], cfg.time)
                 .1
         };
         self.merge(patch)?;
@@ -301,14 +560,111 @@ impl Blockchain {
         self.api_sender.broadcast_transaction(msg)
     }
 
+    /// Returns keys of the core and service tables in the state hash aggregator, as returned
+    /// by the `service_table_unique_key` helper function, together with the names of indexes
+    /// containing the tables. Tables of inactive services are included as well, since
+    /// the services may be activated later.
+    ///
+    /// Returns `None` if some service influencing the blockchain state does not declare
+    /// names of its tables.
+    pub(crate) fn state_tables(&self, snapshot: &dyn Snapshot) -> Option<Vec<(Hash, String)>> {
+        let mut tables = Schema::new(snapshot)
+            .core_state_table_names()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| (Self::service_table_unique_key(CORE_SERVICE, idx), name))
+            .collect::<Vec<_>>();
+        for service in self.service_map.values() {
+            let service_id = service.service_id();
+            let service_tables = service.state_hash(snapshot).len();
+            if service_tables == 0 {
+                continue;
+            }
+            let names = service
+                .state_table_names()
+                .filter(|names| names.len() == service_tables)?;
+            tables.extend(
+                names
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, name)| (Self::service_table_unique_key(service_id, idx), name)),
+            );
+        }
+        Some(tables)
+    }
+
+    /// Saves hashes of the core and service tables into the state hash aggregator and returns
+    /// the resulting state hash.
+    pub(crate) fn update_state_hash(&self, fork: &mut Fork, height: Height) -> Hash {
+        let state_hashes = {
+            let schema = Schema::new(&*fork);
+
+            let vec_core_state = schema.core_state_hash();
+            let mut state_hashes = Vec::new();
+
+            for (idx, core_table_hash) in vec_core_state.into_iter().enumerate() {
+                let key = Self::service_table_unique_key(CORE_SERVICE, idx);
+                state_hashes.push((key, core_table_hash));
+            }
+
+            for service in self.active_services(&*fork, height) {
+                let service_id = service.service_id();
+                let vec_service_state = service.state_hash(&*fork);
+                for (idx, service_table_hash) in vec_service_state.into_iter().enumerate() {
+                    let key = Self::service_table_unique_key(service_id, idx);
+                    state_hashes.push((key, service_table_hash));
+                }
+            }
+
+            state_hashes
+        };
+
+        let mut schema = Schema::new(fork);
+        let mut sum_table = schema.state_hash_aggregator_mut();
+        for (key, hash) in state_hashes {
+            sum_table.put(&key, hash)
+        }
+        sum_table.merkle_root()
+    }
+
     /// Executes the given transactions from the pool.
     /// Then collects the resulting changes from the current storage state and returns them
     /// with the hash of the resulting block.
+    ///
+    /// The time of the resulting block is equal to the time of the latest committed block,
+    /// or to the current time if there are no committed blocks.
+    /// Use [`create_patch_with_time`] to specify the block time.
+    ///
+    /// [`create_patch_with_time`]: #method.create_patch_with_time
     pub fn create_patch(
         &self,
         proposer_id: ValidatorId,
         height: Height,
         tx_hashes: &[Hash],
+    ) -> (Hash, Patch) {
+        let snapshot = self.snapshot();
+        let schema = Schema::new(&snapshot);
+        let time = schema
+            .block_hashes_by_height()
+            .last()
+            .and_then(|block_hash| schema.blocks().get(&block_hash))
+            .map_or_else(Utc::now, |block| block.time());
+        self.create_patch_with_time(proposer_id, height, tx_hashes, time)
+    }
+
+    /// Executes the given transactions from the pool with the given block time.
+    /// Then collects the resulting changes from the current storage state and returns them
+    /// with the hash of the resulting block.
+    ///
+    /// The time is available to transactions with [`TransactionContext::time`].
+    ///
+    /// [`TransactionContext::time`]: struct.TransactionContext.html#method.time
+    pub fn create_patch_with_time(
+        &self,
+        proposer_id: ValidatorId,
+        height: Height,
+        tx_hashes: &[Hash],
+        time: DateTime<Utc>,
     ) -> (Hash, Patch) {
         // Create fork
         let mut fork = self.fork();
@@ -316,61 +672,51 @@ impl Blockchain {
         let block_hash = {
             // Get last hash.
             let last_hash = self.last_hash();
+            // Initialize services activated at this height.
+            for service in self.activated_services(&fork, height) {
+                info!(
+                    "Activating service <{}> at height {}",
+                    service.service_name(),
+                    height
+                );
+                service.initialize(&mut fork);
+                Schema::new(&mut fork)
+                    .set_service_schema_version(service.service_name(), service.schema_version());
+            }
+            // Migrate data of the services to the configured schema versions.
+            if height > Height(0) {
+                self.migrate_services(&mut fork, height);
+            }
             // Save & execute transactions.
             for (index, hash) in tx_hashes.iter().enumerate() {
-                self.execute_transaction(*hash, height, index, &mut fork)
+                self.execute_transaction(*hash, height, time, index, &mut fork)
                     // Execution could fail if the transaction
                     // cannot be deserialized or it isn't in the pool.
                     .expect("Transaction execution error.");
             }
 
-            // Invoke execute method for all services.
-            for service in self.service_map.values() {
+            // Invoke execute method for all active services.
+            for service in self.active_services(&fork, height) {
                 // Skip execution for genesis block.
                 if height > Height(0) {
-                    before_commit(service.as_ref(), &mut fork);
+                    before_commit(service, &mut fork);
                 }
             }
 
-            // Get tx & state hash.
-            let (tx_hash, state_hash) = {
-                let state_hashes = {
-                    let schema = Schema::new(&fork);
-
-                    let vec_core_state = schema.core_state_hash();
-                    let mut state_hashes = Vec::new();
-
-                    for (idx, core_table_hash) in vec_core_state.into_iter().enumerate() {
-                        let key = Self::service_table_unique_key(CORE_SERVICE, idx);
-                        state_hashes.push((key, core_table_hash));
-                    }
-
-                    for service in self.service_map.values() {
-                        let service_id = service.service_id();
-                        let vec_service_state = service.state_hash(&fork);
-                        for (idx, service_table_hash) in vec_service_state.into_iter().enumerate() {
-                            let key = Self::service_table_unique_key(service_id, idx);
-                            state_hashes.push((key, service_table_hash));
-                        }
-                    }
-
-                    state_hashes
-                };
-
+            // Record the proposer, which is used by the skip-inactive leader election.
+            if height > Height(0) {
                 let mut schema = Schema::new(&mut fork);
+                let config = schema.configuration_by_height(height);
+                if !config.consensus.leader_election.is_round_robin() {
+                    let consensus_key = config.validator_keys[proposer_id.0 as usize].consensus_key;
+                    schema.set_last_proposal(&consensus_key, height);
+                }
+            }
 
-                let state_hash = {
-                    let mut sum_table = schema.state_hash_aggregator_mut();
-                    for (key, hash) in state_hashes {
-                        sum_table.put(&key, hash)
-                    }
-                    sum_table.merkle_root()
-                };
-
-                let tx_hash = schema.block_transactions(height).merkle_root();
-
-                (tx_hash, state_hash)
-            };
+            // Get tx & state hash.
+            let state_hash = self.update_state_hash(&mut fork, height);
+            let tx_hash = Schema::new(&fork).block_transactions(height).merkle_root();
+            let events_hash = Schema::new(&fork).block_events(height).merkle_root();
 
             // Create block.
             let block = Block::new(
@@ -380,6 +726,8 @@ impl Blockchain {
                 &last_hash,
                 &tx_hash,
                 &state_hash,
+                &events_hash,
+                time,
             );
             trace!("execute block = {:?}", block);
             // Calculate block hash.
@@ -400,6 +748,7 @@ impl Blockchain {
         &self,
         tx_hash: Hash,
         height: Height,
+        time: DateTime<Utc>,
         index: usize,
         fork: &mut Fork,
     ) -> Result<(), failure::Error> {
@@ -413,16 +762,19 @@ impl Blockchain {
                 ))
             })?;
 
-            let service_name = self
-                .service_map
-                .get(&raw.service_id())
-                .ok_or_else(|| {
-                    failure::err_msg(format!(
-                        "Service not found. Service id: {}",
-                        raw.service_id()
-                    ))
-                })?
-                .service_name();
+            let service_name = if raw.service_id() == CORE_SERVICE {
+                "core"
+            } else {
+                self.service_map
+                    .get(&raw.service_id())
+                    .ok_or_else(|| {
+                        failure::err_msg(format!(
+                            "Service not found. Service id: {}",
+                            raw.service_id()
+                        ))
+                    })?
+                    .service_name()
+            };
 
             let tx = self.tx_from_raw(raw.payload().clone()).map_err(|error| {
                 format_err!("Service <{}>: {}, tx: {:?}", service_name, error, tx_hash)
@@ -430,44 +782,71 @@ impl Blockchain {
             (tx, raw, service_name)
         };
 
-        fork.checkpoint();
-
-        let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
-            let context = TransactionContext::new(&mut *fork, &raw);
-            tx.execute(context)
-        }));
-
-        let tx_result = TransactionResult(match catch_result {
-            Ok(execution_result) => {
-                match execution_result {
-                    Ok(()) => {
-                        fork.commit();
+        let author = raw.author();
+        let validity = if self.is_service_active(&*fork, raw.service_id(), height) {
+            Schema::new(&fork).check_transaction(tx.as_ref(), &author, height)
+        } else {
+            Err(TransactionError::inactive_service(Some(format!(
+                "Service is not active at height {}",
+                height
+            ))))
+        };
+        let tx_result = match validity {
+            Err(e) => {
+                info!(
+                    "Service <{}>: {:?} transaction is not executed: {}",
+                    service_name, tx_hash, e
+                );
+                TransactionResult(Err(e))
+            }
+            Ok(()) => {
+                // The nonce is used even if the transaction execution fails.
+                if let Some(nonce) = tx.nonce() {
+                    Schema::new(&mut *fork)
+                        .author_nonces_mut()
+                        .put(&author, nonce);
+                }
+                fork.checkpoint();
+
+                let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                    let context = TransactionContext::new(self, &mut *fork, &raw, height, time);
+                    tx.execute(context)
+                }));
+
+                TransactionResult(match catch_result {
+                    Ok(execution_result) => {
+                        match execution_result {
+                            Ok(()) => {
+                                fork.commit();
+                            }
+                            Err(ref e) => {
+                                // Unlike panic, transaction failure isn't that rare, so logging
+                                // the whole transaction body is an overkill: it can be
+                                // relatively big.
+                                info!(
+                                    "Service <{}>: {:?} transaction execution failed: {:?}",
+                                    service_name, tx_hash, e
+                                );
+                                fork.rollback();
+                            }
+                        }
+                        execution_result.map_err(TransactionError::from)
                     }
-                    Err(ref e) => {
-                        // Unlike panic, transaction failure isn't that rare, so logging the
-                        // whole transaction body is an overkill: it can be relatively big.
-                        info!(
-                            "Service <{}>: {:?} transaction execution failed: {:?}",
-                            service_name, tx_hash, e
-                        );
+                    Err(err) => {
+                        if err.is::<Error>() {
+                            // Continue panic unwind if the reason is StorageError.
+                            panic::resume_unwind(err);
+                        }
                         fork.rollback();
+                        error!(
+                            "Service <{}>: {:?} transaction execution panicked: {:?}",
+                            service_name, tx, err
+                        );
+                        Err(TransactionError::from_panic(&err))
                     }
-                }
-                execution_result.map_err(TransactionError::from)
-            }
-            Err(err) => {
-                if err.is::<Error>() {
-                    // Continue panic unwind if the reason is StorageError.
-                    panic::resume_unwind(err);
-                }
-                fork.rollback();
-                error!(
-                    "Service <{}>: {:?} transaction execution panicked: {:?}",
-                    service_name, tx, err
-                );
-                Err(TransactionError::from_panic(&err))
+                })
             }
-        });
+        };
 
         let mut schema = Schema::new(fork);
         schema.transaction_results_mut().put(&tx_hash, tx_result);
@@ -507,25 +886,63 @@ impl Blockchain {
                 schema
                     .transactions_pool_len_index_mut()
                     .set(txs_count - u64::from(txs_in_block));
+
+                if let Some(keep_blocks) = self.keep_blocks {
+                    let next_height = schema.height().next();
+                    if next_height.0 > keep_blocks {
+                        schema.prune_blocks_below(Height(next_height.0 - keep_blocks));
+                    }
+                }
+            }
+            if self.archive {
+                self.archive_changes(&mut fork);
             }
             fork.into_patch()
         };
         self.merge(patch)?;
 
-        // Invokes `after_commit` for each service in order of their identifiers
-        for (service_id, service) in self.service_map.iter() {
+        // Invokes `after_commit` for each active service in order of their identifiers
+        let snapshot = self.snapshot();
+        let height = Schema::new(&snapshot).height();
+        for service in self.active_services(snapshot.as_ref(), height) {
             let context = ServiceContext::new(
                 self.service_keypair.0,
                 self.service_keypair.1.clone(),
                 self.api_sender.clone(),
                 self.fork(),
-                *service_id,
+                service.service_id(),
             );
             service.after_commit(&context);
         }
         Ok(())
     }
 
+    /// Stores the previous values of the keys changed in the fork with the block being
+    /// committed.
+    fn archive_changes(&self, fork: &mut Fork) {
+        let snapshot = self.snapshot();
+        let changes = fork
+            .patch()
+            .iter()
+            .flat_map(|(index_name, changes)| {
+                let snapshot = &snapshot;
+                changes.iter().map(move |(key, _)| {
+                    let value = snapshot.get(index_name, key);
+                    ArchivedChange {
+                        index_name: index_name.clone(),
+                        key: key.clone(),
+                        existed: value.is_some(),
+                        value: value.unwrap_or_default(),
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut schema = Schema::new(fork);
+        let height = schema.height();
+        schema.archived_changes_mut(height).extend(changes);
+    }
+
     /// Saves the `Connect` message from a peer to the cache.
     pub(crate) fn save_peer(&mut self, pubkey: &PublicKey, peer: Signed<Connect>) {
         let mut fork = self.fork();
@@ -583,6 +1000,18 @@ impl Blockchain {
         self.merge(fork.into_patch())
             .expect("Unable to save messages to the consensus cache");
     }
+
+    /// Records the evidence of an equivocation. Returns `false` if the evidence
+    /// has already been recorded.
+    pub(crate) fn save_equivocation(&mut self, equivocation: Equivocation) -> bool {
+        let mut fork = self.fork();
+        let added = Schema::new(&mut fork).add_equivocation(equivocation);
+        if added {
+            self.merge(fork.into_patch())
+                .expect("Unable to save the equivocation evidence");
+        }
+        added
+    }
 }
 
 fn before_commit(service: &dyn Service, fork: &mut Fork) {
@@ -617,6 +1046,8 @@ impl Clone for Blockchain {
             service_map: Arc::clone(&self.service_map),
             api_sender: self.api_sender.clone(),
             service_keypair: self.service_keypair.clone(),
+            keep_blocks: self.keep_blocks,
+            archive: self.archive,
         }
     }
 }
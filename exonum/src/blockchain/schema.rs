@@ -12,9 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{config::StoredConfiguration, Block, BlockProof, Blockchain, TransactionResult};
+use super::{
+    config::StoredConfiguration, Block, BlockProof, Blockchain, Equivocation, Event, Transaction,
+    TransactionError, TransactionResult,
+};
 use crate::{
-    crypto::{CryptoHash, Hash, PublicKey},
+    crypto::{self, CryptoHash, Hash, PublicKey},
     helpers::{Height, Round},
     messages::{Connect, Message, Precommit, RawTransaction, Signed},
     proto,
@@ -51,6 +54,15 @@ define_names!(
     PEERS_CACHE => "peers_cache";
     CONSENSUS_MESSAGES_CACHE => "consensus_messages_cache";
     CONSENSUS_ROUND => "consensus_round";
+    PRUNED_HEIGHT => "pruned_height";
+    FIRST_BLOCK_HEIGHT => "first_block_height";
+    AUTHOR_NONCES => "author_nonces";
+    SERVICE_SCHEMA_VERSIONS => "service_schema_versions";
+    BLOCK_EVENTS => "block_events";
+    ARCHIVED_CHANGES => "archived_changes";
+    EQUIVOCATIONS => "equivocations";
+    EQUIVOCATION_EVIDENCE => "equivocation_evidence";
+    LAST_PROPOSALS => "last_proposals";
 );
 
 /// Configuration index.
@@ -114,6 +126,20 @@ impl TxLocation {
     }
 }
 
+/// Previous value of a key changed by a block, stored in the archive mode.
+#[derive(Debug, Clone, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::ArchivedChange", crate = "crate")]
+pub(crate) struct ArchivedChange {
+    /// Name of the column family.
+    pub index_name: String,
+    /// Key within the column family.
+    pub key: Vec<u8>,
+    /// Value of the key before the block. Empty if the key did not exist.
+    pub value: Vec<u8>,
+    /// Whether the key existed before the block.
+    pub existed: bool,
+}
+
 /// Information schema for indices maintained by the Exonum core logic.
 ///
 /// Indices defined by this schema are present in the blockchain regardless of
@@ -148,6 +174,37 @@ where
         ProofMapIndex::new(TRANSACTION_RESULTS, &self.view)
     }
 
+    /// Returns a table that represents a map with a key-value pair of a transaction
+    /// author and the nonce of the latest committed transaction of this author.
+    ///
+    /// Only transactions which return a nonce from `Transaction::nonce` are tracked.
+    pub fn author_nonces(&self) -> ProofMapIndex<&T, PublicKey, u64> {
+        ProofMapIndex::new(AUTHOR_NONCES, &self.view)
+    }
+
+    /// Returns a table that represents a map with a key-value pair of the hash of
+    /// a service name and the schema version of the service data.
+    pub fn service_schema_versions(&self) -> ProofMapIndex<&T, Hash, u32> {
+        ProofMapIndex::new(SERVICE_SCHEMA_VERSIONS, &self.view)
+    }
+
+    /// Returns the schema version of the data of the service with the given name,
+    /// or `None` if the service has not been initialized.
+    pub fn service_schema_version(&self, service_name: &str) -> Option<u32> {
+        self.service_schema_versions()
+            .get(&crypto::hash(service_name.as_bytes()))
+    }
+
+    /// Returns a table that represents a map with a key-value pair of the consensus key
+    /// of a validator and the height of the latest committed block proposed by this validator.
+    ///
+    /// The table is maintained only for the blocks committed with the skip-inactive leader
+    /// election strategy. It is a part of the state hash once non-empty, so that the strategy
+    /// can rely on it even if the block headers are not available, e.g., after the state sync.
+    pub fn last_proposals(&self) -> ProofMapIndex<&T, PublicKey, u64> {
+        ProofMapIndex::new(LAST_PROPOSALS, &self.view)
+    }
+
     /// Returns a table that represents a set of uncommitted transactions hashes.
     pub fn transactions_pool(&self) -> KeySetIndex<&T, Hash> {
         KeySetIndex::new(TRANSACTIONS_POOL, &self.view)
@@ -176,6 +233,9 @@ where
     }
 
     /// Returns a table that keeps block hashes for corresponding block heights.
+    ///
+    /// Hashes are stored starting from the [first block height](#method.first_block_height),
+    /// i.e., the hash of the block at height `h` has index `h - first_block_height`.
     pub fn block_hashes_by_height(&self) -> ListIndex<&T, Hash> {
         ListIndex::new(BLOCK_HASHES_BY_HEIGHT, &self.view)
     }
@@ -186,6 +246,22 @@ where
         ProofListIndex::new_in_family(BLOCK_TRANSACTIONS, &height, &self.view)
     }
 
+    /// Returns a table that keeps a list of events emitted by transactions in the block
+    /// at the given height, in the order of emission. The root hash of this table is
+    /// recorded in the block header.
+    pub fn block_events(&self, height: Height) -> ProofListIndex<&T, Event> {
+        let height: u64 = height.into();
+        ProofListIndex::new_in_family(BLOCK_EVENTS, &height, &self.view)
+    }
+
+    /// Returns a table that keeps the previous values of the keys changed by the block
+    /// at the given height. The table is empty unless the block was committed in the archive
+    /// mode.
+    pub(crate) fn archived_changes(&self, height: Height) -> ListIndex<&T, ArchivedChange> {
+        let height: u64 = height.into();
+        ListIndex::new_in_family(ARCHIVED_CHANGES, &height, &self.view)
+    }
+
     /// Returns a table that keeps a list of precommits for the block with the given hash.
     pub fn precommits(&self, hash: &Hash) -> ListIndex<&T, Signed<Precommit>> {
         ListIndex::new_in_family(PRECOMMITS, hash, &self.view)
@@ -243,24 +319,177 @@ where
             .unwrap_or_else(Round::first)
     }
 
-    /// Returns the block hash for the given height.
+    /// Returns evidence of validators signing conflicting consensus messages, which has been
+    /// detected by this node or received from its peers.
+    ///
+    /// - Table **key** is the hash of the evidence.
+    /// - Table **value** is the evidence.
+    ///
+    /// The evidence is collected by each node independently, so the table does not contribute
+    /// to the `state_hash` and differs among nodes. Hence, the table is not available to
+    /// services: transactions reading it would change the state differently on different
+    /// nodes. The evidence can be obtained with the `v1/equivocations` private API endpoint.
+    /// Validators report the detected evidence with the `ReportEquivocation` transaction,
+    /// which records it into the [`equivocation_evidence`][1] table.
+    ///
+    /// [1]: struct.Schema.html#method.equivocation_evidence
+    pub(crate) fn equivocations(&self) -> MapIndex<&T, Hash, Equivocation> {
+        MapIndex::new(EQUIVOCATIONS, &self.view)
+    }
+
+    /// Returns evidence of validators signing conflicting consensus messages, which has been
+    /// committed to the blockchain with the `ReportEquivocation` transaction.
+    ///
+    /// - Table **key** is the hash of the evidence.
+    /// - Table **value** is the evidence.
+    ///
+    /// The table is a part of the state hash, so services can rely on it, e.g., to penalize
+    /// the offending validators.
+    pub fn equivocation_evidence(&self) -> ProofMapIndex<&T, Hash, Equivocation> {
+        ProofMapIndex::new(EQUIVOCATION_EVIDENCE, &self.view)
+    }
+
+    /// Returns the height starting from which transaction bodies, lists of block
+    /// transactions and precommits are stored. Data for all blocks below this height
+    /// has been pruned. Returns zero height if nothing has been pruned.
+    pub fn pruned_height(&self) -> Height {
+        Entry::new(PRUNED_HEIGHT, &self.view)
+            .get()
+            .map_or_else(Height::zero, Height)
+    }
+
+    /// Returns `true` if transaction bodies and precommits of the block at the given height
+    /// have been pruned.
+    pub fn is_pruned(&self, height: Height) -> bool {
+        height < self.pruned_height()
+    }
+
+    /// Returns the height of the earliest block known to the node. Headers of the blocks
+    /// below this height are unknown, because the node has obtained the blockchain state
+    /// at this height from its peers. Returns zero height if the state sync was not used.
+    pub fn first_block_height(&self) -> Height {
+        Entry::new(FIRST_BLOCK_HEIGHT, &self.view)
+            .get()
+            .map_or_else(Height::zero, Height)
+    }
+
+    /// Returns the block hash for the given height, or `None` if the block is not committed
+    /// yet or lies below the [first block height](#method.first_block_height).
     pub fn block_hash_by_height(&self, height: Height) -> Option<Hash> {
-        self.block_hashes_by_height().get(height.into())
+        let first_height = self.first_block_height();
+        if height < first_height {
+            return None;
+        }
+        self.block_hashes_by_height().get(height.0 - first_height.0)
     }
 
     /// Returns the block for the given height with the proof of its inclusion.
     pub fn block_and_precommits(&self, height: Height) -> Option<BlockProof> {
-        let block_hash = match self.block_hash_by_height(height) {
-            None => return None,
-            Some(block_hash) => block_hash,
-        };
-        let block = self.blocks().get(&block_hash).unwrap();
+        let block_hash = self.block_hash_by_height(height)?;
+        let block = self.blocks().get(&block_hash)?;
         let precommits_table = self.precommits(&block_hash);
         let precommits = precommits_table.iter().collect();
         let res = BlockProof { block, precommits };
         Some(res)
     }
 
+    /// Checks consistency of the stored block at the given height.
+    ///
+    /// The block must refer to the previous block by its `prev_hash`, while its `tx_hash`
+    /// and `tx_count` must match the stored list of block transactions. Precommits
+    /// for the block must be correctly signed by a supermajority of the validators
+    /// from the configuration active at the block height.
+    ///
+    /// Transactions and precommits of the pruned blocks are not checked. Blocks below
+    /// the [first block height](#method.first_block_height) (i.e., ones skipped during
+    /// the state sync) are not checked at all, as well as transactions of the first block.
+    pub fn verify_block(&self, height: Height) -> Result<(), failure::Error> {
+        let first_height = self.first_block_height();
+        if height < first_height {
+            return Ok(());
+        }
+        let block_hash = self
+            .block_hash_by_height(height)
+            .ok_or_else(|| format_err!("Block hash is missing"))?;
+
+        let block = self
+            .blocks()
+            .get(&block_hash)
+            .ok_or_else(|| format_err!("Block {:?} is missing", block_hash))?;
+        ensure!(
+            block.hash() == block_hash,
+            "Block hash does not match the stored hash {:?}",
+            block_hash
+        );
+        ensure!(
+            block.height() == height,
+            "Block has wrong height {}",
+            block.height()
+        );
+
+        if height == Height::zero() {
+            ensure!(
+                *block.prev_hash() == Hash::zero(),
+                "Genesis block refers to a previous block"
+            );
+        } else if height > first_height {
+            let prev_hash = self
+                .block_hash_by_height(height.previous())
+                .ok_or_else(|| format_err!("Previous block hash is missing"))?;
+            ensure!(
+                *block.prev_hash() == prev_hash,
+                "Block refers to a wrong previous block {:?}, expected {:?}",
+                block.prev_hash(),
+                prev_hash
+            );
+        }
+
+        if self.is_pruned(height) {
+            return Ok(());
+        }
+
+        let block_transactions = self.block_transactions(height);
+        // Transactions of the block saved during the state sync are not downloaded.
+        let is_synced = height > Height::zero() && height == first_height;
+        if !is_synced {
+            ensure!(
+                u64::from(block.tx_count()) == block_transactions.len(),
+                "Block has {} transactions, while {} are stored",
+                block.tx_count(),
+                block_transactions.len()
+            );
+            ensure!(
+                *block.tx_hash() == block_transactions.merkle_root(),
+                "Block `tx_hash` does not match the stored transactions"
+            );
+            let transactions = self.transactions();
+            for tx_hash in &block_transactions {
+                ensure!(
+                    transactions.contains(&tx_hash),
+                    "Transaction {:?} is missing",
+                    tx_hash
+                );
+            }
+        }
+
+        if height > Height::zero() {
+            let precommits = self.precommits(&block_hash).iter().collect::<Vec<_>>();
+            for precommit in &precommits {
+                let raw = precommit.signed_message().raw().to_vec();
+                Precommit::verify_precommit(raw).map_err(|e| {
+                    format_err!(
+                        "Precommit from validator {} is not verified: {}",
+                        precommit.validator(),
+                        e
+                    )
+                })?;
+            }
+            let config = self.configuration_by_height(height);
+            BlockProof { block, precommits }.verify(&config.validator_keys)?;
+        }
+        Ok(())
+    }
+
     /// Returns the latest committed block.
     ///
     /// # Panics
@@ -285,7 +514,7 @@ where
             len > 0,
             "An attempt to get the actual `height` during creating the genesis block."
         );
-        Height(len - 1)
+        Height(self.first_block_height().0 + len - 1)
     }
 
     /// Returns the configuration for the latest height of the blockchain.
@@ -355,12 +584,74 @@ where
 
     /// Returns the `state_hash` table for core tables.
     pub fn core_state_hash(&self) -> Vec<Hash> {
+        self.core_state_tables()
+            .into_iter()
+            // The `last_proposals` table is the last one, so skipping it does not change
+            // the keys of the other tables in the state hash aggregator.
+            .filter(|(name, root)| *name != LAST_PROPOSALS || *root != Hash::zero())
+            .map(|(_, root)| root)
+            .collect()
+    }
+
+    /// Returns names of the core tables, whose root hashes may be returned by `core_state_hash`.
+    pub(crate) fn core_state_table_names(&self) -> Vec<String> {
+        self.core_state_tables()
+            .into_iter()
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    /// Returns names and root hashes of the core tables in the order of their keys
+    /// in the state hash aggregator.
+    fn core_state_tables(&self) -> Vec<(&'static str, Hash)> {
         vec![
-            self.configs().merkle_root(),
-            self.transaction_results().merkle_root(),
+            (CONFIGS, self.configs().merkle_root()),
+            (
+                TRANSACTION_RESULTS,
+                self.transaction_results().merkle_root(),
+            ),
+            (AUTHOR_NONCES, self.author_nonces().merkle_root()),
+            (
+                SERVICE_SCHEMA_VERSIONS,
+                self.service_schema_versions().merkle_root(),
+            ),
+            (
+                EQUIVOCATION_EVIDENCE,
+                self.equivocation_evidence().merkle_root(),
+            ),
+            (LAST_PROPOSALS, self.last_proposals().merkle_root()),
         ]
     }
 
+    /// Checks that the transaction can be included into the block at the given height,
+    /// that is, the transaction has not expired and its nonce was not used by its author.
+    pub(crate) fn check_transaction(
+        &self,
+        tx: &dyn Transaction,
+        author: &PublicKey,
+        height: Height,
+    ) -> Result<(), TransactionError> {
+        if let Some(max_height) = tx.max_height() {
+            if height > max_height {
+                return Err(TransactionError::expired(Some(format!(
+                    "Maximum height {} is less than the block height {}",
+                    max_height, height
+                ))));
+            }
+        }
+        if let Some(nonce) = tx.nonce() {
+            if let Some(last_nonce) = self.author_nonces().get(author) {
+                if nonce <= last_nonce {
+                    return Err(TransactionError::invalid_nonce(Some(format!(
+                        "Nonce {} is not greater than the last nonce {} of the author",
+                        nonce, last_nonce
+                    ))));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Constructs a proof of inclusion of a root hash of a specific service
     /// table into the block `state_hash`.
     ///
@@ -411,7 +702,7 @@ where
     /// Returns the next height of the blockchain.
     /// Its value is equal to "height of the latest committed block" + 1.
     fn next_height(&self) -> Height {
-        Height(self.block_hashes_by_height().len())
+        Height(self.first_block_height().0 + self.block_hashes_by_height().len())
     }
 }
 
@@ -432,6 +723,25 @@ impl<'a> Schema<&'a mut Fork> {
         ProofMapIndex::new(TRANSACTION_RESULTS, self.view)
     }
 
+    /// Mutable reference to the [`author_nonces`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.author_nonces
+    pub(crate) fn author_nonces_mut(&mut self) -> ProofMapIndex<&mut Fork, PublicKey, u64> {
+        ProofMapIndex::new(AUTHOR_NONCES, self.view)
+    }
+
+    /// Records that the block at the given height is proposed by the validator with
+    /// the given consensus key.
+    pub(crate) fn set_last_proposal(&mut self, consensus_key: &PublicKey, height: Height) {
+        ProofMapIndex::new(LAST_PROPOSALS, &mut *self.view).put(consensus_key, height.0);
+    }
+
+    /// Records the schema version of the data of the service with the given name.
+    pub(crate) fn set_service_schema_version(&mut self, service_name: &str, version: u32) {
+        ProofMapIndex::new(SERVICE_SCHEMA_VERSIONS, &mut *self.view)
+            .put(&crypto::hash(service_name.as_bytes()), version);
+    }
+
     /// Mutable reference to the [`transactions_pool`][1] index.
     ///
     /// [1]: struct.Schema.html#method.transactions_pool
@@ -478,6 +788,25 @@ impl<'a> Schema<&'a mut Fork> {
         ProofListIndex::new_in_family(BLOCK_TRANSACTIONS, &height, self.view)
     }
 
+    /// Mutable reference to the [`block_events`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.block_events
+    pub(crate) fn block_events_mut(&mut self, height: Height) -> ProofListIndex<&mut Fork, Event> {
+        let height: u64 = height.into();
+        ProofListIndex::new_in_family(BLOCK_EVENTS, &height, self.view)
+    }
+
+    /// Mutable reference to the [`archived_changes`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.archived_changes
+    pub(crate) fn archived_changes_mut(
+        &mut self,
+        height: Height,
+    ) -> ListIndex<&mut Fork, ArchivedChange> {
+        let height: u64 = height.into();
+        ListIndex::new_in_family(ARCHIVED_CHANGES, &height, self.view)
+    }
+
     /// Mutable reference to the [`precommits`][1] index.
     ///
     /// [1]: struct.Schema.html#method.precommits
@@ -523,12 +852,175 @@ impl<'a> Schema<&'a mut Fork> {
         ListIndex::new(CONSENSUS_MESSAGES_CACHE, self.view)
     }
 
+    /// Mutable reference to the [`equivocations`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.equivocations
+    pub(crate) fn equivocations_mut(&mut self) -> MapIndex<&mut Fork, Hash, Equivocation> {
+        MapIndex::new(EQUIVOCATIONS, self.view)
+    }
+
+    /// Records the evidence of an equivocation. Returns `false` if the evidence
+    /// has already been recorded.
+    pub(crate) fn add_equivocation(&mut self, equivocation: Equivocation) -> bool {
+        let hash = equivocation.hash();
+        let mut equivocations = self.equivocations_mut();
+        if equivocations.contains(&hash) {
+            return false;
+        }
+        equivocations.put(&hash, equivocation);
+        true
+    }
+
+    /// Commits the evidence of an equivocation to the [`equivocation_evidence`][1] table.
+    /// Returns `false` if the evidence has already been committed.
+    ///
+    /// [1]: struct.Schema.html#method.equivocation_evidence
+    pub(crate) fn add_equivocation_evidence(&mut self, equivocation: Equivocation) -> bool {
+        let hash = equivocation.hash();
+        let mut evidence = ProofMapIndex::new(EQUIVOCATION_EVIDENCE, &mut *self.view);
+        if evidence.contains(&hash) {
+            return false;
+        }
+        evidence.put(&hash, equivocation);
+        true
+    }
+
     /// Saves the given consensus round value into the storage.
     pub(crate) fn set_consensus_round(&mut self, round: Round) {
         let mut entry: Entry<&mut Fork, _> = Entry::new(CONSENSUS_ROUND, self.view);
         entry.set(round);
     }
 
+    /// Removes transaction bodies, lists of block transactions and precommits for all blocks
+    /// below the given height. Block headers, transaction locations and results are retained.
+    pub(crate) fn prune_blocks_below(&mut self, height: Height) {
+        let from = self.pruned_height();
+        if height <= from {
+            return;
+        }
+
+        for pruned in from.0..height.0 {
+            let pruned = Height(pruned);
+            let block_hash = match self.block_hash_by_height(pruned) {
+                Some(block_hash) => block_hash,
+                None => break,
+            };
+
+            let tx_hashes = self.block_transactions(pruned).iter().collect::<Vec<_>>();
+            let mut transactions = self.transactions_mut();
+            for tx_hash in &tx_hashes {
+                transactions.remove(tx_hash);
+            }
+            self.block_transactions_mut(pruned).clear();
+            self.precommits_mut(&block_hash).clear();
+        }
+
+        let mut entry: Entry<&mut Fork, u64> = Entry::new(PRUNED_HEIGHT, self.view);
+        entry.set(height.0);
+    }
+
+    /// Reverts changes made by the blocks above the given height, using the changes archived
+    /// during their commit. Changes made outside of block commits are not reverted.
+    ///
+    /// Returns `false` if the block at `height` is not committed yet, or if any later block
+    /// was committed without the archive mode. In this case the fork is left partially
+    /// reverted and should be discarded.
+    pub(crate) fn revert_blocks_above(&mut self, height: Height) -> bool {
+        let next_height = self.next_height();
+        if height < self.first_block_height() || height >= next_height {
+            return false;
+        }
+
+        // Changes of the later blocks are reverted first, so that the value of a key
+        // is taken from the earliest block which changed it after `height`.
+        for block_height in (height.next().0..next_height.0).rev() {
+            let changes = {
+                let changes = self.archived_changes(Height(block_height));
+                if changes.is_empty() {
+                    return false;
+                }
+                changes.iter().collect::<Vec<_>>()
+            };
+            for change in changes {
+                if change.existed {
+                    self.view.put(&change.index_name, change.key, change.value);
+                } else {
+                    self.view.remove(&change.index_name, change.key);
+                }
+            }
+        }
+        true
+    }
+
+    /// Rolls the blockchain state back to the one right after the block at the given height
+    /// was committed. The later blocks are removed together with their transaction locations,
+    /// results and all other changes made during their commit, while their transactions
+    /// are returned to the pool.
+    ///
+    /// The consensus messages cache and the consensus round are reset, so that the node
+    /// can resume consensus from the height following `height`.
+    pub(crate) fn rollback(&mut self, height: Height) -> Result<(), failure::Error> {
+        let next_height = self.next_height();
+        ensure!(
+            height < next_height,
+            "Block at height {} is not committed",
+            height
+        );
+        ensure!(
+            height >= self.first_block_height(),
+            "Block at height {} is below the height obtained by the state sync",
+            height
+        );
+        ensure!(
+            self.revert_blocks_above(height),
+            "Some blocks above height {} were committed without the archive mode",
+            height
+        );
+
+        for removed in height.next().0..next_height.0 {
+            self.archived_changes_mut(Height(removed)).clear();
+        }
+        self.consensus_messages_cache_mut().clear();
+        self.set_consensus_round(Round::first());
+        // The pool might be changed after the commit of the latest block, so its length
+        // is recalculated rather than reverted.
+        let pool_len = self.transactions_pool().iter().count() as u64;
+        self.transactions_pool_len_index_mut().set(pool_len);
+        Ok(())
+    }
+
+    /// Saves the block, which state has been downloaded from peers, as the latest one.
+    ///
+    /// Headers of the blocks below the given one are unknown, so the block becomes
+    /// the [first known block](struct.Schema.html#method.first_block_height) and data for all
+    /// the previous blocks is marked as pruned. The list of configuration heights is rebuilt
+    /// from the downloaded configurations.
+    pub(crate) fn commit_synced_block(&mut self, block: &Block, precommits: &[Signed<Precommit>]) {
+        let height = block.height();
+        let block_hash = block.hash();
+        {
+            let mut block_hashes = self.block_hashes_by_height_mut();
+            block_hashes.clear();
+            block_hashes.push(block_hash);
+        }
+        let mut entry: Entry<&mut Fork, u64> = Entry::new(FIRST_BLOCK_HEIGHT, self.view);
+        entry.set(height.0);
+        self.blocks_mut().put(&block_hash, block.clone());
+        self.precommits_mut(&block_hash)
+            .extend(precommits.iter().cloned());
+
+        let mut configs = self.configs().values().collect::<Vec<_>>();
+        configs.sort_by_key(|config| config.actual_from);
+        let mut configs_actual_from = self.configs_actual_from_mut();
+        configs_actual_from.clear();
+        for config in configs {
+            configs_actual_from.push(ConfigReference::new(config.actual_from, &config.hash()));
+        }
+
+        let mut entry: Entry<&mut Fork, u64> = Entry::new(PRUNED_HEIGHT, self.view);
+        entry.set(height.0);
+    }
+
     /// Adds a new configuration to the blockchain, which will become actual at
     /// the `actual_from` height in `config_data`.
     pub fn commit_configuration(&mut self, config_data: StoredConfiguration) {
@@ -582,8 +1074,18 @@ impl<'a> Schema<&'a mut Fork> {
         self.transactions_pool_mut().remove(hash);
     }
 
-    /// Removes transaction from the persistent pool.
-    #[cfg(test)]
+    /// Removes all transactions from the persistent pool together with their bodies.
+    pub(crate) fn clear_transactions_pool(&mut self) {
+        let pool = self.transactions_pool().iter().collect::<Vec<_>>();
+        let mut transactions = self.transactions_mut();
+        for hash in &pool {
+            transactions.remove(hash);
+        }
+        self.transactions_pool_mut().clear();
+        self.transactions_pool_len_index_mut().set(0);
+    }
+
+    /// Removes transaction from the persistent pool together with its body.
     pub(crate) fn reject_transaction(&mut self, hash: &Hash) -> Result<(), ()> {
         let contains = self.transactions_pool_mut().contains(hash);
         self.transactions_pool_mut().remove(hash);
@@ -156,6 +156,18 @@ pub trait Service: Send + Sync + 'static {
     /// [2]: struct.Blockchain.html#method.service_table_unique_key
     fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash>;
 
+    /// Returns names of the indexes whose root hashes are returned by
+    /// [`state_hash`](#tymethod.state_hash), in the same order. The names are used by
+    /// the state sync to download the service tables from peers. Only ordinary (i.e., not
+    /// belonging to a family) `ProofMapIndex`es with `Hash` or `PublicKey` keys and
+    /// `ProofListIndex`es can be downloaded.
+    ///
+    /// *Default implementation returns `None`, which disables the state sync for the nodes
+    /// running this service if the service influences the blockchain state*
+    fn state_table_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
     /// Tries to create a `Transaction` from the given raw message.
     ///
     /// Exonum framework only guarantees that `SERVICE_ID` of the message is equal to the
@@ -213,6 +225,75 @@ pub trait Service: Send + Sync + 'static {
     ///
     /// *Default implementation does nothing*
     fn wire_api(&self, _builder: &mut ServiceApiBuilder) {}
+
+    /// Returns the priority of the transaction in the transaction pool of the node.
+    /// Transactions with a higher priority are included into proposals first and are
+    /// evicted from a full pool last. This method is invoked only for transactions which
+    /// were successfully deserialized by [`tx_from_raw`](#tymethod.tx_from_raw).
+    ///
+    /// *Default implementation returns `0` for all transactions*
+    fn tx_priority(&self, snapshot: &dyn Snapshot, tx: &Signed<RawTransaction>) -> u64 {
+        0
+    }
+
+    /// Returns the latest version of the service data layout supported by the service.
+    /// The data created by [`initialize`](#method.initialize) must correspond to this version.
+    ///
+    /// *Default implementation returns `0`*
+    fn schema_version(&self) -> u32 {
+        0
+    }
+
+    /// Returns the migrations of the service data between schema versions.
+    ///
+    /// Migrations are run when the schema version of the service specified in
+    /// the `service_versions` of the actual configuration exceeds the version
+    /// recorded in the blockchain. Migrations to all the intermediate versions
+    /// are run in the increasing order of versions before executing transactions
+    /// of the block at which the configuration becomes actual.
+    ///
+    /// *Default implementation returns no migrations*
+    fn migrations(&self) -> Vec<Migration> {
+        Vec::new()
+    }
+}
+
+/// Migration of the service data from the previous schema version to the given one.
+pub struct Migration {
+    version: u32,
+    step: Box<dyn Fn(&mut Fork) + Send + Sync>,
+}
+
+impl Migration {
+    /// Creates a migration to the given schema version.
+    pub fn new<F>(version: u32, step: F) -> Self
+    where
+        F: Fn(&mut Fork) + Send + Sync + 'static,
+    {
+        assert!(version > 0, "Migration to the initial schema version");
+        Self {
+            version,
+            step: Box::new(step),
+        }
+    }
+
+    /// Returns the schema version which the service data has after the migration.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Transforms the service data in the fork.
+    pub(crate) fn run(&self, fork: &mut Fork) {
+        (self.step)(fork)
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .finish()
+    }
 }
 
 /// The current node state on which the blockchain is running, or in other words
@@ -546,6 +627,33 @@ impl SharedNodeState {
             })
         }
     }
+
+    /// Notifies subscribers that the transaction has been added into the pool.
+    pub(crate) fn broadcast_tx_added(&self, tx_hash: &Hash) {
+        if let Some(ref address) = self
+            .state
+            .read()
+            .expect("Expected read lock")
+            .broadcast_server_address
+        {
+            address.do_send(websocket::TransactionAdded { tx_hash: *tx_hash })
+        }
+    }
+
+    /// Notifies subscribers that the transaction submitted to the node has been rejected.
+    pub(crate) fn broadcast_tx_rejected(&self, tx_hash: &Hash, description: String) {
+        if let Some(ref address) = self
+            .state
+            .read()
+            .expect("Expected read lock")
+            .broadcast_server_address
+        {
+            address.do_send(websocket::TransactionRejected {
+                tx_hash: *tx_hash,
+                description,
+            })
+        }
+    }
 }
 
 impl<'a, S: Service> From<S> for Box<dyn Service + 'a> {
@@ -14,19 +14,28 @@
 
 #![allow(dead_code, unsafe_code)]
 
+use chrono::{TimeZone, Utc};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
+use std::{fs, iter, path::Path};
+
 use crate::blockchain::{
-    Blockchain, ExecutionResult, Schema, Service, Transaction, TransactionContext, TransactionSet,
+    restore_backup, BackupInfo, Blockchain, Equivocation, ExecutionResult, LeaderElection,
+    LeaderElectionStrategy, Migration, ReportEquivocation, ReportEquivocationError, Schema,
+    Service, Transaction, TransactionContext, TransactionErrorType, TransactionSet,
 };
-use crate::crypto::{gen_keypair, Hash};
-use crate::helpers::{Height, ValidatorId};
-use crate::messages::{Message, RawTransaction};
+use crate::crypto::{self, gen_keypair, CryptoHash, Hash};
+use crate::helpers::{self, Height, Round, ValidatorId};
+use crate::messages::{Message, Precommit, RawTransaction, Signed};
+use crate::node::NodeConfig;
 use crate::proto;
-use crate::storage::{Database, Error, Fork, ListIndex, Snapshot};
+use crate::storage::{
+    self, Database, DbOptions, Error, Fork, ListIndex, MapIndex, RocksDB, Snapshot,
+};
 
 const IDX_NAME: &'static str = "idx_name";
 const TEST_SERVICE_ID: u16 = 255;
+const MIGRATED_IDX_NAME: &'static str = "migrated_idx_name";
 
 struct TestService;
 
@@ -60,10 +69,27 @@ impl Tx {
     }
 }
 
+#[derive(Serialize, Deserialize, ProtobufConvert, Debug, Clone)]
+#[exonum(pb = "proto::schema::tests::BlockchainTestTxB", crate = "crate")]
+struct LimitedTx {
+    b: u64,
+    c: u32,
+}
+
+impl LimitedTx {
+    fn new(max_height: u64, nonce: u32) -> Self {
+        Self {
+            b: max_height,
+            c: nonce,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, TransactionSet, Debug)]
 #[exonum(crate = "crate")]
 enum TestServiceTxs {
     Tx(Tx),
+    LimitedTx(LimitedTx),
 }
 
 impl Transaction for Tx {
@@ -78,6 +104,20 @@ impl Transaction for Tx {
     }
 }
 
+impl Transaction for LimitedTx {
+    fn execute(&self, _: TransactionContext) -> ExecutionResult {
+        Ok(())
+    }
+
+    fn max_height(&self) -> Option<Height> {
+        Some(Height(self.b))
+    }
+
+    fn nonce(&self) -> Option<u64> {
+        Some(u64::from(self.c))
+    }
+}
+
 fn gen_tempdir_name() -> String {
     thread_rng().sample_iter(&Alphanumeric).take(10).collect()
 }
@@ -161,6 +201,638 @@ fn handling_tx_panic_storage_error(blockchain: &mut Blockchain) {
     );
 }
 
+fn pruning_old_blocks(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    assert!(blockchain.set_keep_blocks(Some(0)).is_err());
+    blockchain.set_keep_blocks(Some(2)).unwrap();
+    blockchain.initialize(genesis).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let txs = (1..4)
+        .map(|value| Message::sign_transaction(Tx::new(value), TEST_SERVICE_ID, pk, &sec_key))
+        .collect::<Vec<_>>();
+
+    for (height, tx) in (1..4).map(Height).zip(&txs) {
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(tx.clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.height(), Height(3));
+    assert_eq!(schema.pruned_height(), Height(2));
+    assert!(schema.is_pruned(Height(1)));
+    assert!(!schema.is_pruned(Height(2)));
+
+    // Transaction bodies and block transactions are removed only for the pruned blocks.
+    assert!(schema.transactions().get(&txs[0].hash()).is_none());
+    assert!(schema.block_transactions(Height(1)).is_empty());
+    assert_eq!(
+        schema.transactions().get(&txs[1].hash()),
+        Some(txs[1].clone())
+    );
+    assert_eq!(schema.block_transactions(Height(2)).len(), 1);
+    // Locations and results of the pruned transactions are retained.
+    assert!(schema.transactions_locations().contains(&txs[0].hash()));
+    assert!(schema.transaction_results().contains(&txs[0].hash()));
+    assert!(schema.block_hash_by_height(Height(0)).is_some());
+}
+
+fn sign_precommit(config: &NodeConfig, height: Height, block_hash: Hash) -> Signed<Precommit> {
+    Message::concrete(
+        Precommit::new(
+            ValidatorId::zero(),
+            height,
+            Round::first(),
+            &crypto::hash(&[]),
+            &block_hash,
+            Utc::now(),
+        ),
+        config.consensus_public_key,
+        &config.consensus_secret_key,
+    )
+}
+
+fn chain_verification(blockchain: &mut Blockchain) {
+    let config = helpers::generate_testnet_config(1, 0)[0].clone();
+    blockchain.initialize(config.genesis.clone()).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    for height in (1..3).map(Height) {
+        let tx = Message::sign_transaction(Tx::new(height.0), TEST_SERVICE_ID, pk, &sec_key);
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(tx.clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+        let precommit = sign_precommit(&config, height, block_hash);
+        blockchain
+            .commit(&patch, block_hash, iter::once(precommit))
+            .unwrap();
+    }
+
+    {
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        for height in (0..3).map(Height) {
+            assert!(schema.verify_block(height).is_ok());
+        }
+    }
+
+    // Commit a block without precommits and corrupt the list of transactions
+    // of an earlier block.
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(3), &[]);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+    let mut fork = blockchain.fork();
+    Schema::new(&mut fork)
+        .block_transactions_mut(Height(1))
+        .push(Hash::zero());
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert!(schema.verify_block(Height(0)).is_ok());
+    assert!(schema.verify_block(Height(1)).is_err());
+    assert!(schema.verify_block(Height(2)).is_ok());
+    assert!(schema.verify_block(Height(3)).is_err());
+}
+
+fn backup_and_restore(blockchain: &mut Blockchain, dir: &Path) {
+    // Directories of failed backups are removed.
+    let failed_path = dir.join("uninitialized");
+    assert!(blockchain.create_backup(&failed_path).is_err());
+    assert!(!failed_path.exists());
+
+    let config = helpers::generate_testnet_config(1, 0)[0].clone();
+    blockchain.initialize(config.genesis.clone()).unwrap();
+
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
+    let precommit = sign_precommit(&config, Height(1), block_hash);
+    blockchain
+        .commit(&patch, block_hash, iter::once(precommit))
+        .unwrap();
+
+    let backup_path = dir.join("backup");
+    let info = blockchain.create_backup(&backup_path).unwrap();
+    assert_eq!(info.height, Height(1));
+    assert_eq!(info.block_hash, block_hash);
+    // Existing directories are not overwritten.
+    assert!(blockchain.create_backup(&backup_path).is_err());
+
+    let backup_files = || {
+        let mut files = fs::read_dir(backup_path.join("db"))
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.file_name(), entry.metadata().unwrap().len())
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+    let files = backup_files();
+
+    let db_path = dir.join("restored");
+    assert_eq!(restore_backup(&backup_path, &db_path).unwrap(), info);
+    assert!(restore_backup(&backup_path, &db_path).is_err());
+    // The backup itself is not modified by the restoration.
+    assert_eq!(backup_files(), files);
+
+    let db = RocksDB::open(&db_path, &DbOptions::default()).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(Schema::new(&snapshot).last_block().hash(), block_hash);
+
+    // Directories of failed restorations are removed.
+    let tampered_info = BackupInfo {
+        height: Height(2),
+        ..info
+    };
+    fs::write(
+        backup_path.join("backup.json"),
+        serde_json::to_string(&tampered_info).unwrap(),
+    )
+    .unwrap();
+    let failed_path = dir.join("tampered");
+    assert!(restore_backup(&backup_path, &failed_path).is_err());
+    assert!(!failed_path.exists());
+}
+
+fn leader_election(blockchain: &mut Blockchain) {
+    let skip_inactive = LeaderElectionStrategy::SkipInactive {
+        missed_proposals: 2,
+    };
+    let mut genesis = helpers::generate_testnet_config(4, 0)[0].genesis.clone();
+    genesis.consensus.leader_election = skip_inactive;
+    let consensus_keys = genesis
+        .validator_keys
+        .iter()
+        .map(|keys| keys.consensus_key)
+        .collect::<Vec<_>>();
+    blockchain.initialize(genesis).unwrap();
+    let state_tables = Schema::new(&blockchain.snapshot()).core_state_hash().len();
+    let all_validators = (0..4).map(ValidatorId).collect::<Vec<_>>();
+    let active_validators = (0..3).map(ValidatorId).collect::<Vec<_>>();
+
+    for height in 1..20 {
+        let snapshot = blockchain.snapshot();
+        assert_eq!(
+            LeaderElectionStrategy::RoundRobin.eligible_leaders(snapshot.as_ref()),
+            all_validators
+        );
+        // There are not enough blocks to judge validators before the height 8, while
+        // heights 8..12 are the probe ones.
+        let expected = if height < 8 || height % 12 >= 8 {
+            &all_validators
+        } else {
+            &active_validators
+        };
+        assert_eq!(skip_inactive.eligible_leaders(snapshot.as_ref()), *expected);
+
+        // The last validator never proposes blocks.
+        let proposer = ValidatorId((height % 3) as u16);
+        let (block_hash, patch) = blockchain.create_patch(proposer, Height(height), &[]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    // Proposers are recorded in the state, so the election does not depend on block headers.
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    let last_proposals = schema.last_proposals();
+    let expected = [Some(18), Some(19), Some(17), None];
+    for (key, expected) in consensus_keys.iter().zip(&expected) {
+        assert_eq!(last_proposals.get(key), *expected);
+    }
+    assert_eq!(schema.core_state_hash().len(), state_tables + 1);
+}
+
+fn last_proposals_with_round_robin(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(4, 0)[0].genesis.clone();
+    assert!(genesis.consensus.leader_election.is_round_robin());
+    blockchain.initialize(genesis).unwrap();
+    let state_tables = Schema::new(&blockchain.snapshot()).core_state_hash().len();
+
+    for height in 1..5 {
+        let proposer = ValidatorId((height % 4) as u16);
+        let (block_hash, patch) = blockchain.create_patch(proposer, Height(height), &[]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    // Proposers are not recorded and the table is not a part of the state hash.
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.last_proposals().iter().count(), 0);
+    assert_eq!(schema.core_state_hash().len(), state_tables);
+    assert_eq!(schema.core_state_table_names().len(), state_tables + 1);
+}
+
+fn equivocation_evidence(blockchain: &mut Blockchain) {
+    let configs = helpers::generate_testnet_config(4, 0);
+    blockchain.initialize(configs[0].genesis.clone()).unwrap();
+
+    // Precommits for the same proposal conflict if they commit different blocks.
+    let precommit = sign_precommit(&configs[0], Height(1), crypto::hash(&[1]));
+    let same_precommit = sign_precommit(&configs[0], Height(1), crypto::hash(&[1]));
+    let conflicting_precommit = sign_precommit(&configs[0], Height(1), crypto::hash(&[2]));
+    assert_eq!(
+        precommit.propose_hash(),
+        conflicting_precommit.propose_hash()
+    );
+    assert!(Equivocation::from_precommits(precommit.clone(), same_precommit).is_none());
+    let evidence = Equivocation::from_precommits(precommit, conflicting_precommit).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let report_by_stranger = ReportEquivocation::sign(evidence.clone(), &pk, &sec_key);
+    let report = |config: &NodeConfig| {
+        ReportEquivocation::sign(
+            evidence.clone(),
+            &config.service_public_key,
+            &config.service_secret_key,
+        )
+    };
+    let txs = [report_by_stranger, report(&configs[1]), report(&configs[2])];
+
+    let mut fork = blockchain.fork();
+    {
+        let mut schema = Schema::new(&mut fork);
+        for tx in &txs {
+            schema.add_transaction_into_pool(tx.clone());
+        }
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+    let hashes = txs.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(1), &hashes);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    let error_type = |hash| {
+        schema
+            .transaction_results()
+            .get(&hash)
+            .unwrap()
+            .0
+            .err()
+            .map(|e| e.error_type())
+    };
+    assert_eq!(
+        error_type(hashes[0]),
+        Some(TransactionErrorType::Code(
+            ReportEquivocationError::UnknownReporter as u8
+        ))
+    );
+    assert_eq!(error_type(hashes[1]), None);
+    assert_eq!(
+        error_type(hashes[2]),
+        Some(TransactionErrorType::Code(
+            ReportEquivocationError::AlreadyRecorded as u8
+        ))
+    );
+
+    let recorded = schema.equivocation_evidence();
+    assert_eq!(recorded.iter().count(), 1);
+    assert_eq!(recorded.get(&evidence.hash()), Some(evidence));
+}
+
+fn block_time(blockchain: &mut Blockchain) {
+    // The genesis block has the creation time of the genesis configuration.
+    let mut genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    genesis.time = Utc.timestamp(1_000_000_000, 0);
+    blockchain.initialize(genesis).unwrap();
+    assert_eq!(
+        blockchain.last_block().time(),
+        Utc.timestamp(1_000_000_000, 0)
+    );
+
+    let time = Utc.timestamp(1_500_000_000, 0);
+    let (block_hash, patch) =
+        blockchain.create_patch_with_time(ValidatorId::zero(), Height(1), &[], time);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+    assert_eq!(blockchain.last_block().time(), time);
+
+    // The time of the previous block is used if it is not specified.
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), Height(2), &[]);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+    assert_eq!(blockchain.last_block().time(), time);
+}
+
+fn archive_snapshots(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    blockchain.initialize(genesis).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let txs = (1..5)
+        .map(|value| Message::sign_transaction(Tx::new(value), TEST_SERVICE_ID, pk, &sec_key))
+        .collect::<Vec<_>>();
+
+    for (height, tx) in (1..5).map(Height).zip(&txs) {
+        // The first block is committed without the archive mode.
+        blockchain.set_archive(height > Height(1));
+
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(tx.clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    for height in 1..5 {
+        let snapshot = blockchain.snapshot_at(Height(height)).unwrap();
+        let schema = Schema::new(&snapshot);
+        assert_eq!(schema.height(), Height(height));
+        assert_eq!(
+            schema.last_block().state_hash(),
+            &schema.state_hash_aggregator().merkle_root()
+        );
+
+        let index: ListIndex<_, u64> = ListIndex::new(IDX_NAME, &snapshot);
+        let expected = (1..=height).flat_map(|value| vec![value, 42 / value]);
+        assert!(index.iter().eq(expected));
+
+        let location = schema
+            .transactions_locations()
+            .get(&txs[height as usize - 1].hash());
+        assert_eq!(location.unwrap().block_height(), Height(height));
+        if height < 4 {
+            assert!(!schema
+                .transactions_locations()
+                .contains(&txs[height as usize].hash()));
+        }
+    }
+
+    // The state before the first archived block is not available.
+    assert!(blockchain.snapshot_at(Height(0)).is_none());
+    assert!(blockchain.snapshot_at(Height(5)).is_none());
+}
+
+fn rollback_blocks(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    blockchain.initialize(genesis).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let txs = (1..5)
+        .map(|value| Message::sign_transaction(Tx::new(value), TEST_SERVICE_ID, pk, &sec_key))
+        .collect::<Vec<_>>();
+
+    for (height, tx) in (1..5).map(Height).zip(&txs) {
+        // The first block is committed without the archive mode.
+        blockchain.set_archive(height > Height(1));
+
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool(tx.clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    // Blocks committed without the archive mode cannot be reverted.
+    let mut fork = blockchain.fork();
+    assert!(Schema::new(&mut fork).rollback(Height(0)).is_err());
+    let mut fork = blockchain.fork();
+    assert!(Schema::new(&mut fork).rollback(Height(5)).is_err());
+
+    let mut fork = blockchain.fork();
+    Schema::new(&mut fork).rollback(Height(2)).unwrap();
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    {
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        assert_eq!(schema.height(), Height(2));
+        assert!(schema.archived_changes(Height(3)).is_empty());
+
+        let index: ListIndex<_, u64> = ListIndex::new(IDX_NAME, &snapshot);
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![1, 42, 2, 21]);
+
+        // Transactions of the removed blocks are returned to the pool.
+        assert_eq!(schema.transactions_pool_len(), 2);
+        for tx in &txs[2..] {
+            assert!(schema.transactions_pool().contains(&tx.hash()));
+            assert!(!schema.transactions_locations().contains(&tx.hash()));
+            assert!(!schema.transaction_results().contains(&tx.hash()));
+        }
+    }
+
+    // The blockchain can be continued from the rolled back height.
+    let (block_hash, patch) =
+        blockchain.create_patch(ValidatorId::zero(), Height(3), &[txs[2].hash()]);
+    blockchain
+        .commit(&patch, block_hash, iter::empty())
+        .unwrap();
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.height(), Height(3));
+    assert_eq!(schema.transactions_pool_len(), 1);
+}
+
+fn tx_validity_checks(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    blockchain.initialize(genesis).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let sign = |tx: LimitedTx| Message::sign_transaction(tx, TEST_SERVICE_ID, pk, &sec_key);
+    let tx_ok1 = sign(LimitedTx::new(5, 1));
+    let tx_expired = sign(LimitedTx::new(0, 2));
+    let tx_replayed = sign(LimitedTx::new(4, 1));
+    let tx_ok2 = sign(LimitedTx::new(5, 2));
+    let blocks = [vec![&tx_ok1, &tx_expired], vec![&tx_replayed, &tx_ok2]];
+
+    for (height, txs) in (1..).map(Height).zip(&blocks) {
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            for tx in txs {
+                schema.add_transaction_into_pool((*tx).clone());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let hashes = txs.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &hashes);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    let error_type = |hash| {
+        schema
+            .transaction_results()
+            .get(&hash)
+            .unwrap()
+            .0
+            .err()
+            .map(|e| e.error_type())
+    };
+    assert_eq!(error_type(tx_ok1.hash()), None);
+    assert_eq!(
+        error_type(tx_expired.hash()),
+        Some(TransactionErrorType::Expired)
+    );
+    assert_eq!(
+        error_type(tx_replayed.hash()),
+        Some(TransactionErrorType::InvalidNonce)
+    );
+    assert_eq!(error_type(tx_ok2.hash()), None);
+    assert_eq!(schema.author_nonces().get(&pk), Some(2));
+}
+
+fn service_activation(blockchain: &mut Blockchain) {
+    let mut genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    genesis.inactive_services.push("some_service".to_owned());
+    blockchain.initialize(genesis).unwrap();
+
+    let activation_height = Height(2);
+    let mut fork = blockchain.fork();
+    {
+        let mut schema = Schema::new(&mut fork);
+        let mut config = schema.actual_configuration();
+        assert!(!config.services.contains_key("some_service"));
+        config.previous_cfg_hash = config.hash();
+        config.actual_from = activation_height;
+        config
+            .services
+            .insert("some_service".to_owned(), serde_json::Value::Null);
+        schema.commit_configuration(config);
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    for height in (1..4).map(Height) {
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &[]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+
+        // `before_commit` of the service is invoked only since the activation height.
+        let snapshot = blockchain.snapshot();
+        let index: ListIndex<_, u32> = ListIndex::new(IDX_NAME, &snapshot);
+        let expected_len = height.0.saturating_sub(activation_height.previous().0);
+        assert_eq!(index.len(), expected_len);
+    }
+}
+
+fn inactive_service_txs(blockchain: &mut Blockchain) {
+    let mut genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    genesis.inactive_services.push("test service".to_owned());
+    blockchain.initialize(genesis).unwrap();
+
+    let mut fork = blockchain.fork();
+    {
+        let mut schema = Schema::new(&mut fork);
+        let mut config = schema.actual_configuration();
+        config.previous_cfg_hash = config.hash();
+        config.actual_from = Height(2);
+        config
+            .services
+            .insert("test service".to_owned(), serde_json::Value::Null);
+        schema.commit_configuration(config);
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let (pk, sec_key) = gen_keypair();
+    let sign = |tx: LimitedTx| Message::sign_transaction(tx, TEST_SERVICE_ID, pk, &sec_key);
+    let tx_inactive = sign(LimitedTx::new(5, 1));
+    let tx_active = sign(LimitedTx::new(6, 1));
+
+    for (height, tx) in (1..).map(Height).zip(&[&tx_inactive, &tx_active]) {
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork).add_transaction_into_pool((*tx).clone());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let (block_hash, patch) =
+            blockchain.create_patch(ValidatorId::zero(), height, &[tx.hash()]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+    }
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    let result = schema
+        .transaction_results()
+        .get(&tx_inactive.hash())
+        .unwrap();
+    assert_eq!(
+        result.0.err().map(|e| e.error_type()),
+        Some(TransactionErrorType::InactiveService)
+    );
+    // The nonce of the transaction which was not executed is not used.
+    let result = schema.transaction_results().get(&tx_active.hash()).unwrap();
+    assert!(result.0.is_ok());
+    assert_eq!(schema.author_nonces().get(&pk), Some(1));
+}
+
+fn service_migration(blockchain: &mut Blockchain) {
+    let genesis = helpers::generate_testnet_config(1, 0)[0].genesis.clone();
+    blockchain.initialize(genesis).unwrap();
+
+    // Emulate the data of the service created with the initial schema version.
+    let migration_height = Height(2);
+    let mut fork = blockchain.fork();
+    {
+        ListIndex::new(MIGRATED_IDX_NAME, &mut fork).push(1_u64);
+        let mut schema = Schema::new(&mut fork);
+        assert_eq!(schema.service_schema_version("migrated_service"), Some(2));
+        schema.set_service_schema_version("migrated_service", 0);
+
+        let mut config = schema.actual_configuration();
+        config.previous_cfg_hash = config.hash();
+        config.actual_from = migration_height;
+        config
+            .service_versions
+            .insert("migrated_service".to_owned(), 2);
+        schema.commit_configuration(config);
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    for height in (1..3).map(Height) {
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &[]);
+        blockchain
+            .commit(&patch, block_hash, iter::empty())
+            .unwrap();
+
+        let snapshot = blockchain.snapshot();
+        let version = Schema::new(&snapshot).service_schema_version("migrated_service");
+        if height < migration_height {
+            assert_eq!(version, Some(0));
+            let index: ListIndex<_, u64> = ListIndex::new(MIGRATED_IDX_NAME, &snapshot);
+            assert_eq!(index.len(), 1);
+        } else {
+            assert_eq!(version, Some(2));
+            let index: MapIndex<_, u64, u64> = MapIndex::new(MIGRATED_IDX_NAME, &snapshot);
+            assert_eq!(index.iter().collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+        }
+    }
+}
+
 mod transactions_tests {
     use super::TEST_SERVICE_ID;
     use crate::blockchain::{ExecutionResult, Transaction, TransactionContext, TransactionSet};
@@ -342,6 +1014,49 @@ impl Service for ServicePanicStorageError {
     }
 }
 
+struct MigratedService;
+
+impl Service for MigratedService {
+    fn service_id(&self) -> u16 {
+        2
+    }
+
+    fn service_name(&self) -> &'static str {
+        "migrated_service"
+    }
+
+    fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+        vec![]
+    }
+
+    fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+        unimplemented!()
+    }
+
+    fn schema_version(&self) -> u32 {
+        2
+    }
+
+    fn migrations(&self) -> Vec<Migration> {
+        vec![
+            Migration::new(1, |fork| {
+                ListIndex::new(MIGRATED_IDX_NAME, fork).push(2_u64);
+            }),
+            // Replaces the list with the map from indexes to values.
+            Migration::new(2, |fork| {
+                let values = ListIndex::<_, u64>::new(MIGRATED_IDX_NAME, &*fork)
+                    .iter()
+                    .collect::<Vec<_>>();
+                storage::drop_index(MIGRATED_IDX_NAME, fork);
+                let mut index = MapIndex::new(MIGRATED_IDX_NAME, fork);
+                for (idx, value) in values.into_iter().enumerate() {
+                    index.put(&(idx as u64), value);
+                }
+            }),
+        ]
+    }
+}
+
 fn assert_service_execute(blockchain: &Blockchain, db: &mut Box<dyn Database>) {
     let (_, patch) = blockchain.create_patch(ValidatorId::zero(), Height(1), &[]);
     db.merge(patch).unwrap();
@@ -367,7 +1082,7 @@ mod memorydb_tests {
     use crate::node::ApiSender;
     use crate::storage::{Database, MemoryDB};
 
-    use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
+    use super::{MigratedService, ServiceGood, ServicePanic, ServicePanicStorageError};
 
     fn create_database() -> Box<dyn Database> {
         Box::new(MemoryDB::new())
@@ -410,6 +1125,78 @@ mod memorydb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn pruning_old_blocks() {
+        let mut blockchain = create_blockchain();
+        super::pruning_old_blocks(&mut blockchain);
+    }
+
+    #[test]
+    fn chain_verification() {
+        let mut blockchain = create_blockchain();
+        super::chain_verification(&mut blockchain);
+    }
+
+    #[test]
+    fn leader_election() {
+        let mut blockchain = create_blockchain();
+        super::leader_election(&mut blockchain);
+    }
+
+    #[test]
+    fn last_proposals_with_round_robin() {
+        let mut blockchain = create_blockchain();
+        super::last_proposals_with_round_robin(&mut blockchain);
+    }
+
+    #[test]
+    fn equivocation_evidence() {
+        let mut blockchain = create_blockchain();
+        super::equivocation_evidence(&mut blockchain);
+    }
+
+    #[test]
+    fn block_time() {
+        let mut blockchain = create_blockchain();
+        super::block_time(&mut blockchain);
+    }
+
+    #[test]
+    fn archive_snapshots() {
+        let mut blockchain = create_blockchain();
+        super::archive_snapshots(&mut blockchain);
+    }
+
+    #[test]
+    fn rollback_blocks() {
+        let mut blockchain = create_blockchain();
+        super::rollback_blocks(&mut blockchain);
+    }
+
+    #[test]
+    fn tx_validity_checks() {
+        let mut blockchain = create_blockchain();
+        super::tx_validity_checks(&mut blockchain);
+    }
+
+    #[test]
+    fn service_activation() {
+        let mut blockchain = create_blockchain_with_service(Box::new(ServiceGood));
+        super::service_activation(&mut blockchain);
+    }
+
+    #[test]
+    fn inactive_service_txs() {
+        let mut blockchain = create_blockchain();
+        super::inactive_service_txs(&mut blockchain);
+    }
+
+    #[test]
+    fn service_migration() {
+        let mut blockchain = create_blockchain_with_service(Box::new(MigratedService));
+        super::service_migration(&mut blockchain);
+    }
+
     #[test]
     fn service_execute() {
         let blockchain = create_blockchain_with_service(Box::new(ServiceGood));
@@ -444,7 +1231,7 @@ mod rocksdb_tests {
     use crate::node::ApiSender;
     use crate::storage::{Database, DbOptions, RocksDB};
 
-    use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
+    use super::{MigratedService, ServiceGood, ServicePanic, ServicePanicStorageError};
 
     fn create_database(path: &Path) -> Box<dyn Database> {
         let opts = DbOptions::default();
@@ -496,6 +1283,98 @@ mod rocksdb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn pruning_old_blocks() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::pruning_old_blocks(&mut blockchain);
+    }
+
+    #[test]
+    fn chain_verification() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::chain_verification(&mut blockchain);
+    }
+
+    #[test]
+    fn leader_election() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::leader_election(&mut blockchain);
+    }
+
+    #[test]
+    fn last_proposals_with_round_robin() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::last_proposals_with_round_robin(&mut blockchain);
+    }
+
+    #[test]
+    fn equivocation_evidence() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::equivocation_evidence(&mut blockchain);
+    }
+
+    #[test]
+    fn block_time() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::block_time(&mut blockchain);
+    }
+
+    #[test]
+    fn archive_snapshots() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::archive_snapshots(&mut blockchain);
+    }
+
+    #[test]
+    fn backup_and_restore() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        let backup_dir = create_temp_dir();
+        super::backup_and_restore(&mut blockchain, backup_dir.path());
+    }
+
+    #[test]
+    fn rollback_blocks() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::rollback_blocks(&mut blockchain);
+    }
+
+    #[test]
+    fn tx_validity_checks() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::tx_validity_checks(&mut blockchain);
+    }
+
+    #[test]
+    fn service_activation() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain_with_service(dir.path(), Box::new(ServiceGood));
+        super::service_activation(&mut blockchain);
+    }
+
+    #[test]
+    fn inactive_service_txs() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::inactive_service_txs(&mut blockchain);
+    }
+
+    #[test]
+    fn service_migration() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain_with_service(dir.path(), Box::new(MigratedService));
+        super::service_migration(&mut blockchain);
+    }
+
     #[test]
     fn service_execute() {
         let dir = create_temp_dir();
@@ -14,14 +14,17 @@
 
 //! `Transaction` related types.
 
+use chrono::{DateTime, Utc};
 use hex::ToHex;
 use protobuf::Message;
 use serde::{de::DeserializeOwned, Serialize};
 
 use std::{any::Any, borrow::Cow, convert::Into, error::Error, fmt, u8};
 
+use super::{Blockchain, Event, Schema, StoredConfiguration};
 use crate::crypto::{CryptoHash, Hash, PublicKey};
-use crate::messages::{HexStringRepresentation, RawTransaction, Signed, SignedMessage};
+use crate::helpers::Height;
+use crate::messages::{BinaryForm, HexStringRepresentation, RawTransaction, Signed, SignedMessage};
 use crate::proto::{self, ProtobufConvert};
 use crate::storage::{Fork, StorageValue};
 
@@ -32,6 +35,12 @@ const MAX_ERROR_CODE: u16 = u8::max_value() as u16;
 const TRANSACTION_STATUS_OK: u16 = MAX_ERROR_CODE + 1;
 // `Err(TransactionErrorType::Panic)`.
 const TRANSACTION_STATUS_PANIC: u16 = TRANSACTION_STATUS_OK + 1;
+// `Err(TransactionErrorType::Expired)`.
+const TRANSACTION_STATUS_EXPIRED: u16 = TRANSACTION_STATUS_PANIC + 1;
+// `Err(TransactionErrorType::InvalidNonce)`.
+const TRANSACTION_STATUS_INVALID_NONCE: u16 = TRANSACTION_STATUS_EXPIRED + 1;
+// `Err(TransactionErrorType::InactiveService)`.
+const TRANSACTION_STATUS_INACTIVE_SERVICE: u16 = TRANSACTION_STATUS_INVALID_NONCE + 1;
 
 /// Returns a result of the `Transaction` `execute` method. This result may be
 /// either an empty unit type, in case of success, or an `ExecutionError`, if execution has
@@ -171,26 +180,64 @@ pub trait Transaction: ::std::fmt::Debug + Send + 'static + ::erased_serde::Seri
     /// }
     /// # fn main() {}
     fn execute<'a>(&self, context: TransactionContext<'a>) -> ExecutionResult;
+
+    /// Returns the maximum height of the block that can include the transaction.
+    ///
+    /// Nodes do not accept the transaction into the pool or a proposal after the blockchain
+    /// has reached this height; if the transaction still gets into a block, it is not
+    /// executed and its result is `TransactionErrorType::Expired`.
+    ///
+    /// *Default implementation returns `None`, meaning that the transaction never expires.*
+    fn max_height(&self) -> Option<Height> {
+        None
+    }
+
+    /// Returns the sequence number of the transaction among the transactions of its author.
+    ///
+    /// The nonce must be greater than the nonce of any transaction of the same author
+    /// committed before. Otherwise, the transaction is not accepted into the pool or a proposal;
+    /// if the transaction still gets into a block, it is not executed and its result is
+    /// `TransactionErrorType::InvalidNonce`. Nonces of the committed transactions are stored
+    /// in the [`author_nonces`][1] index.
+    ///
+    /// *Default implementation returns `None`, meaning that the nonce is not checked.*
+    ///
+    /// [1]: struct.Schema.html#method.author_nonces
+    fn nonce(&self) -> Option<u64> {
+        None
+    }
 }
 
 //TODO: Add doc/examples.
 /// Wrapper around database and tx hash.
 #[derive(Debug)]
 pub struct TransactionContext<'a> {
+    blockchain: &'a Blockchain,
     fork: &'a mut Fork,
     service_id: u16,
     tx_hash: Hash,
     author: PublicKey,
+    height: Height,
+    time: DateTime<Utc>,
 }
 
 impl<'a> TransactionContext<'a> {
     #[doc(hidden)]
-    pub fn new(fork: &'a mut Fork, raw_message: &Signed<RawTransaction>) -> Self {
+    pub fn new(
+        blockchain: &'a Blockchain,
+        fork: &'a mut Fork,
+        raw_message: &Signed<RawTransaction>,
+        height: Height,
+        time: DateTime<Utc>,
+    ) -> Self {
         TransactionContext {
+            blockchain,
             fork,
             service_id: raw_message.service_id(),
             tx_hash: raw_message.hash(),
             author: raw_message.author(),
+            height,
+            time,
         }
     }
     /// Returns fork of current blockchain state.
@@ -210,6 +257,38 @@ impl<'a> TransactionContext<'a> {
     pub fn tx_hash(&self) -> Hash {
         self.tx_hash
     }
+    /// Returns the height of the block which includes the transaction.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+    /// Returns the time of the block which includes the transaction. The time is agreed
+    /// upon by validators, so it can be used in the transaction logic.
+    /// See [`block_time`] for details.
+    ///
+    /// [`block_time`]: fn.block_time.html
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+    /// Checks that the services of the blockchain support the schema versions specified
+    /// in the `service_versions` of the given configuration, i.e., that the configuration
+    /// can become actual without failing the migrations of the service data.
+    pub fn check_service_versions(
+        &self,
+        config: &StoredConfiguration,
+    ) -> Result<(), failure::Error> {
+        self.blockchain.check_service_versions(&*self.fork, config)
+    }
+    /// Emits an event with the given topic and the payload serialized with protobuf.
+    /// Events are stored in the blockchain only if the transaction is executed successfully.
+    pub fn emit_event<T: BinaryForm>(&mut self, topic: &str, payload: &T) {
+        let payload = payload
+            .encode()
+            .expect("Failed to serialize the event payload");
+        let event = Event::new(self.tx_hash, self.service_id, topic, payload);
+        Schema::new(&mut *self.fork)
+            .block_events_mut(self.height)
+            .push(event);
+    }
 }
 
 /// Result of unsuccessful transaction execution.
@@ -255,6 +334,12 @@ pub enum TransactionErrorType {
     /// User-defined error code. Can have different meanings for different transactions and
     /// services.
     Code(u8),
+    /// The transaction was included into a block after its maximum height.
+    Expired,
+    /// The nonce of the transaction is not greater than the last nonce of its author.
+    InvalidNonce,
+    /// The transaction was included into a block before its service became active.
+    InactiveService,
 }
 
 /// Result of unsuccessful transaction execution encompassing both service and framework-wide error
@@ -324,13 +409,30 @@ impl TransactionError {
         Self::new(TransactionErrorType::Panic, description)
     }
 
+    /// Creates a new `TransactionError` for a transaction included into a block after
+    /// its maximum height.
+    pub(crate) fn expired(description: Option<String>) -> Self {
+        Self::new(TransactionErrorType::Expired, description)
+    }
+
+    /// Creates a new `TransactionError` for a transaction with an outdated nonce.
+    pub(crate) fn invalid_nonce(description: Option<String>) -> Self {
+        Self::new(TransactionErrorType::InvalidNonce, description)
+    }
+
+    /// Creates a new `TransactionError` for a transaction of a service which is not active
+    /// at the height of the block.
+    pub(crate) fn inactive_service(description: Option<String>) -> Self {
+        Self::new(TransactionErrorType::InactiveService, description)
+    }
+
     /// Creates a new `TransactionError` instance from `std::thread::Result`'s `Err`.
     pub(crate) fn from_panic(panic: &Box<dyn Any + Send>) -> Self {
         Self::panic(panic_description(panic))
     }
 
     /// Returns an error type of this `TransactionError` instance. This can be
-    /// either a panic or a user-defined error code.
+    /// a panic, a user-defined error code or a validity check failure.
     pub fn error_type(&self) -> TransactionErrorType {
         self.error_type
     }
@@ -352,6 +454,9 @@ impl fmt::Display for TransactionError {
         match self.error_type {
             TransactionErrorType::Panic => write!(f, "Panic during execution")?,
             TransactionErrorType::Code(c) => write!(f, "Error code: {}", c)?,
+            TransactionErrorType::Expired => write!(f, "Transaction expired")?,
+            TransactionErrorType::InvalidNonce => write!(f, "Invalid nonce")?,
+            TransactionErrorType::InactiveService => write!(f, "Service is not active")?,
         }
 
         if let Some(ref description) = self.description {
@@ -404,6 +509,11 @@ impl ProtobufConvert for TransactionResult {
             value @ 0..=MAX_ERROR_CODE => Err(TransactionError::code(value as u8, description)),
             TRANSACTION_STATUS_OK => Ok(()),
             TRANSACTION_STATUS_PANIC => Err(TransactionError::panic(description)),
+            TRANSACTION_STATUS_EXPIRED => Err(TransactionError::expired(description)),
+            TRANSACTION_STATUS_INVALID_NONCE => Err(TransactionError::invalid_nonce(description)),
+            TRANSACTION_STATUS_INACTIVE_SERVICE => {
+                Err(TransactionError::inactive_service(description))
+            }
             value => bail!("Invalid TransactionResult value: {}", value),
         }))
     }
@@ -431,6 +541,9 @@ fn status_as_u16(status: &TransactionResult) -> u16 {
         Err(ref e) => match e.error_type {
             TransactionErrorType::Panic => TRANSACTION_STATUS_PANIC,
             TransactionErrorType::Code(c) => u16::from(c),
+            TransactionErrorType::Expired => TRANSACTION_STATUS_EXPIRED,
+            TransactionErrorType::InvalidNonce => TRANSACTION_STATUS_INVALID_NONCE,
+            TransactionErrorType::InactiveService => TRANSACTION_STATUS_INACTIVE_SERVICE,
         },
     }
 }
@@ -565,6 +678,10 @@ mod tests {
                 255,
                 Some("(Not) really long error description".to_owned()),
             )),
+            Err(TransactionError::expired(None)),
+            Err(TransactionError::invalid_nonce(Some(
+                "Nonce 1 is used".to_owned(),
+            ))),
         ]
         .iter()
         .map(|res| TransactionResult(res.to_owned()))
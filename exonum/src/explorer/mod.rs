@@ -28,7 +28,7 @@ use std::{
 };
 
 use crate::blockchain::{
-    Block, Blockchain, Schema, TransactionError, TransactionErrorType, TransactionMessage,
+    Block, Blockchain, Event, Schema, TransactionError, TransactionErrorType, TransactionMessage,
     TransactionResult, TxLocation,
 };
 use crate::crypto::{CryptoHash, Hash};
@@ -117,13 +117,11 @@ impl<'a> BlockInfo<'a> {
     fn new(explorer: &'a BlockchainExplorer, height: Height) -> Self {
         let schema = Schema::new(&explorer.snapshot);
         let header = {
-            let hashes = schema.block_hashes_by_height();
-            let blocks = schema.blocks();
-
-            let block_hash = hashes
-                .get(height.0)
+            let block_hash = schema
+                .block_hash_by_height(height)
                 .unwrap_or_else(|| panic!("Block not found, height: {:?}", height));
-            blocks
+            schema
+                .blocks()
                 .get(&block_hash)
                 .unwrap_or_else(|| panic!("Block not found, hash: {:?}", block_hash))
         };
@@ -445,14 +443,17 @@ pub struct CommittedTransaction {
 /// Transaction execution status. Simplified version of `TransactionResult`.
 #[serde(tag = "type", rename_all = "kebab-case")]
 #[derive(Debug, Serialize, Deserialize)]
-enum TxStatus<'a> {
+pub(crate) enum TxStatus<'a> {
     Success,
     Panic { description: &'a str },
     Error { code: u8, description: &'a str },
+    Expired { description: &'a str },
+    InvalidNonce { description: &'a str },
+    InactiveService { description: &'a str },
 }
 
 impl<'a> TxStatus<'a> {
-    fn serialize<S>(result: &TransactionResult, serializer: S) -> Result<S::Ok, S::Error>
+    pub(crate) fn serialize<S>(result: &TransactionResult, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
@@ -460,7 +461,7 @@ impl<'a> TxStatus<'a> {
         status.serialize(serializer)
     }
 
-    fn deserialize<D>(deserializer: D) -> Result<TransactionResult, D::Error>
+    pub(crate) fn deserialize<D>(deserializer: D) -> Result<TransactionResult, D::Error>
     where
         D: Deserializer<'a>,
     {
@@ -480,6 +481,9 @@ impl<'a> From<&'a TransactionResult> for TxStatus<'a> {
                 match e.error_type() {
                     Panic => TxStatus::Panic { description },
                     Code(code) => TxStatus::Error { code, description },
+                    Expired => TxStatus::Expired { description },
+                    InvalidNonce => TxStatus::InvalidNonce { description },
+                    InactiveService => TxStatus::InactiveService { description },
                 }
             }
         }
@@ -502,6 +506,15 @@ impl<'a> From<TxStatus<'a>> for TransactionResult {
             TxStatus::Error { code, description } => {
                 Err(TransactionError::code(code, to_option(description)))
             }
+            TxStatus::Expired { description } => {
+                Err(TransactionError::expired(to_option(description)))
+            }
+            TxStatus::InvalidNonce { description } => {
+                Err(TransactionError::invalid_nonce(to_option(description)))
+            }
+            TxStatus::InactiveService { description } => {
+                Err(TransactionError::inactive_service(to_option(description)))
+            }
         })
     }
 }
@@ -651,6 +664,17 @@ impl TransactionInfo {
     }
 }
 
+/// Event emitted by a committed transaction together with its location.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventInfo {
+    /// Height of the block which includes the transaction emitted the event.
+    pub height: Height,
+    /// Position of the event in the list of events of the block.
+    pub position: u64,
+    /// The event itself.
+    pub event: Event,
+}
+
 /// Blockchain explorer.
 ///
 /// # Notes
@@ -682,6 +706,22 @@ impl<'a> BlockchainExplorer<'a> {
         }
     }
 
+    /// Creates a new `BlockchainExplorer` instance for the state of the blockchain right after
+    /// the block at the given height was committed. Returns `None` if the state at this height
+    /// is not available; see [`Blockchain::snapshot_at`] for details.
+    ///
+    /// [`Blockchain::snapshot_at`]: ../blockchain/struct.Blockchain.html#method.snapshot_at
+    pub fn at_height(blockchain: &'a Blockchain, height: Height) -> Option<Self> {
+        let snapshot = blockchain.snapshot_at(height)?;
+        Some(BlockchainExplorer {
+            snapshot,
+            transaction_parser: Box::new(move |raw| {
+                let tx = blockchain.tx_from_raw(raw.payload().clone())?;
+                Ok(TransactionMessage::new(raw, tx))
+            }),
+        })
+    }
+
     /// Returns information about the transaction identified by the hash.
     pub fn transaction(&self, tx_hash: &Hash) -> Option<TransactionInfo> {
         let schema = Schema::new(&self.snapshot);
@@ -756,15 +796,42 @@ impl<'a> BlockchainExplorer<'a> {
         }
     }
 
+    /// Returns `true` if the transaction with the specified hash has been committed,
+    /// but its body has been removed from the storage by pruning.
+    pub fn is_transaction_pruned(&self, tx_hash: &Hash) -> bool {
+        let schema = Schema::new(&self.snapshot);
+        schema
+            .transactions_locations()
+            .get(tx_hash)
+            .map_or(false, |location| schema.is_pruned(location.block_height()))
+    }
+
+    /// Returns `true` if transactions and precommits of the block at the specified height
+    /// have been removed from the storage by pruning. The block header is retained anyway.
+    pub fn is_block_pruned(&self, height: Height) -> bool {
+        let schema = Schema::new(&self.snapshot);
+        schema.is_pruned(height)
+    }
+
     /// Returns the height of the blockchain.
     pub fn height(&self) -> Height {
         let schema = Schema::new(&self.snapshot);
         schema.height()
     }
 
-    /// Returns block information for the specified height or `None` if there is no such block.
+    /// Returns the height of the earliest block known to the node. Blocks below this height
+    /// are unavailable, because the node has obtained the blockchain state from its peers.
+    pub fn first_block_height(&self) -> Height {
+        let schema = Schema::new(&self.snapshot);
+        schema.first_block_height()
+    }
+
+    /// Returns block information for the specified height or `None` if there is no such block
+    /// or it lies below the [first known block](#method.first_block_height).
+    ///
+    /// Precommits and transactions of [pruned](#method.is_block_pruned) blocks are empty.
     pub fn block(&self, height: Height) -> Option<BlockInfo> {
-        if self.height() >= height {
+        if self.first_block_height() <= height && height <= self.height() {
             Some(BlockInfo::new(self, height))
         } else {
             None
@@ -772,7 +839,11 @@ impl<'a> BlockchainExplorer<'a> {
     }
 
     /// Returns block together with its transactions for the specified height, or `None`
-    /// if there is no such block.
+    /// if there is no such block or it lies below the [first known block].
+    ///
+    /// Precommits and transactions of [pruned](#method.is_block_pruned) blocks are empty.
+    ///
+    /// [first known block]: #method.first_block_height
     pub fn block_with_txs(&self, height: Height) -> Option<BlockWithTransactions> {
         let schema = Schema::new(&self.snapshot);
         let txs_table = schema.block_transactions(height);
@@ -788,7 +859,37 @@ impl<'a> BlockchainExplorer<'a> {
         })
     }
 
-    /// Iterates over blocks in the blockchain.
+    /// Returns the events emitted by transactions of the block at the specified height
+    /// in the order of emission.
+    pub fn block_events(&self, height: Height) -> Vec<EventInfo> {
+        let schema = Schema::new(&self.snapshot);
+        schema
+            .block_events(height)
+            .iter()
+            .enumerate()
+            .map(|(position, event)| EventInfo {
+                height,
+                position: position as u64,
+                event,
+            })
+            .collect()
+    }
+
+    /// Returns the events emitted by the committed transaction with the specified hash.
+    /// Returns an empty list if the transaction is not committed.
+    pub fn transaction_events(&self, tx_hash: &Hash) -> Vec<EventInfo> {
+        let schema = Schema::new(&self.snapshot);
+        let location = match schema.transactions_locations().get(tx_hash) {
+            Some(location) => location,
+            None => return Vec::new(),
+        };
+        let mut events = self.block_events(location.block_height());
+        events.retain(|info| info.event.tx_hash() == tx_hash);
+        events
+    }
+
+    /// Iterates over blocks in the blockchain. Blocks below the
+    /// [first known block](#method.first_block_height) are skipped.
     pub fn blocks<R: Into<HeightRange>>(&self, heights: R) -> Blocks {
         use std::cmp::max;
 
@@ -796,7 +897,7 @@ impl<'a> BlockchainExplorer<'a> {
         let schema = Schema::new(&self.snapshot);
         let max_height = schema.height();
 
-        let ptr = heights.start_height();
+        let ptr = max(heights.start_height(), schema.first_block_height());
         Blocks {
             explorer: self,
             ptr,
@@ -28,6 +28,7 @@ use super::{
     keys,
     maintenance::Maintenance,
     password::{PassInputMethod, SecretKeyType},
+    snapshot::{self, ExportSnapshot, ImportSnapshot},
     CommandName, Context, ServiceFactory,
 };
 
@@ -69,8 +70,12 @@ impl NodeBuilder {
         T: Into<OsString> + Clone,
     {
         let feedback = ClapBackend::execute_cmd_string(&self.commands, cmd_line);
-        if let Feedback::RunNode(ref ctx) = feedback {
-            self.node_from_run_context(ctx);
+        match feedback {
+            Feedback::RunNode(ref ctx) => {
+                self.node_from_run_context(ctx);
+            }
+            Feedback::ImportSnapshot(ref ctx) => self.import_snapshot(ctx),
+            Feedback::None => {}
         }
         feedback != Feedback::None
     }
@@ -82,7 +87,11 @@ impl NodeBuilder {
                 let node = self.node_from_run_context(ctx);
                 Some(node)
             }
-            _ => None,
+            Feedback::ImportSnapshot(ref ctx) => {
+                self.import_snapshot(ctx);
+                None
+            }
+            Feedback::None => None,
         }
     }
 
@@ -137,6 +146,8 @@ impl NodeBuilder {
             Box::new(GenerateCommonConfig),
             Box::new(Finalize),
             Box::new(Maintenance),
+            Box::new(ExportSnapshot),
+            Box::new(ImportSnapshot),
         ]
         .into_iter()
         .map(|c| (c.name(), CollectedCommand::new(c)))
@@ -174,6 +185,15 @@ impl NodeBuilder {
         };
         Node::new(db, services, config, Some(config_file_path))
     }
+
+    fn import_snapshot(self, ctx: &Context) {
+        let services: Vec<Box<dyn Service>> = self
+            .service_factories
+            .into_iter()
+            .map(|mut factory| factory.make_service(ctx))
+            .collect();
+        snapshot::import_snapshot_with_services(ctx, &services);
+    }
 }
 
 impl fmt::Debug for NodeBuilder {
@@ -17,6 +17,8 @@
 //! This module implement all core commands.
 // spell-checker:ignore exts, rsplitn
 
+use chrono::Utc;
+
 use std::{
     collections::{BTreeMap, HashMap},
     fs,
@@ -36,7 +38,10 @@ use super::{
     Argument, CommandName, Context, DEFAULT_EXONUM_LISTEN_PORT,
 };
 use crate::api::backends::actix::AllowOrigin;
-use crate::blockchain::{config::ValidatorKeys, GenesisConfig};
+use crate::blockchain::{
+    config::{ConsensusConfig, ValidatorKeys},
+    GenesisConfig,
+};
 use crate::crypto::{generate_keys_file, PublicKey};
 use crate::helpers::{config::ConfigFile, ZeroizeOnDrop};
 use crate::node::{ConnectListConfig, NodeApiConfig, NodeConfig};
@@ -55,6 +60,7 @@ const SERVICE_KEY_PATH: &str = "SERVICE_KEY_PATH";
 const NO_PASSWORD: &str = "NO_PASSWORD";
 const CONSENSUS_KEY_PASS_METHOD: &str = "CONSENSUS_KEY_PASS_METHOD";
 const SERVICE_KEY_PASS_METHOD: &str = "SERVICE_KEY_PASS_METHOD";
+const GENESIS_TIME: &str = "GENESIS_TIME";
 
 /// Run command.
 pub struct Run;
@@ -352,6 +358,14 @@ impl Command for GenerateCommonConfig {
                 "validators-count",
                 false,
             ),
+            Argument::new_named(
+                GENESIS_TIME,
+                false,
+                "Time of the genesis block in the RFC 3339 format (default: current time).",
+                None,
+                "genesis-time",
+                false,
+            ),
         ]
     }
 
@@ -376,6 +390,13 @@ impl Command for GenerateCommonConfig {
         let validators_count = context
             .arg::<u16>("VALIDATORS_COUNT")
             .expect("VALIDATORS_COUNT not found");
+        let genesis_time = context
+            .arg::<String>(GENESIS_TIME)
+            .ok()
+            .map_or_else(Utc::now, |time| {
+                time.parse()
+                    .expect("Expected the genesis time in the RFC 3339 format")
+            });
 
         context.set(keys::SERVICES_CONFIG, AbstractConfig::default());
         let new_context = exts(context);
@@ -388,9 +409,10 @@ impl Command for GenerateCommonConfig {
         );
 
         let template = CommonConfigTemplate {
+            consensus_config: ConsensusConfig::default(),
             services_config,
             general_config,
-            ..CommonConfigTemplate::default()
+            genesis_time,
         };
 
         ConfigFile::save(&template, template_path).expect("Could not write template file.");
@@ -665,10 +687,12 @@ impl Finalize {
         template: CommonConfigTemplate,
         configs: &[NodePublicConfig],
     ) -> GenesisConfig {
-        GenesisConfig::new_with_consensus(
+        let mut genesis = GenesisConfig::new_with_consensus(
             template.consensus_config,
             configs.iter().map(|c| c.validator_keys),
-        )
+        );
+        genesis.time = template.genesis_time;
+        genesis
     }
 
     fn reduce_configs(
@@ -841,6 +865,7 @@ impl Command for Finalize {
                 database: Default::default(),
                 connect_list,
                 thread_pool_size: Default::default(),
+                state_sync: Default::default(),
             }
         };
 
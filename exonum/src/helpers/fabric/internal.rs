@@ -23,6 +23,8 @@ use super::{Argument, CommandExtension, CommandName, Context};
 pub enum Feedback {
     /// Run node with current context.
     RunNode(Context),
+    /// Import snapshot with current context and services of the node.
+    ImportSnapshot(Context),
     /// Do nothing
     None,
 }
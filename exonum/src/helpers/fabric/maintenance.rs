@@ -14,16 +14,18 @@
 
 //! This module implements node maintenance actions.
 
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, process};
 
 use super::{
     internal::{CollectedCommand, Command, Feedback},
     Argument, CommandName, Context,
 };
-use crate::blockchain::Schema;
-use crate::helpers::config::ConfigFile;
+use crate::blockchain::{self, Block, Schema};
+use crate::crypto::CryptoHash;
+use crate::helpers::{config::ConfigFile, Height};
+use crate::messages::{BinaryForm, Message, ProtocolMessage, RawTransaction};
 use crate::node::NodeConfig;
-use crate::storage::{Database, DbOptions, RocksDB};
+use crate::storage::{indexes_metadata, Database, DbOptions, RocksDB, Snapshot};
 
 // Context entry for the path to the node config.
 const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
@@ -31,10 +33,35 @@ const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
 const DATABASE_PATH: &str = "DATABASE_PATH";
 // Context entry for the type of action to be performed.
 const MAINTENANCE_ACTION_PATH: &str = "MAINTENANCE_ACTION_PATH";
+// Context entry for the name of the index to be dumped.
+const INDEX_NAME: &str = "INDEX_NAME";
+// Context entry for the height to which the blockchain should be rolled back.
+const ROLLBACK_HEIGHT: &str = "ROLLBACK_HEIGHT";
+// Context entry for the path to the backup directory.
+const BACKUP_PATH: &str = "BACKUP_PATH";
+
+// Names of the core indexes, which values are decoded by the `dump-index` action.
+const CORE_BLOCKS: &str = "core.blocks";
+const CORE_TRANSACTIONS: &str = "core.transactions";
 
 /// Maintenance command. Supported actions:
 ///
 /// - `clear-cache` - clear message cache.
+/// - `inspect` - print the blockchain height, the last committed block and the list
+///   of indexes with their types, entry counts and sizes.
+/// - `dump-index` - print all entries of the index specified with `--index`. Entries
+///   of `core.blocks` and `core.transactions` are decoded, other entries are printed
+///   as raw hex.
+/// - `verify-chain` - check consistency of all stored blocks and fail with the first
+///   inconsistent height.
+/// - `rollback` - revert the blockchain state to the height specified with `--height`.
+///   All blocks above this height should be committed in the archive mode.
+/// - `backup` - create a backup of the database in the directory specified with
+///   `--backup-path`. The database is opened exclusively, so the action works only while
+///   the node is stopped; a running node can be backed up with the `v1/backup` private
+///   API endpoint.
+/// - `restore-backup` - restore the database from the backup specified with `--backup-path`
+///   after verifying its latest block.
 #[derive(Debug)]
 pub struct Maintenance;
 
@@ -53,6 +80,12 @@ impl Maintenance {
         Box::new(RocksDB::open(Path::new(&path), options).expect("Can't load database file"))
     }
 
+    // Reports the failed action and terminates the process with a non-zero exit code.
+    fn fail(message: &str) -> ! {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+
     fn clear_cache(context: &Context) {
         info!("Clearing node cache");
 
@@ -67,6 +100,183 @@ impl Maintenance {
 
         info!("Cache cleared successfully");
     }
+
+    fn inspect(context: &Context) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let snapshot = db.snapshot();
+
+        let schema = Schema::new(&snapshot);
+        if schema.block_hashes_by_height().is_empty() {
+            println!("Blockchain is not initialized");
+        } else {
+            let last_block = schema.last_block();
+            println!("Height: {}", schema.height());
+            println!(
+                "Last block ({}):\n{}",
+                last_block.hash(),
+                serde_json::to_string_pretty(&last_block).unwrap()
+            );
+        }
+
+        println!();
+        println!(
+            "{:<48} {:<16} {:<6} {:>12} {:>16}",
+            "Index", "Type", "Family", "Entries", "Size (bytes)"
+        );
+        for (name, index_type, is_family) in indexes_metadata(&snapshot) {
+            let (entries, size) = Self::index_stats(&*snapshot, &name);
+            println!(
+                "{:<48} {:<16} {:<6} {:>12} {:>16}",
+                name,
+                format!("{:?}", index_type),
+                is_family,
+                entries,
+                size
+            );
+        }
+    }
+
+    // Returns the number of entries and the total size of keys and values of the index.
+    // For index families, the statistics cover all indexes in the family.
+    fn index_stats(snapshot: &dyn Snapshot, name: &str) -> (u64, u64) {
+        let mut iter = snapshot.iter(name, &[]);
+        let (mut entries, mut size) = (0, 0);
+        while let Some((key, value)) = iter.next() {
+            entries += 1;
+            size += (key.len() + value.len()) as u64;
+        }
+        (entries, size)
+    }
+
+    fn dump_index(context: &Context) {
+        let name = context
+            .arg::<String>(INDEX_NAME)
+            .expect("Index name should be specified with `--index`.");
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let snapshot = db.snapshot();
+
+        if !indexes_metadata(&snapshot)
+            .iter()
+            .any(|(index_name, ..)| *index_name == name)
+        {
+            println!("Index `{}` does not exist", name);
+            return;
+        }
+
+        let mut iter = snapshot.iter(&name, &[]);
+        while let Some((key, value)) = iter.next() {
+            let value = Self::decode_value(&name, value).unwrap_or_else(|| hex::encode(value));
+            println!("{} => {}", hex::encode(key), value);
+        }
+    }
+
+    fn verify_chain(context: &Context) {
+        info!("Verifying the blockchain");
+
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let snapshot = db.snapshot();
+        let schema = Schema::new(&snapshot);
+
+        let blocks_count = schema.block_hashes_by_height().len();
+        let first_height = schema.first_block_height();
+        for height in (first_height.0..first_height.0 + blocks_count).map(Height) {
+            if let Err(e) = schema.verify_block(height) {
+                Self::fail(&format!(
+                    "Blockchain is inconsistent at height {}: {}",
+                    height, e
+                ));
+            }
+        }
+        println!("Blockchain is consistent, {} blocks checked", blocks_count);
+    }
+
+    fn rollback(context: &Context) {
+        let height = context
+            .arg::<u64>(ROLLBACK_HEIGHT)
+            .map(Height)
+            .expect("Rollback height should be specified with `--height`.");
+        info!("Rolling back the blockchain to height {}", height);
+
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+        let mut fork = db.fork();
+        if let Err(e) = Schema::new(&mut fork).rollback(height) {
+            Self::fail(&format!("Cannot roll back the blockchain: {}", e));
+        }
+        db.merge_sync(fork.into_patch())
+            .expect("Can't roll back the blockchain");
+
+        info!("Blockchain rolled back successfully");
+    }
+
+    fn backup_path(context: &Context) -> String {
+        context
+            .arg::<String>(BACKUP_PATH)
+            .expect("Backup path should be specified with `--backup-path`.")
+    }
+
+    fn backup(context: &Context) {
+        let path = Self::backup_path(context);
+        info!("Creating a backup in {}", path);
+
+        let config = Self::node_config(context);
+        let db_path = context
+            .arg::<String>(DATABASE_PATH)
+            .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH));
+        // The database is locked by a running node, which should be backed up
+        // via the private API instead.
+        let db = RocksDB::open(Path::new(&db_path), &config.database).unwrap_or_else(|e| {
+            Self::fail(&format!(
+                "Cannot open the database: {}. If the node is running, create a backup \
+                 with the `v1/backup` private API endpoint",
+                e
+            ))
+        });
+        match blockchain::create_backup(&db, Path::new(&path)) {
+            Ok(info) => info!("Backup created successfully at height {}", info.height),
+            Err(e) => Self::fail(&format!("Cannot create a backup: {}", e)),
+        }
+    }
+
+    fn restore_backup(context: &Context) {
+        let path = Self::backup_path(context);
+        let db_path = context
+            .arg::<String>(DATABASE_PATH)
+            .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH));
+        info!("Restoring the database from the backup in {}", path);
+
+        match blockchain::restore_backup(Path::new(&path), Path::new(&db_path)) {
+            Ok(info) => info!("Database restored successfully at height {}", info.height),
+            Err(e) => Self::fail(&format!("Cannot restore the backup: {}", e)),
+        }
+    }
+
+    // Decodes values of the core indexes with known value types. Returns `None` if the index
+    // has an unknown value type or the value cannot be decoded, e.g., if it is corrupted.
+    fn decode_value(index_name: &str, value: &[u8]) -> Option<String> {
+        match index_name {
+            CORE_BLOCKS => {
+                let block = Block::decode(value).ok()?;
+                serde_json::to_string(&block).ok()
+            }
+            CORE_TRANSACTIONS => {
+                let message = Message::from_raw_buffer(value.to_vec()).ok()?;
+                let tx = RawTransaction::try_from(message).ok()?;
+                let (_, payload) = tx.payload().clone().service_transaction().into_raw_parts();
+                let decoded = json!({
+                    "author": tx.author(),
+                    "service_id": tx.service_id(),
+                    "transaction_id": tx.transaction_id(),
+                    "payload": hex::encode(payload),
+                });
+                Some(decoded.to_string())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Command for Maintenance {
@@ -96,6 +306,30 @@ impl Command for Maintenance {
                 "action",
                 false,
             ),
+            Argument::new_named(
+                INDEX_NAME,
+                false,
+                "Name of the index to be dumped with the `dump-index` action.",
+                "i",
+                "index",
+                false,
+            ),
+            Argument::new_named(
+                ROLLBACK_HEIGHT,
+                false,
+                "Height to roll the blockchain back to with the `rollback` action.",
+                None,
+                "height",
+                false,
+            ),
+            Argument::new_named(
+                BACKUP_PATH,
+                false,
+                "Path to the backup directory for the `backup` and `restore-backup` actions.",
+                None,
+                "backup-path",
+                false,
+            ),
         ]
     }
 
@@ -104,7 +338,8 @@ impl Command for Maintenance {
     }
 
     fn about(&self) -> &str {
-        "Maintenance module. Available actions: clear-cache."
+        "Maintenance module. Available actions: clear-cache, inspect, dump-index, verify-chain, \
+         rollback, backup, restore-backup."
     }
 
     fn execute(
@@ -117,12 +352,63 @@ impl Command for Maintenance {
             .arg::<String>(MAINTENANCE_ACTION_PATH)
             .unwrap_or_else(|_| panic!("{} not found.", MAINTENANCE_ACTION_PATH));
 
-        if action == "clear-cache" {
-            Self::clear_cache(&context);
-        } else {
-            println!("Unsupported maintenance action: {}", action);
+        match action.as_str() {
+            "clear-cache" => Self::clear_cache(&context),
+            "inspect" => Self::inspect(&context),
+            "dump-index" => Self::dump_index(&context),
+            "verify-chain" => Self::verify_chain(&context),
+            "rollback" => Self::rollback(&context),
+            "backup" => Self::backup(&context),
+            "restore-backup" => Self::restore_backup(&context),
+            _ => println!("Unsupported maintenance action: {}", action),
         }
 
         Feedback::None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{Maintenance, CORE_BLOCKS, CORE_TRANSACTIONS};
+    use crate::blockchain::Block;
+    use crate::crypto::{gen_keypair, Hash};
+    use crate::helpers::{Height, ValidatorId};
+    use crate::messages::{Message, ServiceTransaction};
+    use crate::storage::StorageValue;
+
+    #[test]
+    fn decode_value_falls_back_on_corrupted_entries() {
+        let hash = Hash::zero();
+        let block = Block::new(
+            ValidatorId(0),
+            Height(1),
+            0,
+            &hash,
+            &hash,
+            &hash,
+            &hash,
+            Utc::now(),
+        );
+        let block_bytes = block.clone().into_bytes();
+        let decoded = Maintenance::decode_value(CORE_BLOCKS, &block_bytes).unwrap();
+        assert_eq!(decoded, serde_json::to_string(&block).unwrap());
+        assert!(Maintenance::decode_value(CORE_BLOCKS, &[0xff; 5]).is_none());
+
+        let (pk, sk) = gen_keypair();
+        let tx = Message::sign_transaction(
+            ServiceTransaction::from_raw_unchecked(1, vec![1, 2, 3]),
+            2,
+            pk,
+            &sk,
+        );
+        let mut tx_bytes = tx.into_bytes();
+        let decoded = Maintenance::decode_value(CORE_TRANSACTIONS, &tx_bytes).unwrap();
+        assert!(decoded.contains("\"payload\":\"010203\""));
+        tx_bytes.truncate(tx_bytes.len() / 2);
+        assert!(Maintenance::decode_value(CORE_TRANSACTIONS, &tx_bytes).is_none());
+
+        assert!(Maintenance::decode_value("unknown", &[1, 2, 3]).is_none());
+    }
+}
@@ -21,6 +21,7 @@ pub use self::{
     internal::Command,
     maintenance::Maintenance,
     shared::{AbstractConfig, CommonConfigTemplate, NodePrivateConfig, NodePublicConfig},
+    snapshot::{ExportSnapshot, ImportSnapshot},
 };
 
 use serde::{Deserialize, Serialize};
@@ -37,6 +38,7 @@ mod info;
 mod internal;
 mod maintenance;
 mod shared;
+mod snapshot;
 #[macro_use]
 mod context_key;
 mod password;
@@ -14,6 +14,8 @@
 
 //! This module is used to collect structures that is shared into `CommandExtension` from `Command`.
 
+use chrono::{DateTime, Utc};
+
 use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf};
 
 use crate::blockchain::config::{ConsensusConfig, ValidatorKeys};
@@ -56,7 +58,7 @@ impl NodePublicConfig {
 }
 
 /// Base config.
-#[derive(PartialEq, Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct CommonConfigTemplate {
     /// Consensus configuration.
     pub consensus_config: ConsensusConfig,
@@ -65,6 +67,8 @@ pub struct CommonConfigTemplate {
     pub services_config: AbstractConfig,
     /// General configuration.
     pub general_config: AbstractConfig,
+    /// Creation time of the template, which is used as the time of the genesis block.
+    pub genesis_time: DateTime<Utc>,
 }
 
 /// `NodePrivateConfig` collects all public and secret keys.
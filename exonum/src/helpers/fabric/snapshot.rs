@@ -0,0 +1,847 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module implements commands for exporting and importing state snapshots.
+//!
+//! A snapshot contains all indexes of the storage at the latest committed height, or at
+//! the height specified with `--height`, together with the header and precommits
+//! of the corresponding block. A new node can be bootstrapped from a snapshot instead
+//! of executing all blocks starting from the genesis one. Snapshots at the past heights
+//! can be exported only if the later blocks have been committed in the archive mode.
+//!
+//! Validators authorizing the block from the snapshot are anchored in the genesis configuration
+//! of the node: each change of the validator keys is proven by a block signed by the previous
+//! validators, whose state contains the new configuration. Such proofs are included into
+//! the snapshot only if it is exported from a node running in the archive mode.
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{
+    internal::{CollectedCommand, Command, Feedback},
+    keys, Argument, CommandName, Context,
+};
+use crate::blockchain::{
+    BlockProof, Blockchain, Schema, Service, StoredConfiguration, ValidatorKeys, CORE_SERVICE,
+};
+use crate::crypto::{CryptoHash, Hash};
+use crate::helpers::{config::ConfigFile, Height, Round};
+use crate::node::NodeConfig;
+use crate::storage::{self, Database, MapProof, RocksDB, Snapshot};
+
+/// Index of the configurations table among the core tables in the state hash aggregator.
+const CONFIGS_TABLE_IDX: usize = 0;
+
+// Context entry for the path to the node config.
+const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
+// Context entry for the path to the database.
+const DATABASE_PATH: &str = "DATABASE_PATH";
+// Context entry for the path to the snapshot file.
+const SNAPSHOT_PATH: &str = "SNAPSHOT_PATH";
+// Context entry for the height of the exported snapshot.
+const EXPORT_HEIGHT: &str = "EXPORT_HEIGHT";
+
+/// Prefix of every snapshot file.
+const SNAPSHOT_MAGIC: &[u8] = b"EXONUM_SNAPSHOT_V1";
+/// Tag of the record that starts entries of a new index.
+const INDEX_TAG: u8 = 1;
+/// Tag of the record that contains a single entry of the current index.
+const ENTRY_TAG: u8 = 2;
+/// Tag of the record that terminates the snapshot.
+const END_TAG: u8 = 0;
+/// Maximum number of entries merged into the database at once during import.
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+fn node_config(ctx: &Context) -> NodeConfig<PathBuf> {
+    let path = ctx
+        .arg::<String>(NODE_CONFIG_PATH)
+        .unwrap_or_else(|_| panic!("{} not found.", NODE_CONFIG_PATH));
+    ConfigFile::load(path).expect("Can't load node config file")
+}
+
+fn database(ctx: &Context, config: &NodeConfig<PathBuf>) -> Box<dyn Database> {
+    let path = ctx
+        .arg::<String>(DATABASE_PATH)
+        .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH));
+    Box::new(RocksDB::open(Path::new(&path), &config.database).expect("Can't load database file"))
+}
+
+fn snapshot_path(ctx: &Context) -> String {
+    ctx.arg::<String>(SNAPSHOT_PATH)
+        .unwrap_or_else(|_| panic!("{} not found.", SNAPSHOT_PATH))
+}
+
+fn args(snapshot_help: &'static str) -> Vec<Argument> {
+    vec![
+        Argument::new_named(
+            NODE_CONFIG_PATH,
+            true,
+            "Path to node configuration file.",
+            "c",
+            "node-config",
+            false,
+        ),
+        Argument::new_named(
+            DATABASE_PATH,
+            true,
+            "Use database with the given path.",
+            "d",
+            "db-path",
+            false,
+        ),
+        Argument::new_named(SNAPSHOT_PATH, true, snapshot_help, "s", "snapshot", false),
+    ]
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(bytes.len() as u32)?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32::<LittleEndian>()? as usize;
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Proof that a configuration has been accepted by the validators of the previous one.
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigProof {
+    /// Block preceding the height from which the configuration is actual.
+    block_proof: BlockProof,
+    /// Proof of the configurations table in the state hash aggregator.
+    to_table: MapProof<Hash, Hash>,
+    /// Proof of the configuration in the configurations table.
+    to_config: MapProof<Hash, StoredConfiguration>,
+}
+
+impl ConfigProof {
+    /// Checks that the configuration is a part of the state authorized by the given validators.
+    fn verify(
+        &self,
+        validator_keys: &[ValidatorKeys],
+        config: &StoredConfiguration,
+    ) -> Result<(), failure::Error> {
+        let block = &self.block_proof.block;
+        ensure!(
+            block.height().next() == config.actual_from,
+            "Configuration proof refers to block {} instead of the one preceding height {}",
+            block.height(),
+            config.actual_from
+        );
+        self.block_proof.verify(validator_keys)?;
+
+        let to_table = self.to_table.clone().check()?;
+        ensure!(
+            to_table.merkle_root() == *block.state_hash(),
+            "Configuration proof does not match the state hash of block {}",
+            block.height()
+        );
+        let table_key = Blockchain::service_table_unique_key(CORE_SERVICE, CONFIGS_TABLE_IDX);
+        let table_hash = to_table
+            .entries()
+            .find(|(key, _)| **key == table_key)
+            .map(|(_, hash)| *hash)
+            .ok_or_else(|| format_err!("Configuration proof does not contain the table hash"))?;
+
+        let to_config = self.to_config.clone().check()?;
+        ensure!(
+            to_config.merkle_root() == table_hash,
+            "Configuration proof does not match the configurations table hash"
+        );
+        let config_hash = config.hash();
+        ensure!(
+            to_config
+                .entries()
+                .any(|(hash, proven)| *hash == config_hash && proven == config),
+            "Configuration actual from height {} is not proven",
+            config.actual_from
+        );
+        Ok(())
+    }
+}
+
+/// Creates proofs for the configurations changing the validator keys up to the given height.
+/// Proofs can be created only if the blocks since the configuration change have been committed
+/// in the archive mode.
+fn config_proofs(db: &dyn Database, height: Height) -> Vec<ConfigProof> {
+    let snapshot = db.snapshot();
+    let schema = Schema::new(&snapshot);
+
+    let mut proofs = Vec::new();
+    let mut validator_keys = None;
+    for reference in schema.configs_actual_from().iter() {
+        let actual_from = reference.actual_from();
+        if actual_from > height {
+            break;
+        }
+        let config = schema
+            .configs()
+            .get(reference.cfg_hash())
+            .expect("Configuration is absent in the storage");
+        let is_changed = validator_keys
+            .as_ref()
+            .map_or(false, |keys| *keys != config.validator_keys);
+        validator_keys = Some(config.validator_keys);
+        if !is_changed {
+            continue;
+        }
+
+        let proof_height = actual_from.previous();
+        let mut fork = db.fork();
+        if !Schema::new(&mut fork).revert_blocks_above(proof_height) {
+            warn!(
+                "Change of validators at height {} cannot be proven without the archive mode",
+                actual_from
+            );
+            continue;
+        }
+        let state = Schema::new(&fork);
+        proofs.push(ConfigProof {
+            block_proof: schema
+                .block_and_precommits(proof_height)
+                .expect("Block is absent in the storage"),
+            to_table: state.get_proof_to_service_table(CORE_SERVICE, CONFIGS_TABLE_IDX),
+            to_config: state.configs().get_proof(*reference.cfg_hash()),
+        });
+    }
+    proofs
+}
+
+/// Writes all indexes of the database at the given height, or at the latest height if it is
+/// not specified, along with the proof of the block at this height.
+fn export_snapshot<W: Write>(
+    db: &dyn Database,
+    height: Option<Height>,
+    writer: &mut W,
+) -> Result<BlockProof, failure::Error> {
+    let snapshot: Box<dyn Snapshot> = match height {
+        Some(height) => {
+            let mut fork = db.fork();
+            ensure!(
+                Schema::new(&mut fork).revert_blocks_above(height),
+                "State at height {} is not available, the later blocks should be committed \
+                 in the archive mode",
+                height
+            );
+            Box::new(fork)
+        }
+        None => db.snapshot(),
+    };
+    let schema = Schema::new(&snapshot);
+    let height = schema.height();
+    let block_proof = schema
+        .block_and_precommits(height)
+        .ok_or_else(|| format_err!("Block at height {} is absent in the storage", height))?;
+
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    write_bytes(writer, &serde_json::to_vec(&block_proof)?)?;
+    write_bytes(writer, &serde_json::to_vec(&config_proofs(db, height))?)?;
+
+    for name in storage::index_names(&snapshot) {
+        writer.write_u8(INDEX_TAG)?;
+        write_bytes(writer, name.as_bytes())?;
+
+        let mut iter = snapshot.iter(&name, &[]);
+        while let Some((key, value)) = iter.next() {
+            writer.write_u8(ENTRY_TAG)?;
+            write_bytes(writer, key)?;
+            write_bytes(writer, value)?;
+        }
+    }
+    writer.write_u8(END_TAG)?;
+    writer.flush()?;
+
+    Ok(block_proof)
+}
+
+/// Reads indexes from the snapshot and merges them into the empty `db`.
+fn import_snapshot<R: Read>(
+    db: &dyn Database,
+    reader: &mut R,
+) -> Result<(BlockProof, Vec<ConfigProof>), failure::Error> {
+    let mut magic = vec![0; SNAPSHOT_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    ensure!(magic == SNAPSHOT_MAGIC, "Unknown snapshot file format");
+    let block_proof: BlockProof = serde_json::from_slice(&read_bytes(reader)?)?;
+    let config_proofs: Vec<ConfigProof> = serde_json::from_slice(&read_bytes(reader)?)?;
+
+    let mut fork = db.fork();
+    let mut index_name = None;
+    let mut batch_size = 0;
+    loop {
+        match reader.read_u8()? {
+            INDEX_TAG => index_name = Some(String::from_utf8(read_bytes(reader)?)?),
+            ENTRY_TAG => {
+                let name = index_name
+                    .as_ref()
+                    .ok_or_else(|| format_err!("Snapshot entry does not belong to any index"))?;
+                let key = read_bytes(reader)?;
+                let value = read_bytes(reader)?;
+                fork.put(name, key, value);
+
+                batch_size += 1;
+                if batch_size == IMPORT_BATCH_SIZE {
+                    db.merge(fork.into_patch())?;
+                    fork = db.fork();
+                    batch_size = 0;
+                }
+            }
+            END_TAG => break,
+            tag => bail!("Unknown record tag in snapshot: {}", tag),
+        }
+    }
+    db.merge(fork.into_patch())?;
+
+    Ok((block_proof, config_proofs))
+}
+
+/// Returns the validator keys actual at the given height. The keys are anchored in the genesis
+/// validator keys of the node: each change of the keys should be proven by the validators
+/// of the previous configuration.
+fn anchored_validator_keys(
+    schema: &Schema<&dyn Snapshot>,
+    genesis_keys: &[ValidatorKeys],
+    config_proofs: &[ConfigProof],
+    height: Height,
+) -> Result<Vec<ValidatorKeys>, failure::Error> {
+    let mut validator_keys = genesis_keys.to_vec();
+    for (idx, reference) in schema.configs_actual_from().iter().enumerate() {
+        let config = schema
+            .configs()
+            .get(reference.cfg_hash())
+            .ok_or_else(|| format_err!("Imported configuration {:?} is absent", reference))?;
+        ensure!(
+            config.actual_from == reference.actual_from(),
+            "Imported configuration reference {:?} is invalid",
+            reference
+        );
+        if idx == 0 {
+            ensure!(
+                config.validator_keys == validator_keys,
+                "Imported genesis configuration does not match the node configuration"
+            );
+            continue;
+        }
+        if config.actual_from > height {
+            break;
+        }
+        if config.validator_keys == validator_keys {
+            continue;
+        }
+
+        let proof = config_proofs
+            .iter()
+            .find(|proof| proof.block_proof.block.height().next() == config.actual_from)
+            .ok_or_else(|| {
+                format_err!(
+                    "Change of validators at height {} is not proven, the snapshot should be \
+                     exported from a node in the archive mode",
+                    config.actual_from
+                )
+            })?;
+        proof.verify(&validator_keys, &config)?;
+        validator_keys = config.validator_keys;
+    }
+    Ok(validator_keys)
+}
+
+/// Checks that hashes of the core and service tables match the imported state hash aggregator.
+fn verify_state_hash(
+    schema: &Schema<&dyn Snapshot>,
+    snapshot: &dyn Snapshot,
+    state_hash: &Hash,
+    height: Height,
+    services: &[Box<dyn Service>],
+) -> Result<(), failure::Error> {
+    let state_hash_aggregator = schema.state_hash_aggregator();
+    ensure!(
+        state_hash_aggregator.merkle_root() == *state_hash,
+        "Imported state hash does not match the block state hash"
+    );
+
+    // Tables of the services inactive at the given height may be absent in the aggregator.
+    let mut tables = HashMap::new();
+    for (idx, hash) in schema.core_state_hash().into_iter().enumerate() {
+        let key = Blockchain::service_table_unique_key(CORE_SERVICE, idx);
+        tables.insert(key, (format!("core table {}", idx), hash, true));
+    }
+    let config = schema.configuration_by_height(height);
+    for service in services {
+        let is_active = config.services.contains_key(service.service_name());
+        for (idx, hash) in service.state_hash(snapshot).into_iter().enumerate() {
+            let key = Blockchain::service_table_unique_key(service.service_id(), idx);
+            let name = format!("table {} of the service {}", idx, service.service_name());
+            tables.insert(key, (name, hash, is_active));
+        }
+    }
+
+    for (key, (name, hash, is_required)) in &tables {
+        match state_hash_aggregator.get(key) {
+            Some(expected) => ensure!(
+                expected == *hash,
+                "Imported {} does not match the state hash",
+                name
+            ),
+            None => ensure!(
+                !is_required,
+                "Imported {} is absent in the state hash",
+                name
+            ),
+        }
+    }
+    for key in state_hash_aggregator.keys() {
+        ensure!(
+            tables.contains_key(&key),
+            "Imported state hash contains a table {:?} of an unknown service",
+            key
+        );
+    }
+    Ok(())
+}
+
+/// Checks that the imported state corresponds to the signed block from the snapshot.
+fn verify_imported_state(
+    snapshot: &dyn Snapshot,
+    block_proof: &BlockProof,
+    config_proofs: &[ConfigProof],
+    genesis_keys: &[ValidatorKeys],
+    services: &[Box<dyn Service>],
+) -> Result<(), failure::Error> {
+    let schema = Schema::new(snapshot);
+    let block = &block_proof.block;
+
+    ensure!(
+        schema.last_block() == *block,
+        "Latest imported block does not match the block from the snapshot"
+    );
+
+    let validator_keys =
+        anchored_validator_keys(&schema, genesis_keys, config_proofs, block.height())?;
+    block_proof.verify(&validator_keys)?;
+
+    verify_state_hash(
+        &schema,
+        snapshot,
+        block.state_hash(),
+        block.height(),
+        services,
+    )
+}
+
+/// Imports the snapshot into the empty database and verifies it against the given services.
+pub(crate) fn import_snapshot_with_services(ctx: &Context, services: &[Box<dyn Service>]) {
+    let config = node_config(ctx);
+    let db = database(ctx, &config);
+    let path = snapshot_path(ctx);
+
+    if !Schema::new(&db.snapshot())
+        .block_hashes_by_height()
+        .is_empty()
+    {
+        panic!("Snapshot can be imported only into an empty database.");
+    }
+
+    info!("Importing snapshot from {}", path);
+    let file = File::open(&path).expect("Can't open snapshot file");
+    let (block_proof, config_proofs) = import_snapshot(db.as_ref(), &mut BufReader::new(file))
+        .unwrap_or_else(|e| panic!("Can't import snapshot: {}", e));
+    verify_imported_state(
+        db.snapshot().as_ref(),
+        &block_proof,
+        &config_proofs,
+        &config.genesis.validator_keys,
+        services,
+    )
+    .unwrap_or_else(|e| panic!("Imported snapshot is invalid: {}", e));
+    clear_local_data(db.as_ref()).expect("Can't clear node-local data");
+    info!(
+        "Snapshot at height {} imported successfully",
+        block_proof.block.height()
+    );
+}
+
+/// Removes node-local data that should not be shared between nodes.
+fn clear_local_data(db: &dyn Database) -> Result<(), failure::Error> {
+    let mut fork = db.fork();
+    {
+        let mut schema = Schema::new(&mut fork);
+        schema.consensus_messages_cache_mut().clear();
+        schema.set_consensus_round(Round::first());
+        schema.peers_cache_mut().clear();
+        schema.clear_transactions_pool();
+    }
+    db.merge_sync(fork.into_patch())?;
+    Ok(())
+}
+
+/// Command for exporting the state of the node into a snapshot file.
+///
+/// The snapshot is taken at the latest committed height, or at the height specified
+/// with `--height` if the later blocks have been committed in the archive mode. The node
+/// should be stopped during the export.
+#[derive(Debug)]
+pub struct ExportSnapshot;
+
+impl Command for ExportSnapshot {
+    fn args(&self) -> Vec<Argument> {
+        let mut args = args("Path to the snapshot file to be created.");
+        args.push(Argument::new_named(
+            EXPORT_HEIGHT,
+            false,
+            "Height of the exported state. Defaults to the latest committed height.",
+            None,
+            "height",
+            false,
+        ));
+        args
+    }
+
+    fn name(&self) -> CommandName {
+        "export-snapshot"
+    }
+
+    fn about(&self) -> &str {
+        "Exports the state of the node at the latest or the given height into a snapshot file."
+    }
+
+    fn execute(
+        &self,
+        _commands: &HashMap<CommandName, CollectedCommand>,
+        context: Context,
+        _: &dyn Fn(Context) -> Context,
+    ) -> Feedback {
+        let config = node_config(&context);
+        let db = database(&context, &config);
+        let path = snapshot_path(&context);
+        let height = context.arg::<u64>(EXPORT_HEIGHT).ok().map(Height);
+
+        info!("Exporting snapshot to {}", path);
+        let file = File::create(&path).expect("Can't create snapshot file");
+        let block_proof = export_snapshot(db.as_ref(), height, &mut BufWriter::new(file))
+            .unwrap_or_else(|e| panic!("Can't export snapshot: {}", e));
+        info!(
+            "Snapshot at height {} exported successfully",
+            block_proof.block.height()
+        );
+
+        Feedback::None
+    }
+}
+
+/// Command for importing the state of the node from a snapshot file.
+///
+/// The state is imported into an empty database. Precommits of the block from the snapshot
+/// are verified against the validators anchored in the genesis configuration of the node.
+/// The imported state hash is verified against the signed block, and hashes of the core
+/// and service tables are recomputed and compared with the imported state hash, so the node
+/// can be started from the height following the snapshot one.
+///
+/// The services of the node are required for the verification, so the import is performed
+/// by the `NodeBuilder` after the command returns `Feedback::ImportSnapshot`.
+#[derive(Debug)]
+pub struct ImportSnapshot;
+
+impl Command for ImportSnapshot {
+    fn args(&self) -> Vec<Argument> {
+        args("Path to the snapshot file to be imported.")
+    }
+
+    fn name(&self) -> CommandName {
+        "import-snapshot"
+    }
+
+    fn about(&self) -> &str {
+        "Imports the state of the node from a snapshot file into an empty database."
+    }
+
+    fn execute(
+        &self,
+        _commands: &HashMap<CommandName, CollectedCommand>,
+        context: Context,
+        _: &dyn Fn(Context) -> Context,
+    ) -> Feedback {
+        let config = node_config(&context);
+        let mut context = context;
+        context.set(keys::NODE_CONFIG, config);
+        Feedback::ImportSnapshot(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use futures::sync::mpsc;
+    use serde_json::Value;
+
+    use std::{iter, sync::Arc};
+
+    use super::{export_snapshot, import_snapshot, verify_imported_state};
+    use crate::blockchain::{Blockchain, Schema, Service, Transaction, ValidatorKeys};
+    use crate::crypto::{self, CryptoHash, Hash};
+    use crate::helpers::{self, Height, Round, ValidatorId};
+    use crate::messages::{Message, Precommit, RawTransaction};
+    use crate::node::{ApiSender, NodeConfig};
+    use crate::storage::{Database, Fork, MemoryDB, ProofMapIndex, Snapshot};
+
+    const TEST_SERVICE_ID: u16 = 255;
+    const VALUES_IDX_NAME: &str = "snapshot_test.values";
+
+    struct TestService;
+
+    impl Service for TestService {
+        fn service_id(&self) -> u16 {
+            TEST_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &'static str {
+            "snapshot_test"
+        }
+
+        fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+            let values: ProofMapIndex<_, Hash, u64> = ProofMapIndex::new(VALUES_IDX_NAME, snapshot);
+            vec![values.merkle_root()]
+        }
+
+        fn tx_from_raw(&self, _: RawTransaction) -> Result<Box<dyn Transaction>, failure::Error> {
+            bail!("Test service has no transactions")
+        }
+
+        fn initialize(&self, fork: &mut Fork) -> Value {
+            let mut values = ProofMapIndex::new(VALUES_IDX_NAME, fork);
+            values.put(&crypto::hash(&[1]), 1_u64);
+            Value::Null
+        }
+    }
+
+    fn create_blockchain(db: &Arc<dyn Database>, config: &NodeConfig) -> Blockchain {
+        let mut blockchain = Blockchain::new(
+            Arc::clone(db),
+            vec![Box::new(TestService) as Box<dyn Service>],
+            config.service_public_key,
+            config.service_secret_key.clone(),
+            ApiSender::new(mpsc::channel(1).0),
+        );
+        blockchain.initialize(config.genesis.clone()).unwrap();
+        blockchain
+    }
+
+    fn commit_block(blockchain: &mut Blockchain, config: &NodeConfig, height: Height) {
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, &[]);
+        let precommit = Message::concrete(
+            Precommit::new(
+                ValidatorId::zero(),
+                height,
+                Round::first(),
+                &crypto::hash(&[]),
+                &block_hash,
+                Utc::now(),
+            ),
+            config.consensus_public_key,
+            &config.consensus_secret_key,
+        );
+        blockchain
+            .commit(&patch, block_hash, iter::once(precommit))
+            .unwrap();
+    }
+
+    /// Commits a configuration with the validators of `new_config` actual from height 2.
+    fn change_validators(blockchain: &mut Blockchain, new_config: &NodeConfig) {
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            let mut stored = schema.actual_configuration();
+            stored.previous_cfg_hash = stored.hash();
+            stored.actual_from = Height(2);
+            stored.validator_keys = new_config.genesis.validator_keys.clone();
+            schema.commit_configuration(stored);
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    fn export_and_verify(
+        db: &Arc<dyn Database>,
+        genesis_keys: &[ValidatorKeys],
+    ) -> Result<(), failure::Error> {
+        let mut buffer = Vec::new();
+        export_snapshot(db.as_ref(), None, &mut buffer).unwrap();
+
+        let imported_db = MemoryDB::new();
+        let (block_proof, config_proofs) = import_snapshot(&imported_db, &mut buffer.as_slice())?;
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        verify_imported_state(
+            imported_db.snapshot().as_ref(),
+            &block_proof,
+            &config_proofs,
+            genesis_keys,
+            &services,
+        )
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        commit_block(&mut blockchain, &config, Height(1));
+
+        let mut buffer = Vec::new();
+        let exported = export_snapshot(db.as_ref(), None, &mut buffer).unwrap();
+
+        let imported_db = MemoryDB::new();
+        let (imported, config_proofs) =
+            import_snapshot(&imported_db, &mut buffer.as_slice()).unwrap();
+        assert_eq!(imported, exported);
+        assert!(config_proofs.is_empty());
+
+        let snapshot = imported_db.snapshot();
+        assert_eq!(Schema::new(&snapshot).last_block(), blockchain.last_block());
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        verify_imported_state(
+            snapshot.as_ref(),
+            &imported,
+            &config_proofs,
+            &config.genesis.validator_keys,
+            &services,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn forged_service_table_rejected() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        commit_block(&mut blockchain, &config, Height(1));
+
+        let mut buffer = Vec::new();
+        let block_proof = export_snapshot(db.as_ref(), None, &mut buffer).unwrap();
+        let imported_db = MemoryDB::new();
+        import_snapshot(&imported_db, &mut buffer.as_slice()).unwrap();
+
+        let mut fork = imported_db.fork();
+        ProofMapIndex::new(VALUES_IDX_NAME, &mut fork).put(&crypto::hash(&[1]), 2_u64);
+        imported_db.merge(fork.into_patch()).unwrap();
+
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        let err = verify_imported_state(
+            imported_db.snapshot().as_ref(),
+            &block_proof,
+            &[],
+            &config.genesis.validator_keys,
+            &services,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("snapshot_test does not match"));
+
+        let err = verify_imported_state(
+            imported_db.snapshot().as_ref(),
+            &block_proof,
+            &[],
+            &config.genesis.validator_keys,
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown service"));
+    }
+
+    #[test]
+    fn foreign_genesis_rejected() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let foreign_config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        commit_block(&mut blockchain, &config, Height(1));
+
+        let err = export_and_verify(&db, &foreign_config.genesis.validator_keys).unwrap_err();
+        assert!(err.to_string().contains("genesis configuration"));
+    }
+
+    #[test]
+    fn unproven_validators_change_rejected() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let new_config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        change_validators(&mut blockchain, &new_config);
+        commit_block(&mut blockchain, &config, Height(1));
+        commit_block(&mut blockchain, &new_config, Height(2));
+
+        let err = export_and_verify(&db, &config.genesis.validator_keys).unwrap_err();
+        assert!(err.to_string().contains("archive mode"));
+    }
+
+    #[test]
+    fn proven_validators_change_accepted() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let new_config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        blockchain.set_archive(true);
+        change_validators(&mut blockchain, &new_config);
+        commit_block(&mut blockchain, &config, Height(1));
+        commit_block(&mut blockchain, &new_config, Height(2));
+
+        export_and_verify(&db, &config.genesis.validator_keys).unwrap();
+        // Validators of the imported block cannot replace the anchored ones.
+        let err = export_and_verify(&db, &new_config.genesis.validator_keys).unwrap_err();
+        assert!(err.to_string().contains("genesis configuration"));
+    }
+
+    #[test]
+    fn snapshot_at_past_height() {
+        let config = helpers::generate_testnet_config(1, 0).remove(0);
+        let db: Arc<dyn Database> = Arc::new(MemoryDB::new());
+        let mut blockchain = create_blockchain(&db, &config);
+        commit_block(&mut blockchain, &config, Height(1));
+        commit_block(&mut blockchain, &config, Height(2));
+
+        // The state at a past height is unavailable without the archive mode.
+        let mut buffer = Vec::new();
+        let err = export_snapshot(db.as_ref(), Some(Height(1)), &mut buffer).unwrap_err();
+        assert!(err.to_string().contains("archive mode"));
+
+        blockchain.set_archive(true);
+        commit_block(&mut blockchain, &config, Height(3));
+        let block = blockchain.last_block();
+        commit_block(&mut blockchain, &config, Height(4));
+
+        let mut buffer = Vec::new();
+        let exported = export_snapshot(db.as_ref(), Some(Height(3)), &mut buffer).unwrap();
+        assert_eq!(exported.block, block);
+
+        let imported_db = MemoryDB::new();
+        let (imported, config_proofs) =
+            import_snapshot(&imported_db, &mut buffer.as_slice()).unwrap();
+        assert_eq!(imported, exported);
+        let snapshot = imported_db.snapshot();
+        assert_eq!(Schema::new(&snapshot).last_block(), block);
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        verify_imported_state(
+            snapshot.as_ref(),
+            &imported,
+            &config_proofs,
+            &config.genesis.validator_keys,
+            &services,
+        )
+        .unwrap();
+    }
+}
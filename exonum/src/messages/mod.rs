@@ -40,6 +40,7 @@ use serde::ser::{Serialize, Serializer};
 use std::{borrow::Cow, cmp::PartialEq, fmt, mem, ops::Deref};
 
 use crate::crypto::{hash, CryptoHash, Hash, PublicKey};
+use crate::proto::ProtobufConvert;
 use crate::storage::StorageValue;
 
 pub(crate) use self::{authorization::SignedMessage, helpers::HexStringRepresentation};
@@ -109,6 +110,11 @@ impl RawTransaction {
     pub fn service_id(&self) -> u16 {
         self.service_id
     }
+
+    /// Returns the identifier of the transaction within the service.
+    pub fn transaction_id(&self) -> u16 {
+        self.service_transaction.transaction_id
+    }
 }
 
 impl BinaryForm for RawTransaction {
@@ -279,6 +285,22 @@ impl<T: ProtocolMessage> CryptoHash for Signed<T> {
     }
 }
 
+/// Signed messages nested into other messages are represented as raw bytes. The signature
+/// and the payload of the message are verified when the outer message is decoded.
+impl<T: ProtocolMessage> ProtobufConvert for Signed<T> {
+    type ProtoStruct = Vec<u8>;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        self.message.raw().to_vec()
+    }
+
+    fn from_pb(pb: Self::ProtoStruct) -> Result<Self, Error> {
+        let protocol = Message::deserialize(SignedMessage::from_raw_buffer(pb)?)?;
+        ProtocolMessage::try_from(protocol)
+            .map_err(|_| format_err!("Couldn't deserialize message."))
+    }
+}
+
 impl PartialEq<Signed<RawTransaction>> for SignedMessage {
     fn eq(&self, other: &Signed<RawTransaction>) -> bool {
         self.eq(other.signed_message())
@@ -36,7 +36,7 @@ use crate::blockchain;
 use crate::crypto::{CryptoHash, Hash, PublicKey, SecretKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::proto;
-use crate::storage::{proof_list_index as merkle, StorageValue};
+use crate::storage::{proof_list_index as merkle, ListProof, MapProof, StorageValue};
 
 /// `SignedMessage` size with zero bytes payload.
 #[doc(hidden)]
@@ -109,7 +109,8 @@ impl Connect {
 ///
 /// ### Processing
 /// If the message's `height` number is bigger than a node's one, then
-/// `BlockRequest` with current node's height is sent in reply.
+/// `BlockRequest` with current node's height is sent in reply, unless the block
+/// at the node's height has been pruned by the sender.
 ///
 /// ### Generation
 /// `Status` message is broadcast regularly with the timeout controlled by
@@ -122,14 +123,17 @@ pub struct Status {
     height: Height,
     /// Hash of the last committed block.
     last_hash: Hash,
+    /// Height of the first block which data has not been pruned by the node.
+    pruned_height: Height,
 }
 
 impl Status {
     /// Create new `Status` message.
-    pub fn new(height: Height, last_hash: &Hash) -> Self {
+    pub fn new(height: Height, last_hash: &Hash, pruned_height: Height) -> Self {
         Self {
             height,
             last_hash: *last_hash,
+            pruned_height,
         }
     }
 
@@ -142,6 +146,12 @@ impl Status {
     pub fn last_hash(&self) -> &Hash {
         &self.last_hash
     }
+
+    /// Height of the first block which data has not been pruned by the node.
+    /// Blocks below this height cannot be requested from the node.
+    pub fn pruned_height(&self) -> Height {
+        self.pruned_height
+    }
 }
 
 /// Proposal for a new block.
@@ -150,6 +160,7 @@ impl Status {
 /// The message is ignored if it
 ///     * contains incorrect `prev_hash`
 ///     * is sent by non-leader
+///     * contains invalid pre-commits for the previous block
 ///     * contains already committed transactions
 ///     * is already known
 ///
@@ -174,6 +185,8 @@ pub struct Propose {
     prev_hash: Hash,
     /// The list of transactions to include in the next block.
     transactions: Vec<Hash>,
+    /// Pre-commits for the previous block, which determine the time of the next block.
+    prev_precommits: Vec<Signed<Precommit>>,
 }
 
 impl Propose {
@@ -184,6 +197,7 @@ impl Propose {
         round: Round,
         prev_hash: &Hash,
         transactions: &[Hash],
+        prev_precommits: &[Signed<Precommit>],
     ) -> Self {
         Self {
             validator,
@@ -191,6 +205,7 @@ impl Propose {
             round,
             prev_hash: *prev_hash,
             transactions: transactions.to_vec(),
+            prev_precommits: prev_precommits.to_vec(),
         }
     }
 
@@ -214,6 +229,10 @@ impl Propose {
     pub fn transactions(&self) -> &[Hash] {
         &self.transactions
     }
+    /// Pre-commits for the previous block, which determine the time of the next block.
+    pub fn prev_precommits(&self) -> &[Signed<Precommit>] {
+        &self.prev_precommits
+    }
 }
 
 /// Pre-vote for a new block.
@@ -469,6 +488,120 @@ impl TransactionsResponse {
     }
 }
 
+/// A chunk of the blockchain state.
+///
+/// ### Validation
+/// The message is ignored if
+///     * its `to` field corresponds to a different node
+///     * the node does not synchronize the state at the height of `block`
+///     * the `table` and `from` fields do not correspond to the requested chunk
+///     * the `precommits` field cannot be parsed or verified
+///     * the `proof` field cannot be parsed or verified against the state hash of `block`
+///
+/// ### Processing
+/// Entries of the chunk are saved into the index, which contains the requested table
+/// on the node, and the next chunk is requested.
+///
+/// ### Generation
+/// The message is sent as response to `StateChunkRequest`.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::StateChunkResponse", crate = "crate")]
+pub struct StateChunkResponse {
+    /// Public key of the recipient.
+    to: PublicKey,
+    /// Header of the block, which state the chunk belongs to.
+    block: blockchain::Block,
+    /// List of pre-commits for the block.
+    precommits: Vec<Vec<u8>>,
+    /// Key of the table in the state hash aggregator.
+    table: Hash,
+    /// Storage key of the first entry in the chunk.
+    from: Vec<u8>,
+    /// Serialized `StateChunkProof`.
+    proof: Vec<u8>,
+    /// Storage key of the first entry in the next chunk.
+    next: Vec<u8>,
+}
+
+impl StateChunkResponse {
+    /// Create new `StateChunkResponse` message.
+    pub fn new(
+        to: &PublicKey,
+        block: blockchain::Block,
+        precommits: Vec<Vec<u8>>,
+        table: &Hash,
+        from: &[u8],
+        proof: &StateChunkProof,
+        next: &[u8],
+    ) -> Self {
+        Self {
+            to: *to,
+            block,
+            precommits,
+            table: *table,
+            from: from.to_vec(),
+            proof: serde_json::to_vec(proof).expect("Couldn't serialize state chunk proof."),
+            next: next.to_vec(),
+        }
+    }
+
+    /// Public key of the recipient.
+    pub fn to(&self) -> &PublicKey {
+        &self.to
+    }
+    /// Header of the block, which state the chunk belongs to.
+    pub fn block(&self) -> &blockchain::Block {
+        &self.block
+    }
+    /// List of pre-commits for the block.
+    pub fn precommits(&self) -> Vec<Vec<u8>> {
+        self.precommits.clone()
+    }
+    /// Key of the table in the state hash aggregator.
+    pub fn table(&self) -> &Hash {
+        &self.table
+    }
+    /// Storage key of the first entry in the chunk.
+    pub fn from(&self) -> &[u8] {
+        &self.from
+    }
+    /// Proof of the chunk entries.
+    pub fn proof(&self) -> Result<StateChunkProof, failure::Error> {
+        serde_json::from_slice(&self.proof).map_err(Into::into)
+    }
+    /// Storage key of the first entry in the next chunk. Empty if the chunk is the last one
+    /// in the table.
+    pub fn next(&self) -> &[u8] {
+        &self.next
+    }
+}
+
+/// Proof of the state chunk entries against the state hash of the block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateChunkProof {
+    /// Proof of the table root hash in the state hash aggregator.
+    pub table: MapProof<Hash, Hash>,
+    /// Proof of the chunk entries against the table root hash. `None` if the table is empty.
+    pub entries: Option<StateEntriesProof>,
+}
+
+/// Proof of the entries of a single Merkelized table.
+///
+/// Values are represented by their binary form, so the proof can be verified only for
+/// tables whose values are hashed as their binary representation.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum StateEntriesProof {
+    /// Entries of a `ProofMapIndex`.
+    Map(MapProof<Hash, Vec<u8>>),
+    /// Entries of a `ProofListIndex` with the specified length.
+    List {
+        /// Length of the list.
+        len: u64,
+        /// Proof of the range of list elements.
+        proof: ListProof<Vec<u8>>,
+    },
+}
+
 /// Request for the `Propose`.
 ///
 /// ### Validation
@@ -654,7 +787,9 @@ impl PeersRequest {
 /// The message is ignored if its `height` is bigger than the node's one.
 ///
 /// ### Processing
-/// `BlockResponse` message is sent as the response.
+/// `BlockResponse` message is sent as the response. If the requested block has been
+/// pruned, the `Status` message of the node is sent instead, so that the requester
+/// knows the node's pruned height.
 ///
 /// ### Generation
 /// This message can be sent during `Status` processing.
@@ -682,6 +817,60 @@ impl BlockRequest {
     }
 }
 
+/// Request for a chunk of the blockchain state at the given `height`.
+///
+/// ### Validation
+/// The message is ignored if its `height` is not the height of the latest committed block.
+/// A node may continue serving requests for a height after new blocks are committed if
+/// it has already started serving the state at this height.
+///
+/// ### Processing
+/// `StateChunkResponse` message is sent as the response.
+///
+/// ### Generation
+/// This message is sent during `Status` processing if the node has no blocks except
+/// the genesis one and lags behind the peer for a sufficient number of blocks.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::StateChunkRequest", crate = "crate")]
+pub struct StateChunkRequest {
+    /// Public key of the recipient.
+    to: PublicKey,
+    /// Height of the block, which state is requested.
+    height: Height,
+    /// Key of the table in the state hash aggregator.
+    table: Hash,
+    /// Storage key of the first entry in the chunk. Empty for the first chunk of the table.
+    from: Vec<u8>,
+}
+
+impl StateChunkRequest {
+    /// Create new `StateChunkRequest`.
+    pub fn new(to: &PublicKey, height: Height, table: &Hash, from: &[u8]) -> Self {
+        Self {
+            to: *to,
+            height,
+            table: *table,
+            from: from.to_vec(),
+        }
+    }
+    /// Public key of the recipient.
+    pub fn to(&self) -> &PublicKey {
+        &self.to
+    }
+    /// Height of the block, which state is requested.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+    /// Key of the table in the state hash aggregator.
+    pub fn table(&self) -> &Hash {
+        &self.table
+    }
+    /// Storage key of the first entry in the chunk.
+    pub fn from(&self) -> &[u8] {
+        &self.from
+    }
+}
+
 impl BlockResponse {
     /// Verify Merkle root of transactions in the block.
     pub fn verify_tx_hash(&self) -> bool {
@@ -862,6 +1051,8 @@ impl_protocol! {
             TransactionsResponse = 0,
             /// Information about block, that sent as response to `BlockRequest`.
             BlockResponse = 1,
+            /// Chunk of the blockchain state, that sent as response to `StateChunkRequest`.
+            StateChunkResponse = 2,
         },
         /// Exonum node requests.
         3 => Requests {
@@ -875,6 +1066,8 @@ impl_protocol! {
             PeersRequest = 3,
             /// Request of some future block.
             BlockRequest = 4,
+            /// Request of a chunk of the blockchain state.
+            StateChunkRequest = 5,
         },
 
     }
@@ -946,6 +1139,7 @@ impl Requests {
             Requests::PrevotesRequest(ref msg) => msg.to(),
             Requests::PeersRequest(ref msg) => msg.to(),
             Requests::BlockRequest(ref msg) => msg.to(),
+            Requests::StateChunkRequest(ref msg) => msg.to(),
         }
     }
 
@@ -957,6 +1151,7 @@ impl Requests {
             Requests::PrevotesRequest(ref msg) => msg.author(),
             Requests::PeersRequest(ref msg) => msg.author(),
             Requests::BlockRequest(ref msg) => msg.author(),
+            Requests::StateChunkRequest(ref msg) => msg.author(),
         }
     }
 }
@@ -2,7 +2,7 @@ use chrono::Utc;
 use hex::FromHex;
 
 use super::{
-    BinaryForm, BlockResponse, Message, Precommit, ProtocolMessage, RawTransaction,
+    BinaryForm, BlockResponse, Message, Precommit, Propose, ProtocolMessage, RawTransaction,
     ServiceTransaction, Signed, SignedMessage, Status, TransactionsResponse,
     RAW_TRANSACTION_EMPTY_SIZE, TRANSACTION_RESPONSE_EMPTY_SIZE,
 };
@@ -86,6 +86,8 @@ fn test_block() {
         &hash(&[1]),
         &hash(&txs),
         &hash(&[3]),
+        &hash(&[4]),
+        ts,
     );
 
     let precommits = vec![
@@ -127,9 +129,24 @@ fn test_block() {
         ),
     ];
     let transactions = vec![
-        Message::concrete(Status::new(Height(2), &hash(&[])), pub_key, &secret_key).hash(),
-        Message::concrete(Status::new(Height(4), &hash(&[2])), pub_key, &secret_key).hash(),
-        Message::concrete(Status::new(Height(7), &hash(&[3])), pub_key, &secret_key).hash(),
+        Message::concrete(
+            Status::new(Height(2), &hash(&[]), Height(0)),
+            pub_key,
+            &secret_key,
+        )
+        .hash(),
+        Message::concrete(
+            Status::new(Height(4), &hash(&[2]), Height(0)),
+            pub_key,
+            &secret_key,
+        )
+        .hash(),
+        Message::concrete(
+            Status::new(Height(7), &hash(&[3]), Height(0)),
+            pub_key,
+            &secret_key,
+        )
+        .hash(),
     ];
     let precommits_buf: Vec<_> = precommits.iter().map(|x| x.clone().serialize()).collect();
     let block = Message::concrete(
@@ -222,6 +239,42 @@ fn test_precommit_serde_wrong_signature() {
     assert_eq!(precommit2, precommit);
 }
 
+#[test]
+fn test_propose_prev_precommits() {
+    let (pub_key, secret_key) = gen_keypair();
+
+    let precommit = Message::concrete(
+        Precommit::new(
+            ValidatorId(1),
+            Height(14),
+            Round(2),
+            &hash(&[1, 2, 3]),
+            &hash(&[3, 2, 1]),
+            Utc::now(),
+        ),
+        pub_key,
+        &secret_key,
+    );
+    let propose = Message::concrete(
+        Propose::new(
+            ValidatorId(0),
+            Height(15),
+            Round(1),
+            &hash(&[3, 2, 1]),
+            &[],
+            &[precommit.clone()],
+        ),
+        pub_key,
+        &secret_key,
+    );
+
+    let propose2: Signed<Propose> = ProtocolMessage::try_from(
+        Message::deserialize(SignedMessage::from_raw_buffer(propose.serialize()).unwrap()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(propose2.prev_precommits(), &[precommit]);
+}
+
 #[test]
 fn test_raw_transaction_small_size() {
     assert!(ServiceTransaction::decode(&vec![0; 1]).is_err());
@@ -15,6 +15,7 @@
 use rand::Rng;
 
 use super::{NodeHandler, NodeRole, RequestData};
+use crate::blockchain::Schema;
 use crate::crypto::PublicKey;
 use crate::events::error::LogError;
 use crate::events::network::ConnectedPeerAddr;
@@ -38,6 +39,9 @@ impl NodeHandler {
             Message::Responses(Responses::TransactionsResponse(msg)) => {
                 self.handle_txs_batch(&msg).log_error()
             }
+            Message::Responses(Responses::StateChunkResponse(msg)) => {
+                self.handle_state_chunk(&msg).log_error()
+            }
         }
     }
 
@@ -167,6 +171,21 @@ impl NodeHandler {
                 self.state.set_node_height(peer, msg.height());
             }
 
+            // Download the state instead of executing blocks if the state sync is enabled.
+            if self.update_state_sync(msg.height(), peer) {
+                return;
+            }
+
+            if msg.pruned_height() > height {
+                warn!(
+                    "Peer {:?} has pruned blocks below height {}, cannot request block {} from it",
+                    peer,
+                    msg.pruned_height(),
+                    height
+                );
+                return;
+            }
+
             // Request block
             self.request(RequestData::Block(height), peer);
         }
@@ -229,7 +248,8 @@ impl NodeHandler {
     /// Broadcasts the `Status` message to all peers.
     pub fn broadcast_status(&mut self) {
         let hash = self.blockchain.last_hash();
-        let status = Status::new(self.state.height(), &hash);
+        let pruned_height = Schema::new(&self.blockchain.snapshot()).pruned_height();
+        let status = Status::new(self.state.height(), &hash, pruned_height);
         trace!("Broadcast status: {:?}", status);
 
         let message = self.sign_message(status);
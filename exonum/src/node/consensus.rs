@@ -12,18 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashSet;
+use chrono::{DateTime, Utc};
 
-use crate::blockchain::Schema;
+use std::collections::{HashMap, HashSet};
+
+use crate::blockchain::{block_time, BlockProof, Equivocation, ReportEquivocation, Schema};
 use crate::crypto::{CryptoHash, Hash, PublicKey};
 use crate::events::InternalRequest;
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::messages::{
     BlockRequest, BlockResponse, Consensus as ConsensusMessage, Precommit, Prevote,
     PrevotesRequest, Propose, ProposeRequest, RawTransaction, Signed, SignedMessage,
-    TransactionsRequest, TransactionsResponse,
+    StateChunkRequest, TransactionsRequest, TransactionsResponse,
 };
-use crate::node::{NodeHandler, RequestData};
+use crate::node::{NodeHandler, NodeTimeout, PoolTransaction, RequestData};
 use crate::storage::Patch;
 
 // TODO Reduce view invocations. (ECR-171)
@@ -110,6 +112,18 @@ impl NodeHandler {
             return;
         }
 
+        // Check precommits for the previous block
+        let time = match self.propose_time(msg) {
+            Ok(time) => time,
+            Err(err) => {
+                error!(
+                    "Received propose with invalid precommits: {}, msg={:?}",
+                    err, msg
+                );
+                return;
+            }
+        };
+
         trace!("Handle propose");
 
         let snapshot = self.blockchain.snapshot();
@@ -117,6 +131,7 @@ impl NodeHandler {
         //TODO: Remove this match after errors refactor. (ECR-979)
         let has_unknown_txs = match self.state.add_propose(
             msg.clone(),
+            time,
             &schema.transactions(),
             &schema.transactions_pool(),
         ) {
@@ -283,8 +298,12 @@ impl NodeHandler {
         let block_hash = block.hash();
 
         if self.state.block(&block_hash).is_none() {
-            let (computed_block_hash, patch) =
-                self.create_block(block.proposer_id(), block.height(), msg.transactions());
+            let (computed_block_hash, patch) = self.create_block(
+                block.proposer_id(),
+                block.height(),
+                msg.transactions(),
+                block.time(),
+            );
             // Verify block_hash.
             assert!(
                 computed_block_hash == block_hash,
@@ -320,6 +339,10 @@ impl NodeHandler {
             self.state.consensus_public_key_of(msg.validator())
         );
 
+        if let Some(equivocation) = self.state.prevote_equivocation(msg) {
+            self.handle_equivocation(equivocation);
+        }
+
         // Add prevote
         let has_consensus = self.state.add_prevote(msg.clone());
 
@@ -442,6 +465,10 @@ impl NodeHandler {
             self.state.consensus_public_key_of(msg.validator())
         );
 
+        if let Some(equivocation) = self.state.precommit_equivocation(msg) {
+            self.handle_equivocation(equivocation);
+        }
+
         // Add precommit
         let has_consensus = self.state.add_precommit(msg.clone());
 
@@ -482,6 +509,9 @@ impl NodeHandler {
             self.blockchain
                 .commit(block_state.patch(), block_hash, precommits)
                 .unwrap();
+            for tx_hash in block_state.txs() {
+                self.tx_pool.remove(tx_hash);
+            }
             // Update node state.
             self.state
                 .update_config(Schema::new(&self.blockchain.snapshot()).actual_configuration());
@@ -489,6 +519,10 @@ impl NodeHandler {
             let block_hash = self.blockchain.last_hash();
             self.state
                 .new_height(&block_hash, self.system_state.current_time());
+            self.update_leaders();
+            let expired = self.tx_pool.remove_expired(self.state.height());
+            self.reject_transactions(&expired);
+            self.release_retained_transactions();
             (block_state.txs().len(), block_state.proposer_id())
         };
 
@@ -537,11 +571,46 @@ impl NodeHandler {
             bail!("Received already processed transaction, hash {:?}", hash)
         }
 
-        if let Err(e) = self.blockchain.tx_from_raw(msg.payload().clone()) {
-            error!("Received invalid transaction {:?}, result: {}", msg, e);
-            bail!("Received malicious transaction.")
+        let tx = match self.blockchain.tx_from_raw(msg.payload().clone()) {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!("Received invalid transaction {:?}, result: {}", msg, e);
+                bail!("Received malicious transaction.")
+            }
+        };
+
+        // Transactions required by the known proposes are saved regardless of the pool limits
+        // and validity checks.
+        let is_referenced = self.state.is_tx_referenced(&hash);
+        if !is_referenced {
+            let service_id = msg.payload().service_id();
+            if !self
+                .blockchain
+                .is_service_active(&snapshot, service_id, self.state.height())
+            {
+                bail!(
+                    "Received transaction {:?} of the inactive service {}",
+                    hash,
+                    service_id
+                )
+            }
+            Schema::new(&snapshot)
+                .check_transaction(tx.as_ref(), &msg.author(), self.state.height())
+                .map_err(|e| format_err!("Received stale transaction {:?}: {}", hash, e))?;
         }
 
+        let priority = self.blockchain.tx_priority(&snapshot, &msg);
+        let entry = PoolTransaction::new(hash, msg.author(), priority, tx.max_height());
+        let evicted = match self.tx_pool.insert(entry) {
+            Ok(evicted) => evicted,
+            Err(_) if is_referenced => {
+                self.retained_txs.insert(hash);
+                Vec::new()
+            }
+            Err(e) => bail!("Transaction {:?} is rejected by the pool: {}", hash, e),
+        };
+        self.reject_transactions(&evicted);
+
         let mut fork = self.blockchain.fork();
         {
             let mut schema = Schema::new(&mut fork);
@@ -550,8 +619,11 @@ impl NodeHandler {
         self.blockchain
             .merge(fork.into_patch())
             .expect("Unable to save transaction to persistent pool.");
+        self.api_state.broadcast_tx_added(&hash);
 
-        if self.state.is_leader() && self.state.round() != Round::zero() {
+        if self.state.is_waiting_for_transactions() {
+            self.stop_waiting_for_transactions();
+        } else if self.state.is_leader() && self.state.round() != Round::zero() {
             self.maybe_add_propose_timeout();
         }
 
@@ -571,6 +643,94 @@ impl NodeHandler {
         Ok(())
     }
 
+    /// Removes transactions dropped by the transaction pool from the persistent pool.
+    /// Transactions required by the known proposes are retained until the next height.
+    pub(super) fn reject_transactions(&mut self, tx_hashes: &[Hash]) {
+        if tx_hashes.is_empty() {
+            return;
+        }
+
+        let mut fork = self.blockchain.fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            for tx_hash in tx_hashes {
+                if self.state.is_tx_referenced(tx_hash) {
+                    self.retained_txs.insert(*tx_hash);
+                } else {
+                    schema
+                        .reject_transaction(tx_hash)
+                        .expect("Rejected transaction is not in the persistent pool");
+                }
+            }
+        }
+        self.blockchain
+            .merge(fork.into_patch())
+            .expect("Unable to remove transactions from persistent pool.");
+    }
+
+    /// Removes the transactions retained for the proposes of the previous height from
+    /// the persistent pool, unless they have been committed or added to the pool again.
+    fn release_retained_transactions(&mut self) {
+        if self.retained_txs.is_empty() {
+            return;
+        }
+
+        let snapshot = self.blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let tx_pool = &self.tx_pool;
+        let released = self
+            .retained_txs
+            .drain()
+            .filter(|tx_hash| {
+                schema.transactions_pool().contains(tx_hash) && !tx_pool.contains(tx_hash)
+            })
+            .collect::<Vec<_>>();
+        self.reject_transactions(&released);
+    }
+
+    /// Selects transactions for the propose of this node. Transactions which can no longer
+    /// be committed are removed from the pool, so the propose may contain fewer transactions
+    /// than the limit even if the pool is larger.
+    fn select_propose_transactions(&mut self) -> Vec<Hash> {
+        let snapshot = self.blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let height = self.state.height();
+
+        let mut nonces = HashMap::new();
+        let mut invalid = Vec::new();
+        let mut txs = Vec::new();
+        for tx_hash in self.tx_pool.select(self.txs_block_limit() as usize) {
+            let raw = schema
+                .transactions()
+                .get(&tx_hash)
+                .expect("Transaction from the pool has no body");
+            let author = raw.author();
+            let tx = self
+                .blockchain
+                .tx_from_raw(raw.payload().clone())
+                .expect("Transaction from the pool cannot be deserialized");
+            if let Err(e) = schema.check_transaction(tx.as_ref(), &author, height) {
+                info!("Transaction {:?} is removed from the pool: {}", tx_hash, e);
+                invalid.push(tx_hash);
+                continue;
+            }
+            // Only the first transaction with a certain nonce of the author is included.
+            if let Some(nonce) = tx.nonce() {
+                if nonces.get(&author).map_or(false, |&last| nonce <= last) {
+                    continue;
+                }
+                nonces.insert(author, nonce);
+            }
+            txs.push(tx_hash);
+        }
+
+        for tx_hash in &invalid {
+            self.tx_pool.remove(tx_hash);
+        }
+        self.reject_transactions(&invalid);
+        txs
+    }
+
     /// Handles raw transactions.
     pub fn handle_txs_batch(
         &mut self,
@@ -597,13 +757,18 @@ impl NodeHandler {
     }
 
     /// Handles external boxed transaction. Additionally transaction will be broadcast to the
-    /// Node's peers.
+    /// Node's peers. If the transaction is rejected, WebSocket sessions which have submitted
+    /// it are notified.
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
     pub fn handle_incoming_tx(&mut self, msg: Signed<RawTransaction>) {
         trace!("Handle incoming transaction");
+        let hash = msg.hash();
         match self.handle_tx(msg.clone()) {
             Ok(_) => self.broadcast(msg),
-            Err(e) => error!("{}", e),
+            Err(e) => {
+                error!("{}", e);
+                self.api_state.broadcast_tx_rejected(&hash, e.to_string());
+            }
         }
     }
 
@@ -619,7 +784,12 @@ impl NodeHandler {
         }
 
         info!("Jump to a new round = {}", round);
+        let was_waiting = self.state.is_waiting_for_transactions();
         self.state.jump_round(round);
+        if was_waiting {
+            // Rounds have not advanced while waiting for transactions.
+            self.restart_expired_round();
+        }
         self.add_round_timeout();
         self.process_new_round();
     }
@@ -654,6 +824,31 @@ impl NodeHandler {
         if round != self.state.round() {
             return;
         }
+        if self.system_state.current_time() < self.round_start_time(round.next()) {
+            // The round has been restarted after waiting for transactions.
+            return;
+        }
+        if let Some(time) = self.transactions_wait_time() {
+            // Hold the round until a transaction is received or an empty block
+            // should be proposed.
+            trace!("Hold round {} until {:?}", round, time);
+            self.state.set_waiting_for_transactions(true);
+            self.add_timeout(NodeTimeout::Round(height, round), time);
+            return;
+        }
+        if self.state.is_waiting_for_transactions() {
+            // The round has been held if its timeout has expired before an empty block
+            // could be proposed.
+            let next_round_start = self.round_start_time(round.next());
+            if self
+                .empty_propose_time()
+                .map_or(false, |time| next_round_start < time)
+            {
+                info!("Continue round {} to propose an empty block", round);
+                self.stop_waiting_for_transactions();
+                return;
+            }
+        }
         warn!("ROUND TIMEOUT height={}, round={}", height, round);
 
         // Update state to new round
@@ -665,6 +860,31 @@ impl NodeHandler {
         self.process_new_round();
     }
 
+    /// Continues the current round after waiting for transactions. If the round timeout has
+    /// expired while waiting, the round is restarted.
+    fn stop_waiting_for_transactions(&mut self) {
+        self.state.set_waiting_for_transactions(false);
+        if self.restart_expired_round() {
+            self.add_round_timeout();
+        }
+        self.process_new_round();
+    }
+
+    /// Postpones the rounds so that the current round starts now if its timeout has expired.
+    /// Returns `true` if the round has been restarted.
+    fn restart_expired_round(&mut self) -> bool {
+        let now = self.system_state.current_time();
+        let round = self.state.round();
+        if now < self.round_start_time(round.next()) {
+            return false;
+        }
+        let delay = now
+            .duration_since(self.round_start_time(round))
+            .unwrap_or_default();
+        self.state.postpone_rounds(delay);
+        true
+    }
+
     /// Handles propose timeout. Node sends `Propose` and `Prevote` if it is a leader as result.
     pub fn handle_propose_timeout(&mut self, height: Height, round: Round) {
         // TODO debug asserts (ECR-171)?
@@ -682,23 +902,35 @@ impl NodeHandler {
             if self.state.have_prevote(round) {
                 return;
             }
-            let snapshot = self.blockchain.snapshot();
-            let schema = Schema::new(&snapshot);
-            let pool = schema.transactions_pool();
-            let pool_len = schema.transactions_pool_len();
+            if let Some(time) = self.transactions_wait_time() {
+                // The propose timeout is re-added once the node stops waiting for transactions.
+                trace!("Skip empty propose until {:?}", time);
+                self.state.set_waiting_for_transactions(true);
+                return;
+            }
+            let pool_len = Schema::new(&self.blockchain.snapshot()).transactions_pool_len();
 
             info!("LEADER: pool = {}", pool_len);
 
             let round = self.state.round();
-            let max_count = ::std::cmp::min(u64::from(self.txs_block_limit()), pool_len);
-
-            let txs: Vec<Hash> = pool.iter().take(max_count as usize).collect();
+            let txs = self.select_propose_transactions();
+            let (time, prev_precommits) = {
+                let snapshot = self.blockchain.snapshot();
+                let schema = Schema::new(&snapshot);
+                let precommits = schema
+                    .precommits(self.state.last_hash())
+                    .iter()
+                    .collect::<Vec<_>>();
+                let time = block_time(&precommits, schema.last_block().time());
+                (time, precommits)
+            };
             let propose = self.sign_message(Propose::new(
                 validator_id,
                 self.state.height(),
                 round,
                 self.state.last_hash(),
                 &txs,
+                &prev_precommits,
             ));
             // Put our propose to the consensus messages cache
             self.blockchain.save_message(round, propose.clone());
@@ -709,7 +941,7 @@ impl NodeHandler {
             self.allow_expedited_propose = true;
 
             // Save our propose into state
-            let hash = self.state.add_self_propose(propose);
+            let hash = self.state.add_self_propose(propose, time);
 
             // Send prevote
             let has_majority_prevotes = self.broadcast_prevote(round, &hash);
@@ -768,6 +1000,14 @@ impl NodeHandler {
                 RequestData::Block(height) => {
                     self.sign_message(BlockRequest::new(&peer, height)).into()
                 }
+                RequestData::StateChunk(ref table, ref from) => {
+                    let height = match self.state_sync {
+                        Some(ref state_sync) => state_sync.height(),
+                        None => return,
+                    };
+                    self.sign_message(StateChunkRequest::new(&peer, height, table, from))
+                        .into()
+                }
             };
             trace!("Send request {:?} to peer {:?}", data, peer);
             self.send_to_peer(peer, message);
@@ -780,8 +1020,39 @@ impl NodeHandler {
         proposer_id: ValidatorId,
         height: Height,
         tx_hashes: &[Hash],
+        time: DateTime<Utc>,
     ) -> (Hash, Patch) {
-        self.blockchain.create_patch(proposer_id, height, tx_hashes)
+        self.blockchain
+            .create_patch_with_time(proposer_id, height, tx_hashes, time)
+    }
+
+    /// Verifies pre-commits for the previous block included into the `Propose` and returns
+    /// the time of the proposed block. Signatures of the pre-commits are verified when
+    /// the `Propose` is decoded.
+    fn propose_time(&self, propose: &Propose) -> Result<DateTime<Utc>, failure::Error> {
+        let precommits = propose.prev_precommits().to_vec();
+
+        let snapshot = self.blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let prev_block = schema
+            .blocks()
+            .get(propose.prev_hash())
+            .ok_or_else(|| format_err!("Unknown previous block {:?}", propose.prev_hash()))?;
+        // The genesis block is committed without precommits.
+        if prev_block.height() == Height::zero() {
+            ensure!(
+                precommits.is_empty(),
+                "Propose contains precommits for the genesis block"
+            );
+        } else {
+            let config = schema.configuration_by_height(prev_block.height());
+            BlockProof {
+                block: prev_block.clone(),
+                precommits: precommits.clone(),
+            }
+            .verify(&config.validator_keys)?;
+        }
+        Ok(block_time(&precommits, prev_block.time()))
     }
 
     /// Calls `create_block` with transactions from the corresponding `Propose` and returns the
@@ -791,12 +1062,18 @@ impl NodeHandler {
         if let Some(hash) = self.state.propose_mut(propose_hash).unwrap().block_hash() {
             return hash;
         }
-        let propose = self.state.propose(propose_hash).unwrap().message().clone();
+        let propose_state = self.state.propose(propose_hash).unwrap();
+        let propose = propose_state.message().clone();
+        let time = propose_state.time();
 
         let tx_hashes = propose.transactions().to_vec();
 
-        let (block_hash, patch) =
-            self.create_block(propose.validator(), propose.height(), tx_hashes.as_slice());
+        let (block_hash, patch) = self.create_block(
+            propose.validator(),
+            propose.height(),
+            tx_hashes.as_slice(),
+            time,
+        );
         // Save patch
         self.state
             .add_block(block_hash, patch, tx_hashes, propose.validator());
@@ -860,6 +1137,38 @@ impl NodeHandler {
         self.state.remove_request(data)
     }
 
+    /// Records the evidence of an equivocation and broadcasts the conflicting messages
+    /// to all peers, so that they could detect the equivocation as well. Validators also
+    /// submit the `ReportEquivocation` transaction to commit the evidence to the blockchain.
+    pub fn handle_equivocation(&mut self, equivocation: Equivocation) {
+        let (first, second) = equivocation.messages();
+        let validator = equivocation.validator();
+        let height = equivocation.height();
+        let round = equivocation.round();
+        if !self.blockchain.save_equivocation(equivocation.clone()) {
+            return;
+        }
+
+        warn!(
+            "Validator {} has signed conflicting messages: height={}, round={}",
+            validator, height, round
+        );
+        self.broadcast(first.signed_message().clone());
+        self.broadcast(second.signed_message().clone());
+
+        let is_committed = Schema::new(&self.blockchain.snapshot())
+            .equivocation_evidence()
+            .contains(&equivocation.hash());
+        if self.state.is_validator() && !is_committed {
+            let report = ReportEquivocation::sign(
+                equivocation,
+                self.state.service_public_key(),
+                self.state.service_secret_key(),
+            );
+            self.handle_incoming_tx(report);
+        }
+    }
+
     /// Broadcasts the `Prevote` message to all peers.
     pub fn broadcast_prevote(&mut self, round: Round, propose_hash: &Hash) -> bool {
         let validator_id = self
@@ -910,7 +1219,7 @@ impl NodeHandler {
     }
 
     /// Checks that pre-commits count is correct and calls `verify_precommit` for each of them.
-    fn verify_precommits(
+    pub(super) fn verify_precommits(
         &self,
         precommits: &[Signed<Precommit>],
         block_hash: &Hash,
@@ -0,0 +1,165 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dump of the consensus state of the node.
+
+use bit_vec::BitVec;
+use chrono::{DateTime, Utc};
+
+use std::time::SystemTime;
+
+use crate::crypto::Hash;
+use crate::helpers::{Height, Round, ValidatorId};
+use crate::node::State;
+
+/// Information about a propose known to the node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProposeInfo {
+    /// Hash of the propose.
+    pub hash: Hash,
+    /// Identifier of the validator which has created the propose.
+    pub validator: ValidatorId,
+    /// Round of the propose.
+    pub round: Round,
+    /// Hash of the block created from the propose, if it has been executed.
+    pub block_hash: Option<Hash>,
+    /// Transactions of the propose which are unknown to the node.
+    pub unknown_txs: Vec<Hash>,
+}
+
+/// Votes known to the node for a certain round.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VotesInfo {
+    /// Round of the votes.
+    pub round: Round,
+    /// Hash of the propose for pre-votes, or hash of the block for pre-commits.
+    pub hash: Hash,
+    /// Bit-vector of the validators that have voted, e.g. `"1101"`.
+    pub validators: String,
+}
+
+/// Information about another validator known to the node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidatorConsensusInfo {
+    /// Identifier of the validator.
+    pub id: ValidatorId,
+    /// Maximal round of the validator known from its consensus messages.
+    pub round: Option<Round>,
+    /// Maximal height of the validator known from its consensus messages.
+    pub height: Height,
+}
+
+/// Dump of the consensus state of the node.
+///
+/// The dump returned by the `v1/consensus_state` private endpoint is taken by the node
+/// when it handles the request, so it reflects the current state of the node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConsensusStateInfo {
+    /// Current height.
+    pub height: Height,
+    /// Current round.
+    pub round: Round,
+    /// Leader of the current round.
+    pub leader: ValidatorId,
+    /// Round in which the node has locked on a propose.
+    pub locked_round: Round,
+    /// Hash of the propose the node is locked on.
+    pub locked_propose: Option<Hash>,
+    /// Known proposes.
+    pub proposes: Vec<ProposeInfo>,
+    /// Known pre-votes.
+    pub prevotes: Vec<VotesInfo>,
+    /// Known pre-commits.
+    pub precommits: Vec<VotesInfo>,
+    /// Information about other validators.
+    pub validators: Vec<ValidatorConsensusInfo>,
+    /// Number of consensus messages queued for the future rounds and heights.
+    pub queued_messages: usize,
+    /// Time at which the dump was taken.
+    pub time: DateTime<Utc>,
+}
+
+impl ConsensusStateInfo {
+    /// Creates a dump of the given node state taken at the given time.
+    pub fn new(state: &State, time: SystemTime) -> Self {
+        let mut proposes = state
+            .proposes()
+            .values()
+            .map(|propose| {
+                let mut unknown_txs: Vec<_> = propose.unknown_txs().iter().cloned().collect();
+                unknown_txs.sort();
+                ProposeInfo {
+                    hash: propose.hash(),
+                    validator: propose.message().validator(),
+                    round: propose.message().round(),
+                    block_hash: propose.block_hash(),
+                    unknown_txs,
+                }
+            })
+            .collect::<Vec<_>>();
+        proposes.sort_by_key(|propose| (propose.round, propose.hash));
+
+        let prevotes = state
+            .prevoted_proposes()
+            .into_iter()
+            .map(|(round, hash)| VotesInfo {
+                round,
+                hash,
+                validators: bits_to_string(&state.known_prevotes(round, &hash)),
+            })
+            .collect();
+        let precommits = state
+            .precommitted_blocks()
+            .into_iter()
+            .map(|(round, hash)| VotesInfo {
+                round,
+                hash,
+                validators: bits_to_string(&state.known_precommits(round, &hash)),
+            })
+            .collect();
+
+        let validators = state
+            .validators()
+            .iter()
+            .enumerate()
+            .map(|(id, keys)| {
+                let id = ValidatorId(id as u16);
+                ValidatorConsensusInfo {
+                    id,
+                    round: state.validators_rounds().get(&id).cloned(),
+                    height: state.node_height(&keys.consensus_key),
+                }
+            })
+            .filter(|info| Some(info.id) != state.validator_id())
+            .collect();
+
+        Self {
+            height: state.height(),
+            round: state.round(),
+            leader: state.leader(state.round()),
+            locked_round: state.locked_round(),
+            locked_propose: state.locked_propose(),
+            proposes,
+            prevotes,
+            precommits,
+            validators,
+            queued_messages: state.queued_len(),
+            time: time.into(),
+        }
+    }
+}
+
+fn bits_to_string(bits: &BitVec) -> String {
+    bits.iter().map(|bit| if bit { '1' } else { '0' }).collect()
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{ConnectListConfig, ExternalMessage, NodeHandler, NodeTimeout};
+use super::{ConnectListConfig, ConsensusStateInfo, ExternalMessage, NodeHandler, NodeTimeout};
 use crate::blockchain::Schema;
 use crate::events::{
     error::LogError, Event, EventHandler, InternalEvent, InternalRequest, NetworkEvent,
@@ -88,6 +88,11 @@ impl NodeHandler {
             }
             ExternalMessage::Shutdown => self.execute_later(InternalRequest::Shutdown),
             ExternalMessage::Rebroadcast => self.handle_rebroadcast(),
+            ExternalMessage::ConsensusState(sender) => {
+                let info = ConsensusStateInfo::new(&self.state, self.system_state.current_time());
+                // The receiver may have been dropped if the request has timed out.
+                sender.send(info).ok();
+            }
         }
     }
 
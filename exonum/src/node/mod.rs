@@ -19,6 +19,8 @@
 
 pub use self::{
     connect_list::{ConnectList, PeerAddress},
+    consensus_state::{ConsensusStateInfo, ProposeInfo, ValidatorConsensusInfo, VotesInfo},
+    pool::{DefaultTransactionPool, PoolError, PoolTransaction, TransactionPool},
     state::{RequestData, State, ValidatorState},
 };
 
@@ -46,7 +48,8 @@ use crate::api::{
     ApiAccess, ApiAggregator,
 };
 use crate::blockchain::{
-    Blockchain, ConsensusConfig, GenesisConfig, Schema, Service, SharedNodeState, ValidatorKeys,
+    Blockchain, ConsensusConfig, GenesisConfig, LeaderElection, Schema, Service, SharedNodeState,
+    ValidatorKeys,
 };
 use crate::crypto::{self, read_keys_from_file, CryptoHash, Hash, PublicKey, SecretKey};
 use crate::events::{
@@ -64,11 +67,16 @@ use crate::messages::{Connect, Message, ProtocolMessage, RawTransaction, Signed,
 use crate::node::state::SharedConnectList;
 use crate::storage::{Database, DbOptions};
 
+use self::state_sync::{StateSync, StateSyncSnapshot};
+
 mod basic;
 mod connect_list;
 mod consensus;
+mod consensus_state;
 mod events;
+mod pool;
 mod requests;
+mod state_sync;
 
 /// External messages.
 #[derive(Debug)]
@@ -83,6 +91,8 @@ pub enum ExternalMessage {
     Shutdown,
     /// Rebroadcast transactions from the pool.
     Rebroadcast,
+    /// Send a dump of the consensus state of the node to the given channel.
+    ConsensusState(std::sync::mpsc::Sender<ConsensusStateInfo>),
 }
 
 /// Node timeout types.
@@ -137,6 +147,17 @@ pub struct NodeHandler {
     config_manager: Option<ConfigManager>,
     /// Can we speed up Propose with transaction pressure?
     allow_expedited_propose: bool,
+    /// State sync configuration.
+    state_sync_config: StateSyncConfig,
+    /// Progress of the state sync performed by this node.
+    state_sync: Option<StateSync>,
+    /// State served to the peers performing state sync.
+    state_sync_snapshot: Option<StateSyncSnapshot>,
+    /// Ordering of the transactions from the persistent pool.
+    tx_pool: Box<dyn TransactionPool>,
+    /// Transactions dropped by the transaction pool, which are kept in the persistent pool
+    /// until the next height because they are required by the known proposes.
+    retained_txs: HashSet<Hash>,
 }
 
 /// Service configuration.
@@ -218,18 +239,59 @@ impl Default for EventsPoolCapacity {
     }
 }
 
+/// Limits of the transaction pool.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionPoolConfig {
+    /// Maximum number of uncommitted transactions kept by the node.
+    pub max_size: usize,
+    /// Maximum number of uncommitted transactions from a single author kept by the node.
+    pub max_txs_per_author: usize,
+}
+
+impl Default for TransactionPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 100_000,
+            max_txs_per_author: 1_000,
+        }
+    }
+}
+
 /// Memory pool configuration parameters.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MemoryPoolConfig {
     /// Sets the maximum number of messages that can be buffered on the event loop's
     /// notification channel before a send will fail.
     pub events_pool_capacity: EventsPoolCapacity,
+    /// Limits of the default transaction pool.
+    #[serde(default)]
+    pub transactions_pool: TransactionPoolConfig,
 }
 
 impl Default for MemoryPoolConfig {
     fn default() -> Self {
         Self {
             events_pool_capacity: EventsPoolCapacity::default(),
+            transactions_pool: TransactionPoolConfig::default(),
+        }
+    }
+}
+
+/// State sync configuration parameters.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateSyncConfig {
+    /// Whether a node with no blocks except the genesis one downloads the latest state
+    /// from peers instead of executing all blocks.
+    pub enabled: bool,
+    /// Minimal number of blocks, which the node should lag behind a peer to start state sync.
+    pub min_lag: u64,
+}
+
+impl Default for StateSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_lag: 1000,
         }
     }
 }
@@ -267,6 +329,9 @@ pub struct NodeConfig<T = SecretKey> {
     pub connect_list: ConnectListConfig,
     /// Transaction Verification Thread Pool size.
     pub thread_pool_size: Option<u8>,
+    /// State sync configuration.
+    #[serde(default)]
+    pub state_sync: StateSyncConfig,
 }
 
 impl NodeConfig<PathBuf> {
@@ -310,6 +375,7 @@ impl NodeConfig<PathBuf> {
             database: self.database,
             connect_list: self.connect_list,
             thread_pool_size: self.thread_pool_size,
+            state_sync: self.state_sync,
         }
     }
 }
@@ -327,6 +393,8 @@ pub struct Configuration {
     pub peer_discovery: Vec<String>,
     /// Memory pool configuration.
     pub mempool: MemoryPoolConfig,
+    /// State sync configuration.
+    pub state_sync: StateSyncConfig,
 }
 
 /// Channel for messages, timeouts and api requests.
@@ -492,7 +560,11 @@ impl NodeHandler {
             None => None,
         };
 
-        Self {
+        let tx_pool = Box::new(DefaultTransactionPool::new(
+            config.mempool.transactions_pool,
+        ));
+
+        let mut handler = Self {
             blockchain,
             api_state,
             system_state,
@@ -503,7 +575,63 @@ impl NodeHandler {
             node_role,
             config_manager,
             allow_expedited_propose: true,
+            state_sync_config: config.state_sync,
+            state_sync: None,
+            state_sync_snapshot: None,
+            tx_pool,
+            retained_txs: HashSet::new(),
+        };
+        handler.update_leaders();
+        handler.fill_transaction_pool();
+        handler
+    }
+
+    /// Updates validators eligible to be leaders at the current height according to
+    /// the leader election strategy from the actual consensus configuration.
+    fn update_leaders(&mut self) {
+        let snapshot = self.blockchain.snapshot();
+        let leaders = self
+            .state
+            .consensus_config()
+            .leader_election
+            .eligible_leaders(snapshot.as_ref());
+        self.state.set_leaders(leaders);
+    }
+
+    /// Replaces the default transaction pool of the node. Transactions from the persistent
+    /// pool are added to the new pool.
+    pub fn set_transaction_pool(&mut self, tx_pool: Box<dyn TransactionPool>) {
+        self.tx_pool = tx_pool;
+        self.fill_transaction_pool();
+    }
+
+    /// Adds transactions from the persistent pool to the transaction pool of the node.
+    /// Transactions which are not accepted by the pool are removed from the persistent pool.
+    fn fill_transaction_pool(&mut self) {
+        let snapshot = self.blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let mut rejected = Vec::new();
+        for tx_hash in schema.transactions_pool().iter() {
+            let raw = schema
+                .transactions()
+                .get(&tx_hash)
+                .expect("Transaction from the persistent pool has no body");
+            let priority = self.blockchain.tx_priority(&snapshot, &raw);
+            let max_height = self
+                .blockchain
+                .tx_from_raw(raw.payload().clone())
+                .ok()
+                .and_then(|tx| tx.max_height());
+            let entry = PoolTransaction::new(tx_hash, raw.author(), priority, max_height);
+            match self.tx_pool.insert(entry) {
+                Ok(evicted) => rejected.extend(evicted),
+                Err(e) => {
+                    warn!("Transaction {:?} is not added to the pool: {}", tx_hash, e);
+                    rejected.push(tx_hash);
+                }
+            }
         }
+        self.reject_transactions(&rejected);
     }
 
     fn sign_message<T: ProtocolMessage>(&self, message: T) -> Signed<T> {
@@ -561,6 +689,11 @@ impl NodeHandler {
         self.state().consensus_config().propose_timeout_threshold
     }
 
+    /// Returns value of the `max_empty_block_interval` field from the current `ConsensusConfig`.
+    pub fn max_empty_block_interval(&self) -> Option<Milliseconds> {
+        self.state().consensus_config().max_empty_block_interval
+    }
+
     /// Returns `State` of the node.
     pub fn state(&self) -> &State {
         &self.state
@@ -706,10 +839,37 @@ impl NodeHandler {
         }
     }
 
-    fn need_faster_propose(&self) -> bool {
+    /// Returns the time after which an empty block can be proposed at the current height,
+    /// or `None` if empty blocks are not skipped.
+    fn empty_propose_time(&self) -> Option<SystemTime> {
+        self.max_empty_block_interval()
+            .map(|interval| self.state.height_start_time() + Duration::from_millis(interval))
+    }
+
+    /// Returns the time until which the node should wait for transactions instead of
+    /// proceeding with the current round, or `None` if the node should not wait.
+    ///
+    /// The node waits if empty blocks are skipped, the transaction pool is empty and
+    /// no proposes are known at the current height.
+    fn transactions_wait_time(&self) -> Option<SystemTime> {
+        let time = self.empty_propose_time()?;
+        if self.system_state.current_time() >= time
+            || self.state.locked_propose().is_some()
+            || !self.state.proposes().is_empty()
+            || self.transactions_pool_len() != 0
+        {
+            return None;
+        }
+        Some(time)
+    }
+
+    fn transactions_pool_len(&self) -> u64 {
         let snapshot = self.blockchain.snapshot();
-        let pending_tx_count = Schema::new(&snapshot).transactions_pool_len();
-        pending_tx_count >= u64::from(self.propose_timeout_threshold())
+        Schema::new(&snapshot).transactions_pool_len()
+    }
+
+    fn need_faster_propose(&self) -> bool {
+        self.transactions_pool_len() >= u64::from(self.propose_timeout_threshold())
     }
 
     /// Adds `NodeTimeout::Status` timeout to the channel.
@@ -747,16 +907,17 @@ impl NodeHandler {
 
     /// Returns start time of the requested round.
     pub fn round_start_time(&self, round: Round) -> SystemTime {
-        // Round start time = H + (r - 1) * t0 + (r-1)(r-2)/2 * dt
+        // Round start time = H + D + (r - 1) * t0 + (r-1)(r-2)/2 * dt
         // Where:
         // H - height start time
+        // D - delay of the rounds accumulated while waiting for transactions
         // t0 - Round(1) timeout length, dt - timeout increase value
         // r - round number, r = 1,2,...
         let previous_round: u64 = round.previous().into();
         let ms = previous_round * self.first_round_timeout()
             + (previous_round * previous_round.saturating_sub(1)) / 2
                 * self.round_timeout_increase();
-        self.state.height_start_time() + Duration::from_millis(ms)
+        self.state.height_start_time() + self.state.rounds_delay() + Duration::from_millis(ms)
     }
 }
 
@@ -905,6 +1066,10 @@ impl Node {
             node_cfg.service_secret_key.clone(),
             ApiSender::new(channel.api_requests.0.clone()),
         );
+        blockchain
+            .set_keep_blocks(node_cfg.database.keep_blocks)
+            .expect("Invalid database options");
+        blockchain.set_archive(node_cfg.database.archive);
         blockchain.initialize(node_cfg.genesis.clone()).unwrap();
 
         let peers = node_cfg.connect_list.addresses();
@@ -923,6 +1088,7 @@ impl Node {
             mempool: node_cfg.mempool,
             network: node_cfg.network,
             peer_discovery: peers,
+            state_sync: node_cfg.state_sync,
         };
 
         let api_state = SharedNodeState::new(node_cfg.api.state_update_timeout as u64);
@@ -947,6 +1113,11 @@ impl Node {
         }
     }
 
+    /// Replaces the default transaction pool of the node.
+    pub fn set_transaction_pool(&mut self, tx_pool: Box<dyn TransactionPool>) {
+        self.handler.set_transaction_pool(tx_pool);
+    }
+
     /// Launches only consensus messages handler.
     /// This may be used if you want to customize api with the `ApiContext`.
     pub fn run_handler(mut self, handshake_params: &HandshakeParams) -> Result<(), Error> {
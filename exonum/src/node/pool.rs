@@ -0,0 +1,319 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordering of the uncommitted transactions known to the node.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::crypto::{Hash, PublicKey};
+use crate::helpers::Height;
+use crate::node::TransactionPoolConfig;
+
+/// Uncommitted transaction tracked by the `TransactionPool`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolTransaction {
+    /// Hash of the transaction.
+    pub hash: Hash,
+    /// Author of the transaction.
+    pub author: PublicKey,
+    /// Priority of the transaction assigned by its service.
+    pub priority: u64,
+    /// Maximum height of the block that can include the transaction.
+    pub max_height: Option<Height>,
+}
+
+impl PoolTransaction {
+    /// Creates a new pool entry.
+    pub fn new(hash: Hash, author: PublicKey, priority: u64, max_height: Option<Height>) -> Self {
+        Self {
+            hash,
+            author,
+            priority,
+            max_height,
+        }
+    }
+}
+
+/// An error returned when a transaction is not admitted into the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Fail)]
+pub enum PoolError {
+    /// The transaction is already in the pool.
+    #[fail(display = "transaction is already in the pool")]
+    AlreadyExists,
+    /// The pool is full and contains no transactions with a lower priority.
+    #[fail(display = "transaction pool is full")]
+    PoolFull,
+    /// The author of the transaction has too many transactions in the pool.
+    #[fail(display = "too many transactions from the same author")]
+    AuthorLimitExceeded,
+}
+
+/// Policy that determines which of the uncommitted transactions are kept by the node
+/// and in which order they are included into proposals.
+///
+/// The pool tracks only hashes of the transactions; their bodies are stored in
+/// the persistent pool of the blockchain [`Schema`]. Transactions evicted from the pool
+/// are removed from the persistent pool by the node.
+///
+/// [`Schema`]: ../blockchain/struct.Schema.html
+pub trait TransactionPool: Send + 'static {
+    /// Adds a transaction into the pool. Returns hashes of the transactions evicted
+    /// to make room for the new one.
+    fn insert(&mut self, tx: PoolTransaction) -> Result<Vec<Hash>, PoolError>;
+
+    /// Removes a transaction from the pool. Returns `true` if the transaction was in the pool.
+    fn remove(&mut self, tx_hash: &Hash) -> bool;
+
+    /// Returns `true` if the pool contains the transaction.
+    fn contains(&self, tx_hash: &Hash) -> bool;
+
+    /// Returns the number of transactions in the pool.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the pool contains no transactions.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns up to `limit` transactions to be included into the next proposal,
+    /// in the order of inclusion.
+    fn select(&self, limit: usize) -> Vec<Hash>;
+
+    /// Removes transactions which cannot be included into the block at the given height
+    /// because of their maximum height. Returns hashes of the removed transactions.
+    fn remove_expired(&mut self, height: Height) -> Vec<Hash>;
+}
+
+/// Default `TransactionPool` implementation.
+///
+/// Transactions are ordered by decreasing priority; transactions with equal priority are
+/// ordered by the time they were added to the pool. When the pool is full, the oldest
+/// transaction with the lowest priority is evicted, provided that its priority does not
+/// exceed the priority of the new transaction.
+pub struct DefaultTransactionPool {
+    config: TransactionPoolConfig,
+    entries: HashMap<Hash, (PoolTransaction, u64)>,
+    order: BTreeSet<(Reverse<u64>, u64, Hash)>,
+    expirations: BTreeSet<(Height, Hash)>,
+    authors: HashMap<PublicKey, usize>,
+    next_seq: u64,
+}
+
+impl DefaultTransactionPool {
+    /// Creates an empty pool with the given limits.
+    pub fn new(config: TransactionPoolConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            order: BTreeSet::new(),
+            expirations: BTreeSet::new(),
+            authors: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    fn eviction_candidate(&self) -> Option<(u64, Hash)> {
+        let (Reverse(lowest_priority), ..) = *self.order.iter().next_back()?;
+        self.order
+            .range((Reverse(lowest_priority), 0, Hash::zero())..)
+            .next()
+            .map(|&(Reverse(priority), _, hash)| (priority, hash))
+    }
+}
+
+impl TransactionPool for DefaultTransactionPool {
+    fn insert(&mut self, tx: PoolTransaction) -> Result<Vec<Hash>, PoolError> {
+        if self.entries.contains_key(&tx.hash) {
+            return Err(PoolError::AlreadyExists);
+        }
+        let author_txs = self.authors.get(&tx.author).cloned().unwrap_or(0);
+        if author_txs >= self.config.max_txs_per_author {
+            return Err(PoolError::AuthorLimitExceeded);
+        }
+
+        let mut evicted = Vec::new();
+        while self.entries.len() >= self.config.max_size {
+            match self.eviction_candidate() {
+                Some((priority, hash)) if priority <= tx.priority => {
+                    self.remove(&hash);
+                    evicted.push(hash);
+                }
+                _ => return Err(PoolError::PoolFull),
+            }
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.insert((Reverse(tx.priority), seq, tx.hash));
+        if let Some(max_height) = tx.max_height {
+            self.expirations.insert((max_height, tx.hash));
+        }
+        *self.authors.entry(tx.author).or_insert(0) += 1;
+        self.entries.insert(tx.hash, (tx, seq));
+        Ok(evicted)
+    }
+
+    fn remove(&mut self, tx_hash: &Hash) -> bool {
+        let (tx, seq) = match self.entries.remove(tx_hash) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        self.order.remove(&(Reverse(tx.priority), seq, tx.hash));
+        if let Some(max_height) = tx.max_height {
+            self.expirations.remove(&(max_height, tx.hash));
+        }
+        let author_txs = self
+            .authors
+            .get_mut(&tx.author)
+            .expect("Author of the pool transaction is not tracked");
+        *author_txs -= 1;
+        if *author_txs == 0 {
+            self.authors.remove(&tx.author);
+        }
+        true
+    }
+
+    fn contains(&self, tx_hash: &Hash) -> bool {
+        self.entries.contains_key(tx_hash)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn select(&self, limit: usize) -> Vec<Hash> {
+        self.order
+            .iter()
+            .take(limit)
+            .map(|&(_, _, hash)| hash)
+            .collect()
+    }
+
+    fn remove_expired(&mut self, height: Height) -> Vec<Hash> {
+        let expired = self
+            .expirations
+            .range(..(height, Hash::zero()))
+            .map(|&(_, hash)| hash)
+            .collect::<Vec<_>>();
+        for hash in &expired {
+            self.remove(hash);
+        }
+        expired
+    }
+}
+
+impl fmt::Debug for DefaultTransactionPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DefaultTransactionPool")
+            .field("config", &self.config)
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{gen_keypair, hash};
+
+    fn tx(data: u8, author: PublicKey, priority: u64) -> PoolTransaction {
+        PoolTransaction::new(hash(&[data]), author, priority, None)
+    }
+
+    fn pool(max_size: usize, max_txs_per_author: usize) -> DefaultTransactionPool {
+        DefaultTransactionPool::new(TransactionPoolConfig {
+            max_size,
+            max_txs_per_author,
+        })
+    }
+
+    #[test]
+    fn select_order() {
+        let mut pool = pool(10, 10);
+        let (author, _) = gen_keypair();
+        let txs = [tx(0, author, 0), tx(1, author, 5), tx(2, author, 0)];
+        for tx in &txs {
+            assert_eq!(pool.insert(*tx), Ok(Vec::new()));
+        }
+
+        assert_eq!(pool.select(10), vec![txs[1].hash, txs[0].hash, txs[2].hash]);
+        assert_eq!(pool.select(2), vec![txs[1].hash, txs[0].hash]);
+        assert_eq!(pool.insert(txs[0]), Err(PoolError::AlreadyExists));
+
+        assert!(pool.remove(&txs[1].hash));
+        assert!(!pool.remove(&txs[1].hash));
+        assert_eq!(pool.select(10), vec![txs[0].hash, txs[2].hash]);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn eviction() {
+        let mut pool = pool(2, 10);
+        let (author, _) = gen_keypair();
+        let txs = [tx(0, author, 1), tx(1, author, 1), tx(2, author, 1)];
+        pool.insert(txs[0]).unwrap();
+        pool.insert(txs[1]).unwrap();
+
+        // The oldest transaction is evicted.
+        assert_eq!(pool.insert(txs[2]), Ok(vec![txs[0].hash]));
+        assert!(!pool.contains(&txs[0].hash));
+        // Transactions with a lower priority are not admitted into the full pool.
+        assert_eq!(pool.insert(tx(3, author, 0)), Err(PoolError::PoolFull));
+        // Transactions with a higher priority evict the ones with a lower priority.
+        let high = tx(4, author, 2);
+        assert_eq!(pool.insert(high), Ok(vec![txs[1].hash]));
+        assert_eq!(pool.select(10), vec![high.hash, txs[2].hash]);
+    }
+
+    #[test]
+    fn author_limit() {
+        let mut pool = pool(10, 2);
+        let (alice, _) = gen_keypair();
+        let (bob, _) = gen_keypair();
+        pool.insert(tx(0, alice, 0)).unwrap();
+        pool.insert(tx(1, alice, 0)).unwrap();
+        assert_eq!(
+            pool.insert(tx(2, alice, 0)),
+            Err(PoolError::AuthorLimitExceeded)
+        );
+        pool.insert(tx(3, bob, 0)).unwrap();
+
+        pool.remove(&hash(&[0]));
+        pool.insert(tx(2, alice, 0)).unwrap();
+        assert_eq!(pool.len(), 4);
+    }
+
+    #[test]
+    fn expiration() {
+        let mut pool = pool(10, 10);
+        let (author, _) = gen_keypair();
+        let txs = [
+            PoolTransaction::new(hash(&[0]), author, 0, Some(Height(2))),
+            PoolTransaction::new(hash(&[1]), author, 0, Some(Height(3))),
+            PoolTransaction::new(hash(&[2]), author, 0, None),
+        ];
+        for tx in &txs {
+            pool.insert(*tx).unwrap();
+        }
+
+        assert!(pool.remove_expired(Height(2)).is_empty());
+        assert_eq!(pool.remove_expired(Height(3)), vec![txs[0].hash]);
+        assert_eq!(pool.remove_expired(Height(10)), vec![txs[1].hash]);
+        assert_eq!(pool.select(10), vec![txs[2].hash]);
+    }
+}
@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::NodeHandler;
+use super::{NodeHandler, StateSyncSnapshot};
 use crate::blockchain::Schema;
 use crate::messages::{
     BlockRequest, BlockResponse, PrevotesRequest, ProposeRequest, Requests, Signed,
-    TransactionsRequest, TransactionsResponse, RAW_TRANSACTION_HEADER,
-    TRANSACTION_RESPONSE_EMPTY_SIZE,
+    StateChunkRequest, StateChunkResponse, Status, TransactionsRequest, TransactionsResponse,
+    RAW_TRANSACTION_HEADER, TRANSACTION_RESPONSE_EMPTY_SIZE,
 };
 
 // TODO: Height should be updated after any message, not only after status (if signature is correct). (ECR-171)
@@ -46,6 +46,7 @@ impl NodeHandler {
             Requests::PrevotesRequest(ref msg) => self.handle_request_prevotes(msg),
             Requests::PeersRequest(ref msg) => self.handle_request_peers(msg),
             Requests::BlockRequest(ref msg) => self.handle_request_block(msg),
+            Requests::StateChunkRequest(ref msg) => self.handle_request_state_chunk(msg),
         }
     }
 
@@ -141,6 +142,16 @@ impl NodeHandler {
         let schema = Schema::new(&snapshot);
 
         let height = msg.height();
+        if schema.is_pruned(height) {
+            trace!("Requested block at height {} has been pruned", height);
+            let status = self.sign_message(Status::new(
+                self.state.height(),
+                &self.blockchain.last_hash(),
+                schema.pruned_height(),
+            ));
+            self.send_to_peer(msg.author(), status);
+            return;
+        }
         let block_hash = schema.block_hash_by_height(height).unwrap();
 
         let block = schema.blocks().get(&block_hash).unwrap();
@@ -158,4 +169,60 @@ impl NodeHandler {
         ));
         self.send_to_peer(msg.author(), block_msg);
     }
+
+    /// Handles `StateChunkRequest` message. For details see the message documentation.
+    pub fn handle_request_state_chunk(&mut self, msg: &Signed<StateChunkRequest>) {
+        trace!(
+            "Handle state chunk request with height:{}, our height: {}",
+            msg.height(),
+            self.state.height()
+        );
+        let is_served = self
+            .state_sync_snapshot
+            .as_ref()
+            .map_or(false, |snapshot| snapshot.height() == msg.height());
+        if !is_served {
+            // Only the state at the latest height can be served to the new peers.
+            if msg.height().next() != self.state.height() {
+                return;
+            }
+            let snapshot = self.blockchain.snapshot();
+            let tables = match self.blockchain.state_tables(snapshot.as_ref()) {
+                Some(tables) => tables,
+                None => {
+                    trace!("State sync is not supported by the services of the node");
+                    return;
+                }
+            };
+            self.state_sync_snapshot = Some(StateSyncSnapshot::new(snapshot, tables));
+        }
+
+        let response = {
+            let snapshot = self.state_sync_snapshot.as_ref().unwrap();
+            let max_message_len = self.state.config().consensus.max_message_len;
+            let (proof, next) = match snapshot.chunk(msg.table(), msg.from(), max_message_len) {
+                Some(chunk) => chunk,
+                None => {
+                    trace!("Requested state chunk is absent, table: {:?}", msg.table());
+                    return;
+                }
+            };
+            let block_proof = snapshot.block_proof();
+            StateChunkResponse::new(
+                &msg.author(),
+                block_proof.block.clone(),
+                block_proof
+                    .precommits
+                    .iter()
+                    .map(|p| p.signed_message().raw().to_vec())
+                    .collect(),
+                msg.table(),
+                msg.from(),
+                &proof,
+                &next,
+            )
+        };
+        let response = self.sign_message(response);
+        self.send_to_peer(msg.author(), response);
+    }
 }
@@ -15,6 +15,7 @@
 //! State of the `NodeHandler`.
 
 use bit_vec::BitVec;
+use chrono::{DateTime, Utc};
 use serde_json::Value;
 
 use std::{
@@ -24,7 +25,7 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use crate::blockchain::{ConsensusConfig, StoredConfiguration, ValidatorKeys};
+use crate::blockchain::{ConsensusConfig, Equivocation, StoredConfiguration, ValidatorKeys};
 use crate::crypto::{Hash, PublicKey, SecretKey};
 use crate::events::network::ConnectedPeerAddr;
 use crate::helpers::{Height, Milliseconds, Round, ValidatorId};
@@ -48,6 +49,8 @@ pub const TRANSACTIONS_REQUEST_TIMEOUT: Milliseconds = 100;
 pub const PREVOTES_REQUEST_TIMEOUT: Milliseconds = 100;
 /// Timeout value for the `BlockRequest` message.
 pub const BLOCK_REQUEST_TIMEOUT: Milliseconds = 100;
+/// Timeout value for the `StateChunkRequest` message.
+pub const STATE_CHUNK_REQUEST_TIMEOUT: Milliseconds = 1000;
 
 /// State of the `NodeHandler`.
 #[derive(Debug)]
@@ -62,6 +65,8 @@ pub struct State {
 
     config: StoredConfiguration,
     connect_list: SharedConnectList,
+    // Validators eligible to be leaders at the current height.
+    leaders: Vec<ValidatorId>,
 
     peers: HashMap<PublicKey, Signed<Connect>>,
     connections: HashMap<PublicKey, ConnectedPeerAddr>,
@@ -69,6 +74,9 @@ pub struct State {
     height: Height,
 
     round: Round,
+    // Delay of the rounds at the current height accumulated while waiting for transactions.
+    rounds_delay: Duration,
+    waiting_for_transactions: bool,
     locked_round: Round,
     locked_propose: Option<Hash>,
     last_hash: Hash,
@@ -117,6 +125,9 @@ pub enum RequestData {
     Prevotes(Round, Hash),
     /// Represents `BlockRequest` message.
     Block(Height),
+    /// Represents `StateChunkRequest` message for the table with the given key in the state
+    /// hash aggregator, starting from the given storage key.
+    StateChunk(Hash, Vec<u8>),
 }
 
 #[derive(Debug)]
@@ -134,6 +145,8 @@ pub struct ProposeState {
     propose: Signed<Propose>,
     unknown_txs: HashSet<Hash>,
     block_hash: Option<Hash>,
+    // Time of the proposed block, determined by the precommits for the previous block.
+    time: DateTime<Utc>,
     // Whether the message has been saved to the consensus messages' cache or not.
     is_saved: bool,
 }
@@ -250,6 +263,17 @@ where
     pub fn messages(&self) -> &Vec<T> {
         &self.messages
     }
+
+    /// Returns the message of the specified validator, if it has been inserted.
+    pub fn message_of(&self, validator: ValidatorId) -> Option<&T> {
+        let voter: usize = validator.into();
+        if !self.validators[voter] {
+            return None;
+        }
+        self.messages
+            .iter()
+            .find(|msg| msg.validator() == validator)
+    }
 }
 
 impl RequestData {
@@ -262,6 +286,7 @@ impl RequestData {
             }
             RequestData::Prevotes(..) => PREVOTES_REQUEST_TIMEOUT,
             RequestData::Block(..) => BLOCK_REQUEST_TIMEOUT,
+            RequestData::StateChunk(..) => STATE_CHUNK_REQUEST_TIMEOUT,
         };
         Duration::from_millis(ms)
     }
@@ -314,6 +339,11 @@ impl ProposeState {
         &self.propose
     }
 
+    /// Returns the time of the proposed block.
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
     /// Returns unknown transactions of the propose.
     pub fn unknown_txs(&self) -> &HashSet<Hash> {
         &self.unknown_txs
@@ -455,11 +485,14 @@ impl State {
             service_public_key,
             service_secret_key,
             connect_list: SharedConnectList::from_connect_list(connect_list),
+            leaders: all_validators(&stored),
             peers,
             connections: HashMap::new(),
             height: last_height,
             height_start_time,
             round: Round::zero(),
+            rounds_delay: Duration::default(),
+            waiting_for_transactions: false,
             locked_round: Round::zero(),
             locked_propose: None,
             last_hash,
@@ -574,6 +607,8 @@ impl State {
         self.renew_validator_id(validator_id);
         trace!("Validator={:#?}", self.validator_state());
 
+        // All validators are leaders in turn until the leaders are set for the new config.
+        self.leaders = all_validators(&config);
         self.config = config;
     }
 
@@ -648,10 +683,30 @@ impl State {
     }
 
     /// Returns the leader id for the specified round and current height.
+    ///
+    /// The leader is chosen among the validators eligible to be leaders at the current height
+    /// in the round-robin order.
     pub fn leader(&self, round: Round) -> ValidatorId {
         let height: u64 = self.height().into();
         let round: u64 = round.into();
-        ValidatorId(((height + round) % (self.validators().len() as u64)) as u16)
+        let index = (height + round) % (self.leaders.len() as u64);
+        self.leaders[index as usize]
+    }
+
+    /// Returns validators eligible to be leaders at the current height.
+    pub fn leaders(&self) -> &[ValidatorId] {
+        &self.leaders
+    }
+
+    /// Sets validators eligible to be leaders at the current height, which are determined
+    /// by the `LeaderElection` strategy from the consensus configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list of leaders is empty.
+    pub fn set_leaders(&mut self, leaders: Vec<ValidatorId>) {
+        assert!(!leaders.is_empty(), "List of leaders is empty");
+        self.leaders = leaders;
     }
 
     /// Updates known round for a validator and returns
@@ -707,6 +762,11 @@ impl State {
             .or_insert_with(Height::zero) = height;
     }
 
+    /// Returns known rounds of validators.
+    pub fn validators_rounds(&self) -> &BTreeMap<ValidatorId, Round> {
+        &self.validators_rounds
+    }
+
     /// Returns a list of nodes whose height is bigger than one of the current node.
     pub fn nodes_with_bigger_height(&self) -> Vec<&PublicKey> {
         self.nodes_max_height
@@ -769,6 +829,11 @@ impl State {
         self.locked_propose
     }
 
+    /// Returns all known proposes.
+    pub fn proposes(&self) -> &HashMap<Hash, ProposeState> {
+        &self.proposes
+    }
+
     /// Returns mutable propose state identified by hash.
     pub fn propose_mut(&mut self, hash: &Hash) -> Option<&mut ProposeState> {
         self.proposes.get_mut(hash)
@@ -786,11 +851,34 @@ impl State {
     /// Updates mode's round.
     pub fn jump_round(&mut self, round: Round) {
         self.round = round;
+        self.waiting_for_transactions = false;
     }
 
     /// Increments node's round by one.
     pub fn new_round(&mut self) {
         self.round.increment();
+        self.waiting_for_transactions = false;
+    }
+
+    /// Returns the delay of the rounds at the current height, which is accumulated
+    /// while the node waits for transactions.
+    pub fn rounds_delay(&self) -> Duration {
+        self.rounds_delay
+    }
+
+    /// Postpones the start of the current round and all following rounds at the current height.
+    pub fn postpone_rounds(&mut self, delay: Duration) {
+        self.rounds_delay += delay;
+    }
+
+    /// Returns `true` if the node waits for transactions to continue the current round.
+    pub fn is_waiting_for_transactions(&self) -> bool {
+        self.waiting_for_transactions
+    }
+
+    /// Sets whether the node waits for transactions to continue the current round.
+    pub fn set_waiting_for_transactions(&mut self, waiting: bool) {
+        self.waiting_for_transactions = waiting;
     }
 
     /// Return incomplete block.
@@ -803,6 +891,8 @@ impl State {
         self.height.increment();
         self.height_start_time = height_start_time;
         self.round = Round::first();
+        self.rounds_delay = Duration::default();
+        self.waiting_for_transactions = false;
         self.locked_round = Round::zero();
         self.locked_propose = None;
         self.last_hash = *block_hash;
@@ -820,6 +910,18 @@ impl State {
         self.incomplete_block = None;
     }
 
+    /// Moves the node to the height following the block, which state has been downloaded
+    /// from peers, and resets previous height data.
+    pub fn new_synced_height(
+        &mut self,
+        block_height: Height,
+        block_hash: &Hash,
+        height_start_time: SystemTime,
+    ) {
+        self.height = block_height;
+        self.new_height(block_hash, height_start_time);
+    }
+
     /// Returns a list of queued consensus messages.
     pub fn queued(&mut self) -> Vec<ConsensusMessage> {
         let mut queued = Vec::new();
@@ -827,6 +929,11 @@ impl State {
         queued
     }
 
+    /// Returns the number of queued consensus messages.
+    pub fn queued_len(&self) -> usize {
+        self.queued.len()
+    }
+
     /// Add consensus message to the queue.
     pub fn add_queued(&mut self, msg: ConsensusMessage) {
         self.queued.push(msg);
@@ -868,6 +975,17 @@ impl State {
         None
     }
 
+    /// Returns `true` if the transaction is included into one of the known proposes
+    /// or into the incomplete block.
+    pub fn is_tx_referenced(&self, tx_hash: &Hash) -> bool {
+        self.proposes
+            .values()
+            .any(|state| state.message().transactions().contains(tx_hash))
+            || self.incomplete_block.as_ref().map_or(false, |block| {
+                block.message().transactions().contains(tx_hash)
+            })
+    }
+
     /// Returns pre-votes for the specified round and propose hash.
     pub fn prevotes(&self, round: Round, propose_hash: Hash) -> &[Signed<Prevote>] {
         self.prevotes
@@ -897,7 +1015,7 @@ impl State {
 
     /// Adds propose from this node to the proposes list for the current height. Such propose
     /// cannot contain unknown transactions. Returns hash of the propose.
-    pub fn add_self_propose(&mut self, msg: Signed<Propose>) -> Hash {
+    pub fn add_self_propose(&mut self, msg: Signed<Propose>, time: DateTime<Utc>) -> Hash {
         debug_assert!(self.validator_state().is_some());
         let propose_hash = msg.hash();
         self.proposes.insert(
@@ -906,6 +1024,7 @@ impl State {
                 propose: msg,
                 unknown_txs: HashSet::new(),
                 block_hash: None,
+                time,
                 // TODO: For the moment it's true because this code gets called immediately after
                 // saving a propose to the cache. Think about making this approach less error-prone.
                 // (ECR-1635)
@@ -916,10 +1035,12 @@ impl State {
         propose_hash
     }
 
-    /// Adds propose from other node. Returns `ProposeState` if it is a new propose.
+    /// Adds propose from other node together with the time of the proposed block, which
+    /// has been verified beforehand. Returns `ProposeState` if it is a new propose.
     pub fn add_propose<S: AsRef<dyn Snapshot>>(
         &mut self,
         msg: Signed<Propose>,
+        time: DateTime<Utc>,
         transactions: &MapIndex<S, Hash, Signed<RawTransaction>>,
         transaction_pool: &KeySetIndex<S, Hash>,
     ) -> Result<&ProposeState, failure::Error> {
@@ -952,6 +1073,7 @@ impl State {
                     propose: msg,
                     unknown_txs,
                     block_hash: None,
+                    time,
                     is_saved: false,
                 }))
             }
@@ -1049,6 +1171,17 @@ impl State {
         votes.count() >= majority_count
     }
 
+    /// Returns the evidence of an equivocation if the validator, which has signed the given
+    /// pre-vote, has already signed a conflicting pre-vote in the same round.
+    pub fn prevote_equivocation(&self, msg: &Signed<Prevote>) -> Option<Equivocation> {
+        self.prevotes
+            .iter()
+            .filter(|&(&(round, _), _)| round == msg.round())
+            .filter_map(|(_, votes)| votes.message_of(msg.validator()))
+            .filter_map(|other| Equivocation::from_prevotes(other.clone(), msg.clone()))
+            .next()
+    }
+
     /// Returns `true` if there are +2/3 pre-votes for the specified round and hash.
     pub fn has_majority_prevotes(&self, round: Round, propose_hash: Hash) -> bool {
         match self.prevotes.get(&(round, propose_hash)) {
@@ -1057,6 +1190,20 @@ impl State {
         }
     }
 
+    /// Returns rounds and hashes of the proposes for which pre-votes are known.
+    pub fn prevoted_proposes(&self) -> Vec<(Round, Hash)> {
+        let mut keys: Vec<_> = self.prevotes.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns rounds and hashes of the blocks for which pre-commits are known.
+    pub fn precommitted_blocks(&self) -> Vec<(Round, Hash)> {
+        let mut keys: Vec<_> = self.precommits.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
     /// Returns ids of validators that that sent pre-votes for the specified propose.
     pub fn known_prevotes(&self, round: Round, propose_hash: &Hash) -> BitVec {
         let len = self.validators().len();
@@ -1073,6 +1220,17 @@ impl State {
             .map_or_else(|| BitVec::from_elem(len, false), |x| x.validators().clone())
     }
 
+    /// Returns the evidence of an equivocation if the validator, which has signed the given
+    /// pre-commit, has already signed a conflicting pre-commit in the same round.
+    pub fn precommit_equivocation(&self, msg: &Signed<Precommit>) -> Option<Equivocation> {
+        self.precommits
+            .iter()
+            .filter(|&(&(round, _), _)| round == msg.round())
+            .filter_map(|(_, votes)| votes.message_of(msg.validator()))
+            .filter_map(|other| Equivocation::from_precommits(other.clone(), msg.clone()))
+            .next()
+    }
+
     /// Adds pre-commit. Returns `true` there are +2/3 pre-commits.
     ///
     /// # Panics
@@ -1211,3 +1369,9 @@ impl State {
         list.add(peer);
     }
 }
+
+fn all_validators(config: &StoredConfiguration) -> Vec<ValidatorId> {
+    (0..config.validator_keys.len())
+        .map(|id| ValidatorId(id as u16))
+        .collect()
+}
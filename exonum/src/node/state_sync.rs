@@ -0,0 +1,666 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State sync allows a node with no blocks except the genesis one to download the latest
+//! state of the Merkelized tables from peers instead of executing all blocks.
+//!
+//! Tables are identified by their keys in the state hash aggregator and are downloaded
+//! chunk by chunk into the indexes declared for the tables by the core schema and
+//! the services of the node, see `Service::state_table_names`. Every chunk contains
+//! the proof of its entries against the root hash of the table and the proof of the root
+//! hash against the state hash of the block, which precommits are verified with
+//! the validator keys known to the node. After all tables are downloaded, the state hash
+//! is recomputed and compared with the one in the block.
+//!
+//! Only the tables taken into account in the state hash are synchronized, so the state sync
+//! is not performed if some service does not declare the indexes of its tables. Downloaded
+//! chunks are saved into the storage right away, so if the sync fails, the node should be
+//! restarted with an empty database.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::{NodeHandler, RequestData};
+use crate::blockchain::{BlockProof, Schema};
+use crate::crypto::{CryptoHash, Hash, PublicKey};
+use crate::helpers::Height;
+use crate::messages::{Precommit, Signed, StateChunkProof, StateChunkResponse, StateEntriesProof};
+use crate::storage::{
+    self, IndexType, ListProof, MapProof, ProofListIndex, ProofMapIndex, Snapshot, StorageKey,
+};
+
+/// Divisor of the maximum message length, which gives the size limit for the entries
+/// of a single chunk. Binary values are serialized into JSON as arrays of numbers,
+/// which takes up to four bytes per value byte.
+const CHUNK_SIZE_DIVISOR: usize = 8;
+
+/// Merkelized index containing a table of the state.
+#[derive(Debug, Clone)]
+enum StateIndex {
+    Map(String),
+    List(String),
+}
+
+/// Verified entries of a state chunk.
+#[derive(Debug)]
+enum StateChunk {
+    Map(Vec<(Hash, Vec<u8>)>),
+    List(u64, Vec<Vec<u8>>),
+}
+
+/// State of the blockchain at a fixed height, which is served to the peers performing
+/// state sync.
+pub(crate) struct StateSyncSnapshot {
+    block_proof: BlockProof,
+    snapshot: Box<dyn Snapshot>,
+    // Names of the indexes containing the tables by the keys of the tables.
+    tables: HashMap<Hash, String>,
+    // Merkelized indexes by their names.
+    indexes: HashMap<String, StateIndex>,
+}
+
+impl StateSyncSnapshot {
+    /// Creates a new snapshot from the latest committed state with the given keys
+    /// and index names of the tables.
+    pub fn new(snapshot: Box<dyn Snapshot>, tables: Vec<(Hash, String)>) -> Self {
+        let block_proof = {
+            let schema = Schema::new(&snapshot);
+            schema
+                .block_and_precommits(schema.height())
+                .expect("Latest block is absent in the storage")
+        };
+
+        let mut indexes = HashMap::new();
+        for name in storage::index_names_by_type(&snapshot, IndexType::ProofMap) {
+            indexes.insert(name.clone(), StateIndex::Map(name));
+        }
+        for name in storage::index_names_by_type(&snapshot, IndexType::ProofList) {
+            indexes.insert(name.clone(), StateIndex::List(name));
+        }
+
+        Self {
+            block_proof,
+            snapshot,
+            tables: tables.into_iter().collect(),
+            indexes,
+        }
+    }
+
+    /// Returns the height of the snapshot.
+    pub fn height(&self) -> Height {
+        self.block_proof.block.height()
+    }
+
+    /// Returns the latest block of the snapshot with its precommits.
+    pub fn block_proof(&self) -> &BlockProof {
+        &self.block_proof
+    }
+
+    /// Returns the proof and the storage key of the next chunk for the chunk of the given
+    /// table starting from the given storage key. Returns `None` if the table or the chunk
+    /// does not exist, or if the table is not contained in a Merkelized index which can be
+    /// transferred.
+    pub fn chunk(
+        &self,
+        table: &Hash,
+        from: &[u8],
+        max_message_len: u32,
+    ) -> Option<(StateChunkProof, Vec<u8>)> {
+        let schema = Schema::new(&self.snapshot);
+        let aggregator = schema.state_hash_aggregator();
+        // Tables of services inactive at the snapshot height are absent in the state
+        // and are sent as empty ones along with the proof of absence.
+        let root = aggregator.get(table).unwrap_or_else(Hash::zero);
+        let table_proof = aggregator.get_proof(*table);
+        if root == Hash::zero() {
+            let proof = StateChunkProof {
+                table: table_proof,
+                entries: None,
+            };
+            return Some((proof, Vec::new()));
+        }
+
+        let size_limit = max_message_len as usize / CHUNK_SIZE_DIVISOR;
+        let name = self.tables.get(table)?;
+        let (entries, next) = match self.indexes.get(name)? {
+            StateIndex::Map(name) => self.map_chunk(name, from, size_limit)?,
+            StateIndex::List(name) => self.list_chunk(name, from, size_limit)?,
+        };
+        let proof = StateChunkProof {
+            table: table_proof,
+            entries: Some(entries),
+        };
+        Some((proof, next))
+    }
+
+    fn map_chunk(
+        &self,
+        name: &str,
+        from: &[u8],
+        size_limit: usize,
+    ) -> Option<(StateEntriesProof, Vec<u8>)> {
+        let index: ProofMapIndex<_, Hash, Vec<u8>> = ProofMapIndex::new(name, &self.snapshot);
+        let iter = if from.is_empty() {
+            index.iter()
+        } else {
+            index.iter_from(&Hash::from_slice(from)?)
+        };
+
+        let mut keys = Vec::new();
+        let mut size = 0;
+        let mut next = Vec::new();
+        for (key, value) in iter {
+            let entry_size = key.as_ref().len() + value.len();
+            if !keys.is_empty() && size + entry_size > size_limit {
+                next = key.as_ref().to_vec();
+                break;
+            }
+            size += entry_size;
+            keys.push(key);
+        }
+
+        if keys.is_empty() {
+            return None;
+        }
+        Some((StateEntriesProof::Map(index.get_multiproof(keys)), next))
+    }
+
+    fn list_chunk(
+        &self,
+        name: &str,
+        from: &[u8],
+        size_limit: usize,
+    ) -> Option<(StateEntriesProof, Vec<u8>)> {
+        let index: ProofListIndex<_, Vec<u8>> = ProofListIndex::new(name, &self.snapshot);
+        let from = list_position(from)?;
+        let len = index.len();
+        if from >= len {
+            return None;
+        }
+
+        let mut to = from;
+        let mut size = 0;
+        for value in index.iter_from(from) {
+            if to > from && size + value.len() > size_limit {
+                break;
+            }
+            size += value.len();
+            to += 1;
+        }
+
+        let next = if to < len { list_key(to) } else { Vec::new() };
+        let proof = index.get_range_proof(from, to);
+        Some((StateEntriesProof::List { len, proof }, next))
+    }
+}
+
+/// Progress of the state sync performed by the node.
+#[derive(Debug)]
+pub(crate) struct StateSync {
+    // Height of the block, which state is downloaded.
+    height: Height,
+    // Block with verified precommits, which is received with the first chunk.
+    block_proof: Option<BlockProof>,
+    // Keys of the tables in the state hash aggregator, which are not downloaded yet,
+    // with the names of the indexes containing the tables.
+    tables: VecDeque<(Hash, String)>,
+    // Storage key of the next chunk of the current table.
+    from: Vec<u8>,
+}
+
+impl StateSync {
+    /// Returns the height of the block, which state is downloaded.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    /// Returns the request for the next chunk, or `None` if all tables are downloaded.
+    fn request_data(&self) -> Option<RequestData> {
+        self.tables
+            .front()
+            .map(|(table, _)| RequestData::StateChunk(*table, self.from.clone()))
+    }
+}
+
+impl NodeHandler {
+    /// Starts or continues the state sync after the `Status` message from the peer with
+    /// the given height is received. Returns `false` if the state sync is not performed.
+    pub(crate) fn update_state_sync(&mut self, peer_height: Height, peer: PublicKey) -> bool {
+        let peer_last_height = peer_height.previous();
+        let in_progress = self
+            .state_sync
+            .as_ref()
+            .map(|sync| (sync.block_proof.is_some(), sync.height));
+
+        match in_progress {
+            // The peer can't have the state at the current sync height.
+            Some((_, height)) if peer_last_height < height => return true,
+            // Continue the sync, as the block at the sync height is already verified.
+            Some((true, _)) => {}
+            // No chunks have been received yet, so the sync can be moved to a newer height.
+            Some((false, height)) => {
+                if peer_last_height > height {
+                    let tables = self.state_sync.as_ref().unwrap().tables.clone();
+                    self.start_state_sync(peer_last_height, tables);
+                }
+            }
+            None if self.can_start_state_sync(peer_last_height) => {
+                let snapshot = self.blockchain.snapshot();
+                match self.blockchain.state_tables(snapshot.as_ref()) {
+                    Some(tables) => self.start_state_sync(peer_last_height, tables.into()),
+                    None => {
+                        warn!(
+                            "State sync is disabled, as some services do not declare \
+                             the indexes of their state tables"
+                        );
+                        self.state_sync_config.enabled = false;
+                        return false;
+                    }
+                }
+            }
+            None => return false,
+        }
+
+        if let Some(data) = self.state_sync.as_ref().and_then(StateSync::request_data) {
+            self.request(data, peer);
+        }
+        true
+    }
+
+    fn can_start_state_sync(&self, peer_last_height: Height) -> bool {
+        // The node should not have any blocks except the genesis one.
+        self.state_sync_config.enabled
+            && self.state.height() == Height(1)
+            && peer_last_height.0 >= self.state_sync_config.min_lag.max(1)
+    }
+
+    fn start_state_sync(&mut self, height: Height, tables: VecDeque<(Hash, String)>) {
+        if let Some(data) = self.state_sync.as_ref().and_then(StateSync::request_data) {
+            self.remove_request(&data);
+        }
+
+        info!("Starting state sync at height {}", height);
+        self.state_sync = Some(StateSync {
+            height,
+            block_proof: None,
+            tables,
+            from: Vec::new(),
+        });
+    }
+
+    /// Handles the `StateChunkResponse` message. For details see the message documentation.
+    pub fn handle_state_chunk(
+        &mut self,
+        msg: &Signed<StateChunkResponse>,
+    ) -> Result<(), failure::Error> {
+        if msg.to() != self.state.consensus_public_key() {
+            bail!(
+                "Received state chunk intended for another peer, to={}, from={}",
+                msg.to().to_hex(),
+                msg.author().to_hex()
+            );
+        }
+
+        if !self.state.connect_list().is_peer_allowed(&msg.author()) {
+            bail!(
+                "Received state chunk from peer = {} which not in ConnectList.",
+                msg.author().to_hex()
+            );
+        }
+
+        let (height, expected_data, index_name, known_block) = match self.state_sync {
+            Some(ref sync) => (
+                sync.height,
+                sync.request_data(),
+                sync.tables.front().map(|(_, name)| name.clone()),
+                sync.block_proof.as_ref().map(|proof| proof.block.clone()),
+            ),
+            None => bail!("Received state chunk while state sync is not performed"),
+        };
+        let data = RequestData::StateChunk(*msg.table(), msg.from().to_vec());
+        if msg.block().height() != height || expected_data.as_ref() != Some(&data) {
+            bail!(
+                "Received unexpected state chunk, height={}, table={:?}, from={:?}",
+                msg.block().height(),
+                msg.table(),
+                msg.from()
+            );
+        }
+
+        let block = msg.block().clone();
+        match known_block {
+            Some(known_block) => ensure!(
+                known_block == block,
+                "Received state chunk for another block, block={:?}",
+                block
+            ),
+            None => {
+                let precommits: Result<Vec<_>, _> = msg
+                    .precommits()
+                    .into_iter()
+                    .map(Precommit::verify_precommit)
+                    .collect();
+                let precommits = precommits?;
+                self.verify_precommits(&precommits, &block.hash(), height)?;
+                let sync = self.state_sync.as_mut().unwrap();
+                sync.block_proof = Some(BlockProof {
+                    block: block.clone(),
+                    precommits,
+                });
+            }
+        }
+
+        let proof = msg.proof()?;
+        let root = verify_table_proof(proof.table, msg.table(), block.state_hash())?;
+        let chunk = match (root == Hash::zero(), proof.entries) {
+            (true, None) => None,
+            (false, Some(StateEntriesProof::Map(proof))) => {
+                Some(verify_map_chunk(proof, root, msg.from(), msg.next())?)
+            }
+            (false, Some(StateEntriesProof::List { len, proof })) => Some(verify_list_chunk(
+                &proof,
+                len,
+                root,
+                msg.from(),
+                msg.next(),
+            )?),
+            _ => bail!("State chunk does not correspond to the table root hash"),
+        };
+        if let Some(chunk) = chunk {
+            // The index name is taken from the local table declarations rather than from
+            // the peer, so that the peer could not write into arbitrary indexes.
+            let index_name = index_name.expect("Requested table is absent");
+            self.save_state_chunk(&index_name, msg.from().is_empty(), chunk)?;
+        }
+
+        self.remove_request(&data);
+        let next_data = {
+            let sync = self.state_sync.as_mut().unwrap();
+            if msg.next().is_empty() {
+                sync.tables.pop_front();
+            }
+            sync.from = msg.next().to_vec();
+            sync.request_data()
+        };
+
+        match next_data {
+            Some(data) => {
+                self.request(data, msg.author());
+                Ok(())
+            }
+            None => self.finish_state_sync(),
+        }
+    }
+
+    fn save_state_chunk(
+        &mut self,
+        name: &str,
+        is_first: bool,
+        chunk: StateChunk,
+    ) -> Result<(), failure::Error> {
+        let mut fork = self.blockchain.fork();
+        match chunk {
+            StateChunk::Map(entries) => {
+                ensure!(
+                    storage::is_index_type_compatible(name, IndexType::ProofMap, &fork),
+                    "Index {} can't be accessed as a ProofMapIndex",
+                    name
+                );
+                let mut index: ProofMapIndex<_, Hash, Vec<u8>> =
+                    ProofMapIndex::new(name, &mut fork);
+                if is_first {
+                    index.clear();
+                }
+                for (key, value) in entries {
+                    index.put(&key, value);
+                }
+            }
+            StateChunk::List(from, values) => {
+                ensure!(
+                    storage::is_index_type_compatible(name, IndexType::ProofList, &fork),
+                    "Index {} can't be accessed as a ProofListIndex",
+                    name
+                );
+                let mut index: ProofListIndex<_, Vec<u8>> = ProofListIndex::new(name, &mut fork);
+                if is_first {
+                    index.clear();
+                }
+                ensure!(
+                    index.len() == from,
+                    "State chunk of index {} starts from {}, while the index length is {}",
+                    name,
+                    from,
+                    index.len()
+                );
+                index.extend(values);
+            }
+        }
+        self.blockchain.merge(fork.into_patch())?;
+        Ok(())
+    }
+
+    fn finish_state_sync(&mut self) -> Result<(), failure::Error> {
+        let BlockProof { block, precommits } = self
+            .state_sync
+            .take()
+            .and_then(|sync| sync.block_proof)
+            .expect("State sync is finished without verified block");
+        let block_hash = block.hash();
+
+        let mut fork = self.blockchain.fork();
+        let state_hash = self.blockchain.update_state_hash(&mut fork, block.height());
+        if state_hash != *block.state_hash() {
+            // The storage is already modified, so the sync can't be restarted.
+            self.state_sync_config.enabled = false;
+            bail!(
+                "State hash of the downloaded state {:?} does not match the block state hash \
+                 {:?}. The node should be restarted with an empty database.",
+                state_hash,
+                block.state_hash()
+            );
+        }
+        Schema::new(&mut fork).commit_synced_block(&block, &precommits);
+        self.blockchain.merge(fork.into_patch())?;
+
+        self.state
+            .update_config(Schema::new(&self.blockchain.snapshot()).actual_configuration());
+        self.state.new_synced_height(
+            block.height(),
+            &block_hash,
+            self.system_state.current_time(),
+        );
+        self.update_leaders();
+        info!(
+            "STATE SYNC ====== height={}, hash={}",
+            block.height(),
+            block_hash.to_hex()
+        );
+
+        self.broadcast_status();
+        self.add_status_timeout();
+        self.add_round_timeout();
+        if self.state.is_leader() {
+            self.add_propose_timeout();
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the proof of the table root hash against the state hash and returns
+/// the root hash.
+fn verify_table_proof(
+    proof: MapProof<Hash, Hash>,
+    table: &Hash,
+    state_hash: &Hash,
+) -> Result<Hash, failure::Error> {
+    let checked = proof.check()?;
+    ensure!(
+        checked.merkle_root() == *state_hash,
+        "Table proof does not match the state hash"
+    );
+    if checked.missing_keys().any(|key| key == table) {
+        // The table belongs to a service inactive at the synced height.
+        return Ok(Hash::zero());
+    }
+    checked
+        .entries()
+        .find(|(key, _)| *key == table)
+        .map(|(_, root)| *root)
+        .ok_or_else(|| format_err!("Table {:?} is absent in the proof", table))
+}
+
+/// Verifies the proof of map entries against the table root hash. Completeness of
+/// the chunk is not checked, but is ensured by the state hash check after the sync.
+fn verify_map_chunk(
+    proof: MapProof<Hash, Vec<u8>>,
+    root: Hash,
+    from: &[u8],
+    next: &[u8],
+) -> Result<StateChunk, failure::Error> {
+    let checked = proof.check()?;
+    ensure!(
+        checked.merkle_root() == root,
+        "State chunk proof does not match the table root hash"
+    );
+    ensure!(
+        checked.missing_keys().next().is_none(),
+        "State chunk proof contains missing keys"
+    );
+
+    let entries = checked
+        .entries()
+        .map(|(key, value)| (*key, value.clone()))
+        .collect::<Vec<_>>();
+    ensure!(!entries.is_empty(), "State chunk is empty");
+    let in_range = entries
+        .iter()
+        .all(|(key, _)| key.as_ref() >= from && (next.is_empty() || key.as_ref() < next));
+    ensure!(
+        in_range,
+        "State chunk entries are out of the requested range"
+    );
+    Ok(StateChunk::Map(entries))
+}
+
+/// Verifies the proof of list elements against the table root hash.
+fn verify_list_chunk(
+    proof: &ListProof<Vec<u8>>,
+    len: u64,
+    root: Hash,
+    from: &[u8],
+    next: &[u8],
+) -> Result<StateChunk, failure::Error> {
+    let from = list_position(from).ok_or_else(|| format_err!("Invalid list position"))?;
+    let values = proof
+        .validate(root, len)
+        .map_err(|e| format_err!("Invalid state chunk proof: {:?}", e))?;
+    ensure!(!values.is_empty(), "State chunk is empty");
+
+    let is_contiguous = values
+        .iter()
+        .enumerate()
+        .all(|(i, (index, _))| *index == from + i as u64);
+    ensure!(
+        is_contiguous,
+        "State chunk entries are out of the requested range"
+    );
+    let to = from + values.len() as u64;
+    let expected_next = if to < len { list_key(to) } else { Vec::new() };
+    ensure!(
+        next == expected_next.as_slice(),
+        "State chunk has incorrect next position"
+    );
+
+    let values = values.into_iter().map(|(_, value)| value.clone()).collect();
+    Ok(StateChunk::List(from, values))
+}
+
+fn list_position(key: &[u8]) -> Option<u64> {
+    match key.len() {
+        0 => Some(0),
+        8 => Some(u64::read(key)),
+        _ => None,
+    }
+}
+
+fn list_key(position: u64) -> Vec<u8> {
+    let mut key = vec![0; position.size()];
+    position.write(&mut key);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{list_key, list_position, verify_list_chunk, verify_map_chunk, StateChunk};
+    use crate::crypto::{self, Hash};
+    use crate::storage::{Database, MemoryDB, ProofListIndex, ProofMapIndex};
+
+    #[test]
+    fn list_positions() {
+        assert_eq!(list_position(&[]), Some(0));
+        assert_eq!(list_position(&list_key(42)), Some(42));
+        assert_eq!(list_position(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn map_chunk_verification() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let keys = (0_u8..10).map(|i| crypto::hash(&[i])).collect::<Vec<_>>();
+        let mut index: ProofMapIndex<_, Hash, Vec<u8>> = ProofMapIndex::new("map", &mut fork);
+        for (i, key) in keys.iter().enumerate() {
+            index.put(key, vec![i as u8; 3]);
+        }
+        let root = index.merkle_root();
+
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+        let proof = index.get_multiproof(sorted_keys[..5].to_vec());
+        let next = sorted_keys[5].as_ref().to_vec();
+        match verify_map_chunk(proof, root, &[], &next).unwrap() {
+            StateChunk::Map(entries) => assert_eq!(entries.len(), 5),
+            chunk => panic!("Unexpected chunk: {:?}", chunk),
+        }
+
+        // Entries beyond the next chunk key are rejected.
+        let proof = index.get_multiproof(sorted_keys[..6].to_vec());
+        assert!(verify_map_chunk(proof, root, &[], &next).is_err());
+
+        // Proof for another root hash is rejected.
+        let proof = index.get_multiproof(sorted_keys[..5].to_vec());
+        assert!(verify_map_chunk(proof, Hash::zero(), &[], &next).is_err());
+    }
+
+    #[test]
+    fn list_chunk_verification() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index: ProofListIndex<_, Vec<u8>> = ProofListIndex::new("list", &mut fork);
+        index.extend((0_u8..10).map(|i| vec![i; 3]));
+        let root = index.merkle_root();
+
+        let proof = index.get_range_proof(3, 6);
+        match verify_list_chunk(&proof, 10, root, &list_key(3), &list_key(6)).unwrap() {
+            StateChunk::List(from, values) => {
+                assert_eq!(from, 3);
+                assert_eq!(values, vec![vec![3; 3], vec![4; 3], vec![5; 3]]);
+            }
+            chunk => panic!("Unexpected chunk: {:?}", chunk),
+        }
+
+        // Chunk with a mismatching start position is rejected.
+        assert!(verify_list_chunk(&proof, 10, root, &list_key(2), &list_key(6)).is_err());
+        // Chunk with an incorrect next position is rejected.
+        assert!(verify_list_chunk(&proof, 10, root, &list_key(3), &[]).is_err());
+    }
+}
@@ -59,11 +59,15 @@
 //! }
 //! ```
 
-pub use self::schema::blockchain::{Block, ConfigReference, TransactionResult, TxLocation};
+pub use self::schema::blockchain::{
+    ArchivedChange, Block, ConfigReference, Equivocation, Event, ReportEquivocation,
+    TransactionResult, TxLocation,
+};
 pub use self::schema::helpers::{BitVec, Hash, PublicKey, Signature};
 pub use self::schema::protocol::{
     BlockRequest, BlockResponse, Connect, PeersRequest, Precommit, Prevote, PrevotesRequest,
-    Propose, ProposeRequest, Status, TransactionsRequest, TransactionsResponse,
+    Propose, ProposeRequest, StateChunkRequest, StateChunkResponse, Status, TransactionsRequest,
+    TransactionsResponse,
 };
 
 pub mod schema;
@@ -18,6 +18,7 @@ use std::time::Duration;
 
 use crate::crypto::CryptoHash;
 use crate::helpers::{Height, Round, ValidatorId};
+use crate::messages::{Message, Status};
 use crate::node::state::{BLOCK_REQUEST_TIMEOUT, TRANSACTIONS_REQUEST_TIMEOUT};
 use crate::sandbox::{sandbox::timestamping_sandbox, sandbox_tests_helper::*};
 
@@ -943,3 +944,51 @@ fn transactions_request_to_multiple_nodes() {
         sandbox.s(ValidatorId(0)),
     ));
 }
+
+/// idea of the test is:
+/// - getting Status from other node with later height, which has pruned the block
+///   at our height
+/// - BlockRequest should not be sent to this node
+#[test]
+fn do_not_request_pruned_block() {
+    let sandbox = timestamping_sandbox();
+
+    sandbox.recv(&Message::concrete(
+        Status::new(Height(5), &empty_hash(), Height(3)),
+        sandbox.p(ValidatorId(3)),
+        sandbox.s(ValidatorId(3)),
+    ));
+
+    sandbox.add_time(Duration::from_millis(BLOCK_REQUEST_TIMEOUT));
+    sandbox.assert_state(Height(1), Round(1));
+}
+
+/// idea of the test is:
+/// - prune old blocks of the node
+/// - receive BlockRequest for the pruned block
+/// - Status with the pruned height should be sent in reply
+#[test]
+fn respond_with_status_to_pruned_block_request() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+    sandbox.blockchain_mut().set_keep_blocks(Some(1)).unwrap();
+
+    add_one_height(&sandbox, &sandbox_state);
+    add_one_height(&sandbox, &sandbox_state);
+    sandbox.assert_state(Height(3), Round(1));
+
+    sandbox.recv(&sandbox.create_block_request(
+        &sandbox.p(ValidatorId(1)),
+        &sandbox.p(ValidatorId(0)),
+        Height(1),
+        sandbox.s(ValidatorId(1)),
+    ));
+    sandbox.send(
+        sandbox.p(ValidatorId(1)),
+        &Message::concrete(
+            Status::new(Height(3), &sandbox.last_hash(), Height(2)),
+            sandbox.p(ValidatorId(0)),
+            sandbox.s(ValidatorId(0)),
+        ),
+    );
+}
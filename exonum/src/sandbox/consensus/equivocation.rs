@@ -0,0 +1,129 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests in this module are designed to test detection of validators signing
+//! conflicting consensus messages.
+
+use crate::blockchain::{Equivocation, ReportEquivocation, Schema};
+use crate::crypto::{self, CryptoHash};
+use crate::helpers::{Height, Round, ValidatorId};
+use crate::messages::{RawTransaction, Signed};
+use crate::sandbox::{
+    sandbox::{timestamping_sandbox, Sandbox},
+    sandbox_tests_helper::*,
+};
+
+/// - receive two prevotes of the same validator for different proposes in the same round
+/// - the evidence is recorded and both prevotes are broadcast
+/// - the evidence is reported with a transaction
+/// - the evidence is not broadcast again when the prevote is received one more time
+#[test]
+fn record_and_broadcast_conflicting_prevotes() {
+    let sandbox = timestamping_sandbox();
+
+    let prevote = sandbox.create_prevote(
+        ValidatorId(2),
+        Height(1),
+        Round(1),
+        &empty_hash(),
+        NOT_LOCKED,
+        sandbox.s(ValidatorId(2)),
+    );
+    let conflicting_prevote = sandbox.create_prevote(
+        ValidatorId(2),
+        Height(1),
+        Round(1),
+        &crypto::hash(&[1]),
+        NOT_LOCKED,
+        sandbox.s(ValidatorId(2)),
+    );
+
+    sandbox.recv(&prevote);
+    sandbox.recv(&conflicting_prevote);
+
+    let equivocation =
+        Equivocation::from_prevotes(prevote.clone(), conflicting_prevote.clone()).unwrap();
+    if let Equivocation::Prevotes {
+        ref first,
+        ref second,
+    } = equivocation
+    {
+        sandbox.broadcast(first);
+        sandbox.broadcast(second);
+    }
+    let report = report_equivocation(&sandbox, &equivocation);
+    sandbox.broadcast(&report);
+
+    sandbox.recv(&conflicting_prevote);
+
+    let snapshot = sandbox.blockchain_ref().snapshot();
+    let schema = Schema::new(&snapshot);
+    let equivocations = schema.equivocations();
+    assert_eq!(equivocations.values().count(), 1);
+    assert_eq!(equivocations.get(&equivocation.hash()), Some(equivocation));
+    assert!(schema.transactions_pool().contains(&report.hash()));
+}
+
+/// - receive two precommits of the same validator for different proposes in the same round
+/// - the evidence is recorded and both precommits are broadcast
+/// - the evidence is reported with a transaction
+#[test]
+fn record_and_broadcast_conflicting_precommits() {
+    let sandbox = timestamping_sandbox();
+
+    let precommit = sandbox.create_precommit(
+        ValidatorId(3),
+        Height(1),
+        Round(1),
+        &empty_hash(),
+        &empty_hash(),
+        sandbox.time().into(),
+        sandbox.s(ValidatorId(3)),
+    );
+    let conflicting_precommit = sandbox.create_precommit(
+        ValidatorId(3),
+        Height(1),
+        Round(1),
+        &crypto::hash(&[1]),
+        &crypto::hash(&[2]),
+        sandbox.time().into(),
+        sandbox.s(ValidatorId(3)),
+    );
+
+    sandbox.recv(&precommit);
+    sandbox.recv(&conflicting_precommit);
+
+    let equivocation = Equivocation::from_precommits(precommit, conflicting_precommit).unwrap();
+    if let Equivocation::Precommits {
+        ref first,
+        ref second,
+    } = equivocation
+    {
+        sandbox.broadcast(first);
+        sandbox.broadcast(second);
+    }
+    let report = report_equivocation(&sandbox, &equivocation);
+    sandbox.broadcast(&report);
+
+    let snapshot = sandbox.blockchain_ref().snapshot();
+    let schema = Schema::new(&snapshot);
+    let equivocations = schema.equivocations();
+    assert_eq!(equivocations.get(&equivocation.hash()), Some(equivocation));
+    assert!(schema.transactions_pool().contains(&report.hash()));
+}
+
+fn report_equivocation(sandbox: &Sandbox, equivocation: &Equivocation) -> Signed<RawTransaction> {
+    let (public_key, secret_key) = sandbox.blockchain_ref().service_keypair.clone();
+    ReportEquivocation::sign(equivocation.clone(), &public_key, &secret_key)
+}
@@ -53,6 +53,7 @@ fn test_ignore_message_with_incorrect_validator_id() {
             Round(1),
             &sandbox.last_hash(),
             &[],
+            &[],
         ),
         sandbox.p(ValidatorId(1)),
         sandbox.s(ValidatorId(1)),
@@ -121,6 +122,32 @@ fn ignore_propose_with_committed_transaction() {
     //    broadcast here is absent
 }
 
+#[test]
+fn ignore_propose_without_precommits_for_previous_block() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+
+    add_one_height(&sandbox, &sandbox_state);
+
+    let leader = sandbox.current_leader();
+    // The propose does not contain precommits for the block at the previous height,
+    // so Prevote is not broadcast.
+    let propose = Message::concrete(
+        Propose::new(
+            leader,
+            sandbox.current_height(),
+            sandbox.current_round(),
+            &sandbox.last_hash(),
+            &[],
+            &[],
+        ),
+        sandbox.p(leader),
+        sandbox.s(leader),
+    );
+
+    sandbox.recv(&propose);
+}
+
 #[test]
 fn handle_propose_that_sends_before_than_propose_timeout_exceeded() {
     let sandbox = timestamping_sandbox();
@@ -17,9 +17,11 @@
 mod basic;
 mod block_request;
 mod config;
+mod equivocation;
 mod invalid_message;
 mod recovery;
 mod round_details;
+mod state_sync;
 mod timeouts;
 mod transactions;
 mod unsynchronized_message;
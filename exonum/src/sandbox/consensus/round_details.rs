@@ -20,14 +20,16 @@
 use std::collections::HashSet;
 
 use bit_vec::BitVec;
+use chrono::{DateTime, Utc};
 
 use std::time::Duration;
 
 use crate::crypto::CryptoHash;
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::messages::{PrevotesRequest, ProtocolMessage, TransactionsRequest};
-use crate::node::state::{
-    PREVOTES_REQUEST_TIMEOUT, PROPOSE_REQUEST_TIMEOUT, TRANSACTIONS_REQUEST_TIMEOUT,
+use crate::node::{
+    state::{PREVOTES_REQUEST_TIMEOUT, PROPOSE_REQUEST_TIMEOUT, TRANSACTIONS_REQUEST_TIMEOUT},
+    ConsensusStateInfo, ProposeInfo, VotesInfo,
 };
 use crate::sandbox::{
     sandbox::{self, timestamping_sandbox},
@@ -1817,3 +1819,58 @@ fn handle_precommit_remove_propose_request_ask_precommitters() {
         "Should send TransactionsRequest to all validators"
     );
 }
+
+/// idea of the scenario:
+/// - receive propose with unknown tx and prevote for it
+/// - check that the dump of the consensus state contains them
+#[test]
+fn consensus_state_info_contains_proposes_and_votes() {
+    let sandbox = timestamping_sandbox();
+
+    let tx = gen_timestamping_tx();
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx.hash()])
+        .build();
+
+    sandbox.recv(&propose);
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(3),
+        Height(1),
+        Round(1),
+        &propose.hash(),
+        NOT_LOCKED,
+        sandbox.s(ValidatorId(3)),
+    ));
+
+    let info = ConsensusStateInfo::new(&sandbox.node_state(), sandbox.time());
+    assert_eq!(info.height, Height(1));
+    assert_eq!(info.round, Round(1));
+    assert_eq!(info.leader, sandbox.leader(Round(1)));
+    assert_eq!(info.locked_round, NOT_LOCKED);
+    assert_eq!(info.locked_propose, None);
+    assert_eq!(
+        info.proposes,
+        vec![ProposeInfo {
+            hash: propose.hash(),
+            validator: propose.validator(),
+            round: Round(1),
+            block_hash: None,
+            unknown_txs: vec![tx.hash()],
+        }]
+    );
+    assert_eq!(
+        info.prevotes,
+        vec![VotesInfo {
+            round: Round(1),
+            hash: propose.hash(),
+            validators: "0001".to_owned(),
+        }]
+    );
+    assert!(info.precommits.is_empty());
+    assert_eq!(
+        info.validators.iter().map(|v| v.id).collect::<Vec<_>>(),
+        vec![ValidatorId(1), ValidatorId(2), ValidatorId(3)]
+    );
+    assert_eq!(info.queued_messages, 0);
+    assert_eq!(info.time, DateTime::<Utc>::from(sandbox.time()));
+}
@@ -0,0 +1,240 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests in this module are designed to test the state sync protocol.
+
+use crate::blockchain::{Blockchain, Schema, CORE_SERVICE};
+use crate::crypto::Hash;
+use crate::explorer::BlockchainExplorer;
+use crate::helpers::{Height, ValidatorId};
+use crate::messages::{Message, Responses, Signed, StateChunkRequest, StateChunkResponse};
+use crate::node::StateSyncConfig;
+use crate::sandbox::{
+    sandbox::{timestamping_sandbox_builder, Sandbox, SandboxBuilder},
+    sandbox_tests_helper::*,
+    timestamping::TIMESTAMPING_SERVICE,
+};
+
+fn state_sync_config() -> StateSyncConfig {
+    StateSyncConfig {
+        enabled: true,
+        min_lag: 1,
+    }
+}
+
+/// Sends the request from the `requester` node to the `responder` node and returns
+/// the response as if it was sent to the `requester` node. Both nodes act as the validator 0,
+/// so the messages are re-signed with the keys of the validator 1.
+fn serve_state_chunk(
+    responder: &Sandbox,
+    requester: &Sandbox,
+    height: Height,
+    table: &Hash,
+    from: &[u8],
+) -> Signed<StateChunkResponse> {
+    let request = Message::concrete(
+        StateChunkRequest::new(&responder.p(ValidatorId(0)), height, table, from),
+        responder.p(ValidatorId(1)),
+        responder.s(ValidatorId(1)),
+    );
+    responder.recv(&request);
+    responder.process_events();
+    let response = match responder.pop_sent() {
+        Some((_, Message::Responses(Responses::StateChunkResponse(response)))) => response,
+        message => panic!("Expected state chunk response, got {:?}", message),
+    };
+
+    Message::concrete(
+        StateChunkResponse::new(
+            &requester.p(ValidatorId(0)),
+            response.block().clone(),
+            response.precommits(),
+            response.table(),
+            response.from(),
+            &response.proof().unwrap(),
+            response.next(),
+        ),
+        requester.p(ValidatorId(1)),
+        requester.s(ValidatorId(1)),
+    )
+}
+
+/// Makes the `requester` node download the state of the `responder` node, which has committed
+/// two blocks after the genesis one.
+fn sync_state(responder: &Sandbox, requester: &Sandbox) {
+    requester.recv(&requester.create_status(
+        &requester.p(ValidatorId(1)),
+        Height(3),
+        &responder.last_hash(),
+        requester.s(ValidatorId(1)),
+    ));
+
+    for idx in 0..6 {
+        let table = Blockchain::service_table_unique_key(CORE_SERVICE, idx);
+        requester.send(
+            requester.p(ValidatorId(1)),
+            &Message::concrete(
+                StateChunkRequest::new(&requester.p(ValidatorId(1)), Height(2), &table, &[]),
+                requester.p(ValidatorId(0)),
+                requester.s(ValidatorId(0)),
+            ),
+        );
+        let response = serve_state_chunk(&responder, &requester, Height(2), &table, &[]);
+        assert!(response.next().is_empty());
+        requester.recv(&response);
+    }
+}
+
+/// - responder commits two blocks
+/// - requester with no blocks receives `Status` from the peer and downloads the core tables
+///   chunk by chunk in the order of their keys
+/// - requester commits the synced block and broadcasts its `Status`
+#[test]
+fn state_sync_downloads_core_tables() {
+    let responder = SandboxBuilder::new().build();
+    let sandbox_state = SandboxState::new();
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+
+    let requester = SandboxBuilder::new()
+        .with_state_sync(state_sync_config())
+        .build();
+    sync_state(&responder, &requester);
+
+    requester.check_broadcast_status(Height(3), &responder.last_hash());
+    assert_eq!(requester.current_height(), Height(3));
+    assert_eq!(requester.last_block(), responder.last_block());
+    assert_eq!(requester.last_state_hash(), responder.last_state_hash());
+}
+
+/// - requester downloads the state at height 2 from the responder
+/// - blocks below the synced one are unavailable both in the schema and in the explorer
+#[test]
+fn blocks_below_synced_one_are_unavailable() {
+    let responder = SandboxBuilder::new().build();
+    let sandbox_state = SandboxState::new();
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+
+    let requester = SandboxBuilder::new()
+        .with_state_sync(state_sync_config())
+        .build();
+    sync_state(&responder, &requester);
+
+    let blockchain = requester.blockchain_ref();
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.first_block_height(), Height(2));
+    assert_eq!(schema.height(), Height(2));
+    assert_eq!(schema.block_hash_by_height(Height(0)), None);
+    assert_eq!(schema.block_hash_by_height(Height(1)), None);
+    assert_eq!(
+        schema.block_hash_by_height(Height(2)),
+        Some(responder.last_hash())
+    );
+    assert!(schema.block_and_precommits(Height(1)).is_none());
+
+    let explorer = BlockchainExplorer::new(&blockchain);
+    assert_eq!(explorer.first_block_height(), Height(2));
+    assert!(explorer.block(Height(0)).is_none());
+    assert!(explorer.block(Height(1)).is_none());
+    assert!(explorer.block_with_txs(Height(1)).is_none());
+    assert!(explorer.is_block_pruned(Height(1)));
+    assert_eq!(
+        *explorer.block(Height(2)).unwrap().header(),
+        responder.last_block()
+    );
+    assert_eq!(
+        explorer.block_with_txs(Height(2)).unwrap().header,
+        responder.last_block()
+    );
+
+    let heights = explorer
+        .blocks(..)
+        .map(|block| block.height())
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![Height(2)]);
+    let heights = explorer
+        .blocks(..)
+        .rev()
+        .map(|block| block.height())
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![Height(2)]);
+    assert_eq!(explorer.blocks(..Height(2)).count(), 0);
+}
+
+/// - node with the timestamping service, which does not declare its state tables,
+///   receives `Status` from the peer
+/// - node requests the next block instead of starting the state sync
+#[test]
+fn state_sync_is_not_started_for_undeclared_tables() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_state_sync(state_sync_config())
+        .build();
+
+    sandbox.recv(&sandbox.create_status(
+        &sandbox.p(ValidatorId(1)),
+        Height(3),
+        &empty_hash(),
+        sandbox.s(ValidatorId(1)),
+    ));
+    sandbox.send(
+        sandbox.p(ValidatorId(1)),
+        &sandbox.create_block_request(
+            &sandbox.p(ValidatorId(0)),
+            &sandbox.p(ValidatorId(1)),
+            Height(1),
+            sandbox.s(ValidatorId(0)),
+        ),
+    );
+}
+
+/// - node with the timestamping service receives a request for the table of the service
+/// - node does not respond, as the table is not declared
+#[test]
+fn undeclared_table_is_not_served() {
+    let sandbox = timestamping_sandbox_builder().build();
+    add_one_height(&sandbox, &SandboxState::new());
+
+    let table = Blockchain::service_table_unique_key(TIMESTAMPING_SERVICE, 0);
+    sandbox.recv(&Message::concrete(
+        StateChunkRequest::new(&sandbox.p(ValidatorId(0)), Height(1), &table, &[]),
+        sandbox.p(ValidatorId(1)),
+        sandbox.s(ValidatorId(1)),
+    ));
+    sandbox.process_events();
+    assert!(sandbox.pop_sent().is_none());
+}
+
+/// - requester downloads the state at height 2 from the responder
+/// - the synced block is verified, while the blocks below it are skipped
+#[test]
+fn synced_blocks_are_verified() {
+    let responder = SandboxBuilder::new().build();
+    let sandbox_state = SandboxState::new();
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+    add_one_height_with_transactions(&responder, &sandbox_state, &[]);
+
+    let requester = SandboxBuilder::new()
+        .with_state_sync(state_sync_config())
+        .build();
+    sync_state(&responder, &requester);
+
+    let snapshot = requester.blockchain_ref().snapshot();
+    let schema = Schema::new(&snapshot);
+    for height in 0..=2 {
+        assert!(schema.verify_block(Height(height)).is_ok());
+    }
+    assert!(schema.verify_block(Height(3)).is_err());
+}
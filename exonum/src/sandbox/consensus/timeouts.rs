@@ -19,7 +19,10 @@ use std::time::Duration;
 use crate::crypto::CryptoHash;
 use crate::helpers::{Height, Round, ValidatorId};
 use crate::node::state::PROPOSE_REQUEST_TIMEOUT;
-use crate::sandbox::{sandbox::timestamping_sandbox, sandbox_tests_helper::*};
+use crate::sandbox::{
+    sandbox::{timestamping_sandbox, timestamping_sandbox_builder},
+    sandbox_tests_helper::*,
+};
 
 /// HANDLE ROUND TIMEOUT:
 /// - Ignore if height and round are not the same
@@ -284,3 +287,117 @@ fn test_round_timeout_increase() {
     sandbox.add_time(Duration::from_millis(1));
     sandbox.assert_state(Height(1), Round(5));
 }
+
+/// idea of the scenario is to wait for transactions for a long time
+/// then:
+///  - round timeouts expire, but the round is held and timeouts do not increase
+///  - when transaction is received - the round is restarted
+///  - propose with this transaction is sent when we become leader
+#[test]
+fn test_hold_round_while_transaction_pool_is_empty() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_empty_block_interval = Some(100_000);
+        })
+        .build();
+
+    for _ in 0..50 {
+        sandbox.add_time(Duration::from_millis(sandbox.first_round_timeout()));
+        sandbox.assert_state(Height(1), Round(1));
+    }
+
+    let tx = gen_timestamping_tx();
+    sandbox.recv(&tx);
+
+    // the round is restarted
+    sandbox.add_time(Duration::from_millis(sandbox.first_round_timeout() - 1));
+    sandbox.assert_state(Height(1), Round(1));
+    sandbox.add_time(Duration::from_millis(1));
+    sandbox.assert_state(Height(1), Round(2));
+
+    // ok, we are leader
+    sandbox.add_time(Duration::from_millis(
+        sandbox.current_round_timeout() + PROPOSE_TIMEOUT,
+    ));
+    sandbox.assert_state(Height(1), Round(3));
+
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx.hash()])
+        .build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    sandbox.add_time(Duration::from_millis(0));
+}
+
+/// idea of the scenario is to wait for transactions while the leader is offline
+/// then:
+///  - the round is held until `max_empty_block_interval` passes
+///  - the round is restarted, but the leader does not send propose
+///  - rounds advance, and empty propose is sent when we become leader
+#[test]
+fn test_send_empty_propose_after_max_empty_block_interval() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_empty_block_interval = Some(3000);
+        })
+        .build();
+    assert!(!sandbox.is_leader());
+
+    let first_round_timeout = sandbox.first_round_timeout();
+    sandbox.add_time(Duration::from_millis(first_round_timeout));
+    sandbox.assert_state(Height(1), Round(1));
+    sandbox.add_time(Duration::from_millis(3000 - first_round_timeout - 1));
+    sandbox.assert_state(Height(1), Round(1));
+
+    // the round is restarted
+    sandbox.add_time(Duration::from_millis(1));
+    sandbox.assert_state(Height(1), Round(1));
+    sandbox.add_time(Duration::from_millis(first_round_timeout - 1));
+    sandbox.assert_state(Height(1), Round(1));
+    sandbox.add_time(Duration::from_millis(1));
+    sandbox.assert_state(Height(1), Round(2));
+
+    // ok, we are leader
+    sandbox.add_time(Duration::from_millis(
+        sandbox.current_round_timeout() + PROPOSE_TIMEOUT,
+    ));
+    sandbox.assert_state(Height(1), Round(3));
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    sandbox.add_time(Duration::from_millis(0));
+}
+
+/// idea of the scenario is to become leader with the empty transaction pool
+/// then:
+///  - propose timeout is triggered, but propose is not sent
+///  - when transaction is received - propose with this transaction is sent
+#[test]
+fn test_skip_empty_propose_until_transaction_is_received() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_empty_block_interval = Some(100_000);
+        })
+        .build();
+    let sandbox_state = SandboxState::new();
+
+    add_one_height(&sandbox, &sandbox_state);
+    add_one_height(&sandbox, &sandbox_state);
+
+    // ok, we are leader, but the pool is empty
+    sandbox.assert_state(Height(3), Round(1));
+    assert!(sandbox.is_leader());
+    sandbox.add_time(Duration::from_millis(PROPOSE_TIMEOUT));
+
+    let tx = gen_timestamping_tx();
+    sandbox.recv(&tx);
+    sandbox.add_time(Duration::from_millis(0));
+
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx.hash()])
+        .build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    sandbox.add_time(Duration::from_millis(0));
+}
@@ -21,7 +21,10 @@ use std::time::Duration;
 use crate::crypto::{gen_keypair, CryptoHash, Hash};
 use crate::helpers::{Height, Milliseconds, Round, ValidatorId};
 use crate::messages::{RawTransaction, Signed};
-use crate::node::state::TRANSACTIONS_REQUEST_TIMEOUT;
+use crate::node::{
+    state::{BLOCK_REQUEST_TIMEOUT, TRANSACTIONS_REQUEST_TIMEOUT},
+    DefaultTransactionPool, TransactionPoolConfig,
+};
 use crate::sandbox::{
     config_updater::TxConfig,
     sandbox::{timestamping_sandbox, timestamping_sandbox_builder, Sandbox},
@@ -49,9 +52,7 @@ fn timestamping_sandbox_with_threshold() -> Sandbox {
 }
 
 fn tx_hashes(transactions: &[Signed<RawTransaction>]) -> Vec<Hash> {
-    let mut hashes = transactions.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
-    hashes.sort();
-    hashes
+    transactions.iter().map(|tx| tx.hash()).collect()
 }
 
 /// sends transactions into pool and returns this transactions in processing order
@@ -727,3 +728,84 @@ fn expedited_propose_on_transaction_pressure() {
     sandbox.broadcast(&propose);
     sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
 }
+
+/// - receive propose with tx A
+/// - tx A is evicted from the pool by tx B, but is kept in the persistent pool for the propose
+/// - commit block with tx B received from the peer
+/// - tx A is removed from the persistent pool at the new height
+#[test]
+fn evicted_tx_referenced_by_propose_is_removed_after_commit() {
+    let sandbox = timestamping_sandbox();
+    sandbox
+        .node_handler_mut()
+        .set_transaction_pool(Box::new(DefaultTransactionPool::new(
+            TransactionPoolConfig {
+                max_size: 1,
+                ..TransactionPoolConfig::default()
+            },
+        )));
+
+    let tx_a = gen_timestamping_tx();
+    let tx_b = gen_timestamping_tx();
+
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&[tx_a.hash()])
+        .build();
+    sandbox.recv(&tx_a);
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+
+    sandbox.recv(&tx_b);
+    sandbox.assert_pool_len(2);
+
+    let block = BlockBuilder::new(&sandbox)
+        .with_tx_hash(&tx_b.hash())
+        .with_state_hash(&sandbox.compute_state_hash(&[tx_b.clone()]))
+        .build();
+    let precommits = (1..4).map(|i| {
+        sandbox.create_precommit(
+            ValidatorId(i),
+            Height(1),
+            Round(1),
+            &propose.hash(),
+            &block.hash(),
+            sandbox.time().into(),
+            sandbox.s(ValidatorId(i)),
+        )
+    });
+    let precommits = precommits.collect::<Vec<_>>();
+
+    sandbox.recv(&sandbox.create_status(
+        &sandbox.p(ValidatorId(3)),
+        Height(2),
+        &block.hash(),
+        sandbox.s(ValidatorId(3)),
+    ));
+    sandbox.add_time(Duration::from_millis(BLOCK_REQUEST_TIMEOUT));
+    sandbox.send(
+        sandbox.p(ValidatorId(3)),
+        &sandbox.create_block_request(
+            &sandbox.p(ValidatorId(0)),
+            &sandbox.p(ValidatorId(3)),
+            Height(1),
+            sandbox.s(ValidatorId(0)),
+        ),
+    );
+    sandbox.recv(&sandbox.create_block_response(
+        &sandbox.p(ValidatorId(3)),
+        &sandbox.p(ValidatorId(0)),
+        block.clone(),
+        precommits,
+        &[tx_b.hash()],
+        sandbox.s(ValidatorId(3)),
+    ));
+
+    sandbox.assert_state(Height(2), Round(1));
+    sandbox.broadcast(&sandbox.create_status(
+        &sandbox.p(ValidatorId(0)),
+        Height(2),
+        &block.hash(),
+        sandbox.s(ValidatorId(0)),
+    ));
+    sandbox.assert_pool_len(0);
+}
@@ -33,10 +33,14 @@ use super::{
 };
 use crate::{
     blockchain::{
-        Block, BlockProof, Blockchain, ConsensusConfig, GenesisConfig, Schema, Service,
-        SharedNodeState, StoredConfiguration, Transaction, ValidatorKeys,
+        block_time, Block, BlockProof, Blockchain, ConsensusConfig, GenesisConfig,
+        LeaderElectionStrategy, Schema, Service, SharedNodeState, StoredConfiguration, Transaction,
+        ValidatorKeys,
+    },
+    crypto::{
+        gen_keypair, gen_keypair_from_seed, CryptoHash, Hash, PublicKey, SecretKey, Seed,
+        SEED_LENGTH,
     },
-    crypto::{gen_keypair, gen_keypair_from_seed, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH},
     events::{
         network::NetworkConfiguration, Event, EventHandler, InternalEvent, InternalRequest,
         NetworkEvent, NetworkRequest, TimeoutRequest,
@@ -50,7 +54,7 @@ use crate::{
     node::{
         ApiSender, Configuration, ConnectInfo, ConnectList, ConnectListConfig, ExternalMessage,
         ListenerConfig, NodeHandler, NodeSender, PeerAddress, ServiceConfig, State,
-        SystemStateProvider,
+        StateSyncConfig, SystemStateProvider,
     },
     storage::{MapProof, MemoryDB},
 };
@@ -230,7 +234,11 @@ impl Sandbox {
         last_hash: &Hash,
         secret_key: &SecretKey,
     ) -> Signed<Status> {
-        Message::concrete(Status::new(height, last_hash), *author, secret_key)
+        Message::concrete(
+            Status::new(height, last_hash, Height::zero()),
+            *author,
+            secret_key,
+        )
     }
 
     /// Creates a `BlockResponse` message signed by this validator.
@@ -291,8 +299,16 @@ impl Sandbox {
         tx_hashes: &[Hash],
         secret_key: &SecretKey,
     ) -> Signed<Propose> {
+        let prev_precommits = self.precommits_of(last_hash);
         Message::concrete(
-            Propose::new(validator_id, height, round, last_hash, tx_hashes),
+            Propose::new(
+                validator_id,
+                height,
+                round,
+                last_hash,
+                tx_hashes,
+                &prev_precommits,
+            ),
             self.p(validator_id),
             secret_key,
         )
@@ -570,10 +586,10 @@ impl Sandbox {
     }
 
     pub fn check_broadcast_status(&self, height: Height, block_hash: &Hash) {
-        self.broadcast(&self.create_status(
-            &self.node_public_key(),
-            height,
-            block_hash,
+        let pruned_height = Schema::new(&self.blockchain_ref().snapshot()).pruned_height();
+        self.broadcast(&Message::concrete(
+            Status::new(height, block_hash, pruned_height),
+            self.node_public_key(),
             &self.node_secret_key(),
         ));
     }
@@ -625,6 +641,23 @@ impl Sandbox {
         *self.last_block().state_hash()
     }
 
+    /// Returns pre-commits for the block with the given hash stored by the node.
+    pub fn precommits_of(&self, block_hash: &Hash) -> Vec<Signed<Precommit>> {
+        let snapshot = self.blockchain_ref().snapshot();
+        let precommits = Schema::new(&snapshot)
+            .precommits(block_hash)
+            .iter()
+            .collect();
+        precommits
+    }
+
+    /// Returns the time of the block following the latest committed one, if the proposal
+    /// of the block contains pre-commits for the latest block stored by the node.
+    pub fn next_block_time(&self) -> chrono::DateTime<chrono::Utc> {
+        let last_block = self.last_block();
+        block_time(&self.precommits_of(&last_block.hash()), last_block.time())
+    }
+
     pub fn filter_present_transactions<'a, I>(&self, txs: I) -> Vec<Signed<RawTransaction>>
     where
         I: IntoIterator<Item = &'a Signed<RawTransaction>>,
@@ -857,6 +890,7 @@ impl Sandbox {
             network: NetworkConfiguration::default(),
             peer_discovery: Vec::new(),
             mempool: Default::default(),
+            state_sync: Default::default(),
         };
 
         let system_state = SandboxSystemStateProvider {
@@ -956,6 +990,7 @@ pub struct SandboxBuilder {
     services: Vec<Box<dyn Service>>,
     validators_count: u8,
     consensus_config: ConsensusConfig,
+    state_sync_config: StateSyncConfig,
 }
 
 impl SandboxBuilder {
@@ -973,7 +1008,10 @@ impl SandboxBuilder {
                 min_propose_timeout: PROPOSE_TIMEOUT,
                 max_propose_timeout: PROPOSE_TIMEOUT,
                 propose_timeout_threshold: std::u32::MAX,
+                leader_election: LeaderElectionStrategy::default(),
+                max_empty_block_interval: None,
             },
+            state_sync_config: StateSyncConfig::default(),
         }
     }
 
@@ -997,6 +1035,11 @@ impl SandboxBuilder {
         self
     }
 
+    pub fn with_state_sync(mut self, state_sync_config: StateSyncConfig) -> Self {
+        self.state_sync_config = state_sync_config;
+        self
+    }
+
     pub fn build(self) -> Sandbox {
         let _ = env_logger::Builder::from_default_env()
             .target(env_logger::Target::Stdout)
@@ -1005,6 +1048,7 @@ impl SandboxBuilder {
         let mut sandbox = sandbox_with_services_uninitialized(
             self.services,
             self.consensus_config,
+            self.state_sync_config,
             self.validators_count,
         );
 
@@ -1027,6 +1071,7 @@ fn gen_primitive_socket_addr(idx: u8) -> SocketAddr {
 fn sandbox_with_services_uninitialized(
     services: Vec<Box<dyn Service>>,
     consensus: ConsensusConfig,
+    state_sync: StateSyncConfig,
     validators_count: u8,
 ) -> Sandbox {
     let validators = (0..validators_count)
@@ -1063,7 +1108,7 @@ fn sandbox_with_services_uninitialized(
         ApiSender::new(api_channel.0.clone()),
     );
 
-    let genesis = GenesisConfig::new_with_consensus(
+    let mut genesis = GenesisConfig::new_with_consensus(
         consensus,
         validators
             .iter()
@@ -1073,6 +1118,8 @@ fn sandbox_with_services_uninitialized(
                 service_key: (x.1).0,
             }),
     );
+    // Sandboxes built separately should share the genesis block.
+    genesis.time = (UNIX_EPOCH + Duration::new(INITIAL_TIME_IN_SECS, 0)).into();
 
     let connect_list_config =
         ConnectListConfig::from_validator_keys(&genesis.validator_keys, &str_addresses);
@@ -1093,6 +1140,7 @@ fn sandbox_with_services_uninitialized(
         network: NetworkConfiguration::default(),
         peer_discovery: Vec::new(),
         mempool: Default::default(),
+        state_sync,
     };
 
     let system_state = SandboxSystemStateProvider {
@@ -104,6 +104,8 @@ impl<'a> BlockBuilder<'a> {
             &self
                 .state_hash
                 .unwrap_or_else(|| self.sandbox.last_state_hash()),
+            &Hash::zero(),
+            self.sandbox.next_block_time(),
         )
     }
 }
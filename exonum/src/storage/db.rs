@@ -21,9 +21,10 @@ use std::{
         HashMap,
     },
     iter::{Iterator as StdIterator, Peekable},
+    path::Path,
 };
 
-use super::Result;
+use super::{Error, Result};
 
 /// Map containing changes with a corresponding key.
 #[derive(Debug, Clone)]
@@ -285,6 +286,17 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error, the method guarantees no changes are applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Creates a consistent copy of the current database state in the given directory,
+    /// which should not exist beforehand. The copy can be opened as a separate database.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns an error, as checkpoints are not supported
+    /// by all databases.
+    fn create_checkpoint(&self, _path: &Path) -> Result<()> {
+        Err(Error::new("Checkpoints are not supported by the database"))
+    }
 }
 
 /// A read-only snapshot of a storage backend.
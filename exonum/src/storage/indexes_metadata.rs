@@ -20,7 +20,10 @@ use std::fmt;
 
 use crate::crypto::{self, CryptoHash, Hash};
 use crate::proto::{self, ProtobufConvert};
-use crate::storage::{base_index::BaseIndex, Fork, Snapshot, StorageValue};
+use crate::storage::{
+    base_index::{self, BaseIndex},
+    Fork, Snapshot, StorageValue,
+};
 
 pub const INDEXES_METADATA_TABLE_NAME: &str = "__INDEXES_METADATA__";
 
@@ -56,6 +59,9 @@ pub enum IndexType {
     ProofList = 5,
     ProofMap = 6,
     ValueSet = 7,
+    HashedProofMap = 8,
+    ProofKeySet = 9,
+    ProofValueSet = 10,
 }
 
 impl ProtobufConvert for IndexType {
@@ -76,6 +82,9 @@ impl ProtobufConvert for IndexType {
             5 => ProofList,
             6 => ProofMap,
             7 => ValueSet,
+            8 => HashedProofMap,
+            9 => ProofKeySet,
+            10 => ProofValueSet,
             invalid => bail!(
                 "Unreachable pattern ({:?}) while constructing table type. \
                  Storage data is probably corrupted",
@@ -179,6 +188,69 @@ impl fmt::Display for StorageMetadata {
     }
 }
 
+/// Returns names of all indexes registered in the storage, including the metadata table itself.
+///
+/// Names of index families are returned once per family.
+pub fn index_names<T: AsRef<dyn Snapshot>>(view: T) -> Vec<String> {
+    let metadata = BaseIndex::indexes_metadata(view);
+    let mut names = metadata
+        .iter::<_, String, Vec<u8>>(&())
+        .map(|(name, _)| name)
+        .filter(|name| name != CORE_STORAGE_METADATA_KEY)
+        .collect::<Vec<_>>();
+    names.push(INDEXES_METADATA_TABLE_NAME.to_owned());
+    names
+}
+
+/// Returns names of all ordinary (i.e., not belonging to a family) indexes of the given type.
+pub fn index_names_by_type<T: AsRef<dyn Snapshot>>(view: T, index_type: IndexType) -> Vec<String> {
+    let metadata = BaseIndex::indexes_metadata(view);
+    metadata
+        .iter::<_, String, Vec<u8>>(&())
+        .filter(|(name, _)| name != CORE_STORAGE_METADATA_KEY)
+        .filter_map(|(name, value)| {
+            let metadata = IndexMetadata::from_bytes(value.into());
+            if metadata.index_type == index_type && !metadata.is_family {
+                Some(name)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns names of all indexes registered in the storage together with their types
+/// and family flags, sorted by name. The metadata table itself is not included.
+pub fn indexes_metadata<T: AsRef<dyn Snapshot>>(view: T) -> Vec<(String, IndexType, bool)> {
+    let metadata = BaseIndex::indexes_metadata(view);
+    metadata
+        .iter::<_, String, Vec<u8>>(&())
+        .filter(|(name, _)| name != CORE_STORAGE_METADATA_KEY)
+        .map(|(name, value)| {
+            let metadata = IndexMetadata::from_bytes(value.into());
+            (name, metadata.index_type, metadata.is_family)
+        })
+        .collect()
+}
+
+/// Returns `true` if an ordinary index of the given type can be accessed by the given name,
+/// i.e., the index either does not exist or has been created with the same type.
+pub fn is_index_type_compatible(name: &str, index_type: IndexType, view: &dyn Snapshot) -> bool {
+    if name.is_empty()
+        || !base_index::is_valid_name(name)
+        || name == INDEXES_METADATA_TABLE_NAME
+        || name == CORE_STORAGE_METADATA_KEY
+    {
+        return false;
+    }
+    let metadata = BaseIndex::indexes_metadata(view);
+    metadata
+        .get::<_, IndexMetadata>(name)
+        .map_or(true, |value| {
+            value.index_type == index_type && !value.is_family
+        })
+}
+
 pub fn set_index_type(name: &str, index_type: IndexType, is_family: bool, view: &mut Fork) {
     if name == INDEXES_METADATA_TABLE_NAME || name == CORE_STORAGE_METADATA_KEY {
         panic!("Attempt to access an internal storage infrastructure");
@@ -189,10 +261,25 @@ pub fn set_index_type(name: &str, index_type: IndexType, is_family: bool, view:
     }
 }
 
+/// Removes all data of the index or the index family with the given name together with
+/// its metadata, so that the name can be reused by an index of another type.
+///
+/// # Panics
+///
+/// Panics if the name belongs to the storage infrastructure.
+pub fn drop_index(name: &str, view: &mut Fork) {
+    if name == INDEXES_METADATA_TABLE_NAME || name == CORE_STORAGE_METADATA_KEY {
+        panic!("Attempt to access an internal storage infrastructure");
+    }
+    view.remove_by_prefix(name, None);
+    let mut metadata = BaseIndex::indexes_metadata(view);
+    metadata.remove(name);
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        IndexMetadata, IndexType, StorageMetadata, CORE_STORAGE_METADATA,
+        drop_index, IndexMetadata, IndexType, StorageMetadata, CORE_STORAGE_METADATA,
         CORE_STORAGE_METADATA_KEY, INDEXES_METADATA_TABLE_NAME,
     };
     use crate::crypto::{Hash, PublicKey};
@@ -234,6 +321,22 @@ mod tests {
         index.put(&"TestKey".to_string(), 42);
     }
 
+    #[test]
+    fn drop_index_with_another_type() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        {
+            let mut index = ProofMapIndex::new("test_index", &mut fork);
+            index.put(&PublicKey::zero(), 42);
+        }
+
+        drop_index("test_index", &mut fork);
+        let mut index: MapIndex<_, PublicKey, i32> = MapIndex::new("test_index", &mut fork);
+        assert!(index.get(&PublicKey::zero()).is_none());
+        index.put(&PublicKey::zero(), 1);
+        assert_eq!(index.get(&PublicKey::zero()), Some(1));
+    }
+
     #[test]
     #[should_panic(expected = "Attempt to access index 'test_index' of type Map, \
                                while said index was initially created with type ProofMap")]
@@ -76,8 +76,12 @@
 //!   proofs of existence and is implemented as a Merkle tree.
 //! - [`ProofMapIndex`] is a Merkelized version of `MapIndex` that supports cryptographic
 //!   proofs of existence and is implemented as a binary Merkle Patricia tree.
+//! - [`HashedProofMapIndex`] is a variant of `ProofMapIndex` that supports keys of
+//!   arbitrary length by using their hashes as paths in the Merkle Patricia tree.
 //! - [`KeySetIndex`] and [`ValueSetIndex`] is a set of items, similar to [`BTreeSet`] and
 //!   [`HashSet`].
+//! - [`ProofKeySetIndex`] and [`ProofValueSetIndex`] are Merkelized versions of the sets
+//!   that support cryptographic proofs of membership and non-membership.
 //!
 //! [`Database`]: trait.Database.html
 //! [`RocksDB`]: struct.RocksDB.html
@@ -96,8 +100,11 @@
 //! [`MapIndex`]: map_index/struct.MapIndex.html
 //! [`ProofListIndex`]: proof_list_index/struct.ProofListIndex.html
 //! [`ProofMapIndex`]: proof_map_index/struct.ProofMapIndex.html
+//! [`HashedProofMapIndex`]: proof_map_index/struct.HashedProofMapIndex.html
 //! [`KeySetIndex`]: key_set_index/struct.KeySetIndex.html
 //! [`ValueSetIndex`]: value_set_index/struct.ValueSetIndex.html
+//! [`ProofKeySetIndex`]: proof_key_set_index/struct.ProofKeySetIndex.html
+//! [`ProofValueSetIndex`]: proof_value_set_index/struct.ProofValueSetIndex.html
 //! [doc:storage]: https://exonum.com/doc/architecture/storage
 //! [`Option`]: https://doc.rust-lang.org/std/option/enum.Option.html
 //! [`Box`]: https://doc.rust-lang.org/std/boxed/struct.Box.html
@@ -106,10 +113,15 @@
 //! [`BTreeSet`]: https://doc.rust-lang.org/std/collections/struct.BTreeSet.html
 //! [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
 
-pub(crate) use self::indexes_metadata::StorageMetadata;
+pub(crate) use self::indexes_metadata::{
+    index_names, index_names_by_type, indexes_metadata, is_index_type_compatible, IndexType,
+    StorageMetadata,
+};
 
 #[doc(no_inline)]
-pub use self::proof_map_index::{HashedKey, MapProof, ProofMapIndex};
+pub use self::proof_map_index::{
+    Hashed, HashedKey, HashedProofMapIndex, MapProof, MapRangeProof, ProofMapIndex,
+};
 pub use self::{
     db::{
         Change, Changes, ChangesIterator, Database, Fork, Iter, Iterator, Patch, PatchIterator,
@@ -118,13 +130,16 @@ pub use self::{
     entry::Entry,
     error::Error,
     hash::UniqueHash,
+    indexes_metadata::drop_index,
     key_set_index::KeySetIndex,
     keys::StorageKey,
     list_index::ListIndex,
     map_index::MapIndex,
     memorydb::MemoryDB,
-    options::DbOptions,
-    proof_list_index::{ListProof, ProofListIndex},
+    options::{CompressionType, DbOptions, IndexOptions},
+    proof_key_set_index::ProofKeySetIndex,
+    proof_list_index::{ListConsistencyProof, ListProof, ProofListIndex},
+    proof_value_set_index::ProofValueSetIndex,
     rocksdb::RocksDB,
     sparse_list_index::SparseListIndex,
     value_set_index::ValueSetIndex,
@@ -149,8 +164,10 @@ mod values;
 pub mod key_set_index;
 pub mod list_index;
 pub mod map_index;
+pub mod proof_key_set_index;
 pub mod proof_list_index;
 pub mod proof_map_index;
+pub mod proof_value_set_index;
 pub mod sparse_list_index;
 pub mod value_set_index;
 
@@ -14,10 +14,36 @@
 
 //! Abstract settings for databases.
 
+use serde::{de::Error, Deserialize, Deserializer};
+
+use std::collections::BTreeMap;
+
 /// Options for the database.
 ///
 /// These parameters apply to the underlying database of Exonum, currently `RocksDB`.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+///
+/// # Examples
+///
+/// Tuning options can be specified for all indexes and overridden for the indexes
+/// which names start with a given prefix. For example, transactions can be compressed
+/// more heavily than the rest of the data in the node TOML config:
+///
+/// ```toml
+/// [database]
+/// create_if_missing = true
+///
+/// [database.indexes]
+/// compression = "lz4"
+/// block_cache_size = 268435456
+///
+/// [database.index_overrides."core.transactions"]
+/// compression = "zstd"
+///
+/// [database.index_overrides."core.state_hash_aggregator"]
+/// block_cache_size = 1073741824
+/// bloom_filter_bits = 10
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DbOptions {
     /// Number of open files that can be used by the database.
     ///
@@ -38,6 +64,52 @@ pub struct DbOptions {
     ///
     /// Defaults to `true`.
     pub create_if_missing: bool,
+    /// Number of the latest blocks for which transaction bodies, lists of block transactions
+    /// and precommits are retained.
+    ///
+    /// Data for older blocks is removed from the database on every commit. Block headers,
+    /// transaction locations and results, as well as the `state_hash_aggregator` are never
+    /// pruned, so the state proofs remain available. A pruned node cannot serve old blocks
+    /// to lagging peers.
+    ///
+    /// Defaults to `None`, meaning that the node keeps the whole history. The value
+    /// of `0` is rejected, as at least the latest block must be kept.
+    #[serde(default, deserialize_with = "deserialize_keep_blocks")]
+    pub keep_blocks: Option<u64>,
+    /// An option to indicate whether the node should keep historical versions of the state.
+    ///
+    /// In the archive mode, the previous values of all the keys changed by a block are
+    /// stored together with the block, which allows to obtain a read-only snapshot
+    /// of the state at any height committed after the archive mode has been enabled.
+    /// The archive grows with every block, so this option is intended for nodes serving
+    /// historical queries, e.g., to auditors.
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub archive: bool,
+    /// Tuning options applied to all indexes, unless overridden in `index_overrides`.
+    ///
+    /// Defaults to the options of the underlying database.
+    #[serde(default)]
+    pub indexes: IndexOptions,
+    /// Tuning options for the indexes which names start with the given prefix. If several
+    /// prefixes match the name of an index, the longest one is used. Options not specified
+    /// in the override are taken from `indexes`.
+    ///
+    /// Defaults to no overrides.
+    #[serde(default)]
+    pub index_overrides: BTreeMap<String, IndexOptions>,
+}
+
+impl DbOptions {
+    /// Returns the tuning options for the index with the given name.
+    pub fn index_options(&self, name: &str) -> IndexOptions {
+        self.index_overrides
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.indexes, |(_, options)| options.or(self.indexes))
+    }
 }
 
 impl Default for DbOptions {
@@ -45,6 +117,140 @@ impl Default for DbOptions {
         Self {
             max_open_files: None,
             create_if_missing: true,
+            keep_blocks: None,
+            archive: false,
+            indexes: IndexOptions::default(),
+            index_overrides: BTreeMap::new(),
+        }
+    }
+}
+
+fn deserialize_keep_blocks<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let keep_blocks = Option::<u64>::deserialize(deserializer)?;
+    if keep_blocks == Some(0) {
+        return Err(D::Error::custom(
+            "`keep_blocks` should be positive, as at least one block must be kept",
+        ));
+    }
+    Ok(keep_blocks)
+}
+
+/// Tuning options for the indexes.
+///
+/// Options set to `None` are left at the defaults of the underlying database. Indexes
+/// belonging to the same family share the options, which are determined by the family name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexOptions {
+    /// Compression algorithm for the index data.
+    #[serde(default)]
+    pub compression: Option<CompressionType>,
+    /// Size of the block cache in bytes. The cache specified in `DbOptions::indexes` is shared
+    /// by all indexes. Indexes which override the cache size or bloom filters in
+    /// `DbOptions::index_overrides` get a separate cache for each index or index family.
+    #[serde(default)]
+    pub block_cache_size: Option<usize>,
+    /// Size of the in-memory write buffer in bytes, after which the buffer is flushed
+    /// to disk.
+    #[serde(default)]
+    pub write_buffer_size: Option<usize>,
+    /// Number of bits per key in the bloom filter, which speeds up lookups of keys
+    /// at the cost of memory. The value of `10` gives about 1% of false positives.
+    #[serde(default)]
+    pub bloom_filter_bits: Option<i32>,
+}
+
+impl IndexOptions {
+    /// Returns options, in which unspecified values are taken from `defaults`.
+    fn or(self, defaults: Self) -> Self {
+        Self {
+            compression: self.compression.or(defaults.compression),
+            block_cache_size: self.block_cache_size.or(defaults.block_cache_size),
+            write_buffer_size: self.write_buffer_size.or(defaults.write_buffer_size),
+            bloom_filter_bits: self.bloom_filter_bits.or(defaults.bloom_filter_bits),
         }
     }
 }
+
+/// Compression algorithms supported by the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionType {
+    /// No compression.
+    None,
+    /// [Snappy](https://github.com/google/snappy) compression.
+    Snappy,
+    /// [Zlib](https://zlib.net) compression.
+    Zlib,
+    /// [Bzip2](http://www.bzip.org) compression.
+    Bz2,
+    /// [LZ4](https://lz4.github.io/lz4/) compression.
+    Lz4,
+    /// High compression mode of LZ4.
+    Lz4hc,
+    /// [Zstandard](https://facebook.github.io/zstd/) compression.
+    Zstd,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_options_overrides() {
+        let options: DbOptions = toml::from_str(
+            r#"
+            max_open_files = 256
+            create_if_missing = true
+
+            [indexes]
+            compression = "lz4"
+            block_cache_size = 1024
+
+            [index_overrides."core."]
+            write_buffer_size = 2048
+
+            [index_overrides."core.transactions"]
+            compression = "zstd"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            options.index_options("service.wallets"),
+            IndexOptions {
+                compression: Some(CompressionType::Lz4),
+                block_cache_size: Some(1024),
+                ..IndexOptions::default()
+            }
+        );
+        assert_eq!(
+            options.index_options("core.blocks"),
+            IndexOptions {
+                compression: Some(CompressionType::Lz4),
+                block_cache_size: Some(1024),
+                write_buffer_size: Some(2048),
+                ..IndexOptions::default()
+            }
+        );
+        assert_eq!(
+            options.index_options("core.transactions_pool"),
+            IndexOptions {
+                compression: Some(CompressionType::Zstd),
+                block_cache_size: Some(1024),
+                ..IndexOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn zero_keep_blocks_is_rejected() {
+        let options: DbOptions =
+            toml::from_str("create_if_missing = true\nkeep_blocks = 5").unwrap();
+        assert_eq!(options.keep_blocks, Some(5));
+        let options = toml::from_str::<DbOptions>("create_if_missing = true\nkeep_blocks = 0");
+        assert!(options.is_err());
+    }
+}
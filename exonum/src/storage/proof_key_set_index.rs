@@ -0,0 +1,542 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a Merkelized set for items that utilize the `StorageKey` trait.
+//!
+//! `ProofKeySetIndex` implements a set on top of a Merkle Patricia tree, which allows
+//! to prove membership and non-membership of the items. The given section contains
+//! information on the methods related to `ProofKeySetIndex` and the iterator over
+//! the items of this set.
+
+use std::fmt;
+
+use super::{
+    base_index::BaseIndex,
+    indexes_metadata::IndexType,
+    proof_map_index::{Hashed, HashedProofMapIndex, HashedProofMapIndexKeys},
+    Fork, MapProof, Snapshot, StorageKey,
+};
+use crate::crypto::Hash;
+
+/// A Merkelized set of key items.
+///
+/// `ProofKeySetIndex` implements a set that stores the elements in the leaves
+/// of a Merkle Patricia tree with empty values, using the hashes of the elements
+/// as paths in the tree. Thus, elements of any size are supported, and the set
+/// provides proofs of membership and non-membership for its elements.
+/// `ProofKeySetIndex` requires that elements should implement the [`StorageKey`] trait.
+///
+/// The proofs are [`MapProof`]s with the elements as keys and `()` as values.
+///
+/// [`StorageKey`]: ../trait.StorageKey.html
+/// [`MapProof`]: ../proof_map_index/struct.MapProof.html
+pub struct ProofKeySetIndex<T, K> {
+    inner: HashedProofMapIndex<T, K, ()>,
+}
+
+/// An iterator over the items of a `ProofKeySetIndex`.
+///
+/// This struct is created by the [`iter`] method on [`ProofKeySetIndex`].
+/// See its documentation for details.
+///
+/// [`iter`]: struct.ProofKeySetIndex.html#method.iter
+/// [`ProofKeySetIndex`]: struct.ProofKeySetIndex.html
+#[derive(Debug)]
+pub struct ProofKeySetIndexIter<'a, K> {
+    inner: HashedProofMapIndexKeys<'a, K>,
+}
+
+impl<T, K> ProofKeySetIndex<T, K>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, u8> = ProofKeySetIndex::new(name, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: ProofKeySetIndex<_, u8> = ProofKeySetIndex::new(name, &mut fork);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        Self {
+            inner: HashedProofMapIndex::from_base(BaseIndex::new(
+                index_name,
+                IndexType::ProofKeySet,
+                view,
+            )),
+        }
+    }
+
+    /// Creates a new index representation based on the name, index ID in family
+    /// and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let index_id = vec![123];
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, u8> =
+    ///     ProofKeySetIndex::new_in_family(name, &index_id, &snapshot);
+    /// ```
+    pub fn new_in_family<S, I>(family_name: S, index_id: &I, view: T) -> Self
+    where
+        I: StorageKey,
+        I: ?Sized,
+        S: AsRef<str>,
+    {
+        Self {
+            inner: HashedProofMapIndex::from_base(BaseIndex::new_in_family(
+                family_name,
+                index_id,
+                IndexType::ProofKeySet,
+                view,
+            )),
+        }
+    }
+
+    /// Returns `true` if the set contains the indicated value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    /// assert!(!index.contains(&1));
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    /// ```
+    pub fn contains(&self, item: &K) -> bool {
+        self.inner.contains(item)
+    }
+
+    /// Returns the Merkle root hash of the set or the default hash value if it is empty.
+    /// The default hash consists solely of zeroes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    /// assert_eq!(Hash::default(), index.merkle_root());
+    ///
+    /// index.insert(1);
+    /// assert_ne!(Hash::default(), index.merkle_root());
+    /// ```
+    pub fn merkle_root(&self) -> Hash {
+        self.inner.merkle_root()
+    }
+
+    /// Returns the proof of membership or non-membership for the specified item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    /// index.insert(1_u32);
+    ///
+    /// let proof = index.get_proof(1).check().unwrap();
+    /// assert_eq!(proof.merkle_root(), index.merkle_root());
+    /// assert_eq!(proof.entries().count(), 1);
+    ///
+    /// let proof = index.get_proof(2).check().unwrap();
+    /// assert_eq!(proof.merkle_root(), index.merkle_root());
+    /// assert_eq!(proof.missing_keys().count(), 1);
+    /// ```
+    pub fn get_proof(&self, item: K) -> MapProof<Hashed<K>, ()> {
+        self.inner.get_proof(item)
+    }
+
+    /// Returns the combined proof of membership or non-membership for the multiple
+    /// specified items.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, u8> = ProofKeySetIndex::new(name, &snapshot);
+    ///
+    /// let proof = index.get_multiproof(vec![1, 2, 3]);
+    /// ```
+    pub fn get_multiproof<KI>(&self, items: KI) -> MapProof<Hashed<K>, ()>
+    where
+        KI: IntoIterator<Item = K>,
+    {
+        self.inner.get_multiproof(items)
+    }
+
+    /// Returns an iterator visiting all elements in the order of their hashes.
+    /// The iterator element type is `K::Owned`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofKeySetIndex<_, u8> = ProofKeySetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter() {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter(&self) -> ProofKeySetIndexIter<K> {
+        ProofKeySetIndexIter {
+            inner: self.inner.keys(),
+        }
+    }
+}
+
+impl<'a, K> ProofKeySetIndex<&'a mut Fork, K>
+where
+    K: StorageKey,
+{
+    /// Adds a key to the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    /// ```
+    #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
+    pub fn insert(&mut self, item: K) {
+        self.inner.put(&item, ())
+    }
+
+    /// Removes a key from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    ///
+    /// index.remove(&1);
+    /// assert!(!index.contains(&1));
+    /// ```
+    pub fn remove(&mut self, item: &K) {
+        self.inner.remove(item)
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// # Notes
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofKeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofKeySetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    ///
+    /// index.clear();
+    /// assert!(!index.contains(&1));
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+}
+
+impl<'a, T, K> ::std::iter::IntoIterator for &'a ProofKeySetIndex<T, K>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+{
+    type Item = K::Owned;
+    type IntoIter = ProofKeySetIndexIter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K> Iterator for ProofKeySetIndexIter<'a, K>
+where
+    K: StorageKey,
+{
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<T, K> fmt::Debug for ProofKeySetIndex<T, K>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProofKeySetIndex")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::super::{Database, KeySetIndex, MemoryDB};
+    use super::*;
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    fn items() -> Vec<String> {
+        (0..10).map(|i| format!("item #{}", i)).collect()
+    }
+
+    fn is_valid(proof: &MapProof<Hashed<String>, ()>, root: Hash) -> bool {
+        proof
+            .clone()
+            .check()
+            .map_or(false, |proof| proof.merkle_root() == root)
+    }
+
+    #[test]
+    fn proof_key_set_methods() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+
+        let items = vec!["revoked #1".to_owned(), "revoked #2".to_owned()];
+        for item in &items {
+            index.insert(item.clone());
+        }
+        assert!(index.contains(&items[0]));
+        assert!(!index.contains(&"valid".to_owned()));
+
+        let mut stored_items: Vec<_> = index.iter().collect();
+        stored_items.sort();
+        assert_eq!(stored_items, items);
+
+        let proof = index.get_multiproof(vec![items[1].clone(), "valid".to_owned()]);
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof: MapProof<Hashed<String>, ()> = serde_json::from_str(&json).unwrap();
+        let proof = proof.check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        let present: Vec<_> = proof.entries().map(|(item, _)| item.as_str()).collect();
+        assert_eq!(present, vec!["revoked #2"]);
+        let missing: Vec<_> = proof.missing_keys().map(|item| item.as_str()).collect();
+        assert_eq!(missing, vec!["valid"]);
+
+        index.remove(&items[0]);
+        assert!(!index.contains(&items[0]));
+        index.clear();
+        assert_eq!(index.iter().count(), 0);
+        assert_eq!(index.merkle_root(), Hash::zero());
+    }
+
+    #[test]
+    fn membership_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+
+        for item in &items {
+            let proof = index.get_proof(item.clone()).check().unwrap();
+            assert_eq!(proof.merkle_root(), index.merkle_root());
+            let present: Vec<_> = proof.entries().map(|(item, _)| item.as_str()).collect();
+            assert_eq!(present, vec![item.as_str()]);
+            assert_eq!(proof.missing_keys().count(), 0);
+        }
+
+        let proof = index.get_multiproof(items.clone()).check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        let mut present: Vec<_> = proof.entries().map(|(item, _)| (**item).clone()).collect();
+        present.sort();
+        assert_eq!(present, items);
+    }
+
+    #[test]
+    fn non_membership_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items[..5] {
+            index.insert(item.clone());
+        }
+
+        for item in &items[5..] {
+            let proof = index.get_proof(item.clone()).check().unwrap();
+            assert_eq!(proof.merkle_root(), index.merkle_root());
+            assert_eq!(proof.entries().count(), 0);
+            let missing: Vec<_> = proof.missing_keys().map(|item| item.as_str()).collect();
+            assert_eq!(missing, vec![item.as_str()]);
+        }
+    }
+
+    #[test]
+    fn proofs_in_empty_set() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let index: ProofKeySetIndex<_, String> = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+
+        let proof = index.get_proof("missing".to_owned());
+        assert_eq!(proof.proof_unchecked(), vec![]);
+        let proof = proof.check().unwrap();
+        assert_eq!(proof.merkle_root(), Hash::zero());
+        assert_eq!(proof.missing_keys().count(), 1);
+
+        let proof = index.get_multiproof(items()).check().unwrap();
+        assert_eq!(proof.merkle_root(), Hash::zero());
+        assert_eq!(proof.missing_keys().count(), items().len());
+    }
+
+    #[test]
+    fn proofs_after_remove() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+        let initial_root = index.merkle_root();
+
+        index.remove(&items[3]);
+        assert_ne!(index.merkle_root(), initial_root);
+        let proof = index.get_proof(items[3].clone()).check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        assert_eq!(proof.missing_keys().count(), 1);
+        let proof = index.get_proof(items[4].clone()).check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        assert_eq!(proof.entries().count(), 1);
+
+        // Inserting the removed item back restores the previous state.
+        index.insert(items[3].clone());
+        assert_eq!(index.merkle_root(), initial_root);
+    }
+
+    #[test]
+    fn tampered_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items[..5] {
+            index.insert(item.clone());
+        }
+        let root = index.merkle_root();
+
+        let proof = index.get_proof(items[0].clone());
+        assert!(is_valid(&proof, root));
+        let json = serde_json::to_value(&proof).unwrap();
+
+        let mut tampered = json.clone();
+        tampered["entries"][0]["key"] = Value::from(items[7].clone());
+        let tampered: MapProof<Hashed<String>, ()> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+
+        let mut tampered = json.clone();
+        tampered["proof"][0]["hash"] = serde_json::to_value(Hash::zero()).unwrap();
+        let tampered: MapProof<Hashed<String>, ()> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+
+        // Proof of absence for an item of the set.
+        let mut tampered = json;
+        tampered["entries"][0] = json!({ "missing": items[0] });
+        let tampered: MapProof<Hashed<String>, ()> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+    }
+
+    #[test]
+    #[should_panic(expected = "initially created with type KeySet")]
+    fn key_set_as_proof_key_set() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = KeySetIndex::new(INDEX_NAME, &mut fork);
+            index.insert(1_u8);
+        }
+        let index: ProofKeySetIndex<_, u8> = ProofKeySetIndex::new(INDEX_NAME, &mut fork);
+        index.contains(&1);
+    }
+}
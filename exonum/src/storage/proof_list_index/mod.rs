@@ -14,7 +14,7 @@
 
 //! An implementation of a Merkelized version of an array list (Merkle tree).
 
-pub use self::proof::{ListProof, ListProofError};
+pub use self::proof::{ListConsistencyProof, ListProof, ListProofError};
 
 use std::{cell::Cell, marker::PhantomData};
 
@@ -399,6 +399,41 @@ where
         self.construct_proof(self.root_key(), from, to)
     }
 
+    /// Returns the proof that the first `old_len` elements of the list did not change
+    /// since the list had `old_len` elements, i.e., that the list was only appended to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `old_len` is greater than the length of the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3].iter().cloned());
+    /// let old_root = index.merkle_root();
+    ///
+    /// index.extend([4, 5].iter().cloned());
+    /// let proof = index.get_consistency_proof(3);
+    /// assert!(proof.validate(old_root, 3, index.merkle_root(), 5).is_ok());
+    /// ```
+    pub fn get_consistency_proof(&self, old_len: u64) -> ListConsistencyProof {
+        if old_len > self.len() {
+            panic!(
+                "Illegal old length: the len is {}, but the old length is {}",
+                self.len(),
+                old_len
+            );
+        }
+        ListConsistencyProof::new(old_len, self.len(), |key| self.get_branch_unchecked(key))
+    }
+
     /// Returns an iterator over the list. The iterator element type is V.
     ///
     /// # Examples
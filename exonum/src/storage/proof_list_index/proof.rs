@@ -40,6 +40,10 @@ pub enum ListProofError {
     UnexpectedBranch,
     /// The hash of the proof is not equal to the trusted root hash.
     UnmatchedRootHash,
+    /// The old length of the list in the consistency proof is greater than the new one.
+    InvalidLength,
+    /// The consistency proof contains too few or too many hashes.
+    UnexpectedHashCount,
 }
 
 impl<V: StorageValue> ListProof<V> {
@@ -230,3 +234,161 @@ where
         Ok(res)
     }
 }
+
+/// A proof that a `ProofListIndex` is an append-only extension of its earlier version.
+///
+/// The proof shows that the first `old_len` elements of a list with `new_len` elements
+/// are exactly the elements of the list with `old_len` elements, so that no elements were
+/// changed or removed between the two versions of the list. This is similar to consistency
+/// proofs in Certificate Transparency.
+///
+/// The proof consists of the hashes of the largest complete subtrees covering the old list
+/// (`prefix`) and of the subtrees covering the elements appended afterwards (`suffix`), both
+/// in the left-to-right order. These hashes are sufficient to compute the Merkle roots
+/// of both versions of the list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListConsistencyProof {
+    prefix: Vec<Hash>,
+    suffix: Vec<Hash>,
+}
+
+impl ListConsistencyProof {
+    /// Creates a consistency proof using the `lookup` function to obtain the hashes
+    /// of the tree nodes in the list with `new_len` elements.
+    pub(super) fn new<F>(old_len: u64, new_len: u64, lookup: F) -> Self
+    where
+        F: Fn(ProofListKey) -> Hash,
+    {
+        debug_assert!(old_len <= new_len);
+
+        let mut proof = Self {
+            prefix: Vec::new(),
+            suffix: Vec::new(),
+        };
+        if new_len > 0 {
+            proof.collect(root_key(new_len), old_len, new_len, &lookup);
+        }
+        proof
+    }
+
+    fn collect<F>(&mut self, key: ProofListKey, old_len: u64, new_len: u64, lookup: &F)
+    where
+        F: Fn(ProofListKey) -> Hash,
+    {
+        let (start, end) = leaf_range(key);
+        if end <= old_len {
+            self.prefix.push(lookup(key));
+        } else if start >= old_len {
+            self.suffix.push(lookup(key));
+        } else {
+            self.collect(key.left(), old_len, new_len, lookup);
+            let right = key.right();
+            if right.first_left_leaf_index() < new_len {
+                self.collect(right, old_len, new_len, lookup);
+            }
+        }
+    }
+
+    /// Returns the hashes of the subtrees covering the old version of the list.
+    pub fn prefix(&self) -> &[Hash] {
+        &self.prefix
+    }
+
+    /// Returns the hashes of the subtrees covering the elements appended to the list.
+    pub fn suffix(&self) -> &[Hash] {
+        &self.suffix
+    }
+
+    /// Verifies the correctness of the proof by the trusted Merkle root hashes and lengths
+    /// of the old and the new versions of the list.
+    ///
+    /// If the proof is valid, the new list is the old list with zero or more elements
+    /// appended to it. Otherwise, `Err` is returned.
+    pub fn validate(
+        &self,
+        old_root: Hash,
+        old_len: u64,
+        new_root: Hash,
+        new_len: u64,
+    ) -> Result<(), ListProofError> {
+        if old_len > new_len {
+            return Err(ListProofError::InvalidLength);
+        }
+
+        // The old version of the list is covered by the `prefix` hashes only.
+        let actual_old_root = self.compute_root(old_len, old_len, &[])?;
+        let actual_new_root = self.compute_root(old_len, new_len, &self.suffix)?;
+        if actual_old_root != old_root || actual_new_root != new_root {
+            return Err(ListProofError::UnmatchedRootHash);
+        }
+        Ok(())
+    }
+
+    /// Computes the Merkle root of the list with `len` elements, checking that all
+    /// the `prefix` and `suffix` hashes are used.
+    fn compute_root(
+        &self,
+        old_len: u64,
+        len: u64,
+        suffix: &[Hash],
+    ) -> Result<Hash, ListProofError> {
+        if len == 0 {
+            if !self.prefix.is_empty() || !suffix.is_empty() {
+                return Err(ListProofError::UnexpectedHashCount);
+            }
+            return Ok(Hash::zero());
+        }
+        let (mut prefix, mut suffix) = (self.prefix.iter(), suffix.iter());
+        let root = subtree_hash(root_key(len), old_len, len, &mut prefix, &mut suffix)?;
+        if prefix.next().is_some() || suffix.next().is_some() {
+            return Err(ListProofError::UnexpectedHashCount);
+        }
+        Ok(root)
+    }
+}
+
+fn root_key(len: u64) -> ProofListKey {
+    let height = len.next_power_of_two().trailing_zeros() as u8 + 1;
+    ProofListKey::new(height, 0)
+}
+
+/// Returns the range of leaf indices covered by the tree node with the specified key.
+fn leaf_range(key: ProofListKey) -> (u64, u64) {
+    let start = key.first_left_leaf_index();
+    (start, start + (1 << (key.height() - 1)))
+}
+
+/// Computes the hash of the tree node in the list with `len` elements, taking the hashes
+/// of the subtrees lying before `old_len` from `prefix` and the rest of them from `suffix`.
+fn subtree_hash<'a, I>(
+    key: ProofListKey,
+    old_len: u64,
+    len: u64,
+    prefix: &mut I,
+    suffix: &mut I,
+) -> Result<Hash, ListProofError>
+where
+    I: Iterator<Item = &'a Hash>,
+{
+    let (start, end) = leaf_range(key);
+    if end <= old_len {
+        prefix
+            .next()
+            .cloned()
+            .ok_or(ListProofError::UnexpectedHashCount)
+    } else if start >= old_len {
+        suffix
+            .next()
+            .cloned()
+            .ok_or(ListProofError::UnexpectedHashCount)
+    } else {
+        let left = subtree_hash(key.left(), old_len, len, prefix, suffix)?;
+        let right = key.right();
+        if right.first_left_leaf_index() < len {
+            let right = subtree_hash(right, old_len, len, prefix, suffix)?;
+            Ok(hash_pair(&left, &right))
+        } else {
+            Ok(hash_one(&left))
+        }
+    }
+}
@@ -17,7 +17,9 @@ use serde::Serialize;
 use serde_json::{from_str, to_string};
 
 use self::ListProof::*;
-use super::{hash_one, hash_pair, root_hash, ListProof, ProofListIndex};
+use super::{
+    hash_one, hash_pair, root_hash, ListConsistencyProof, ListProof, ListProofError, ProofListIndex,
+};
 use crate::crypto::{hash, CryptoHash, Hash};
 use crate::storage::Database;
 
@@ -543,6 +545,74 @@ fn same_merkle_root(db1: Box<dyn Database>, db2: Box<dyn Database>) {
     assert_eq!(i1.merkle_root(), i2.merkle_root());
 }
 
+fn consistency_proofs(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    let values = random_values(40);
+
+    let mut roots = vec![index.merkle_root()];
+    for value in &values {
+        index.push(value.clone());
+        roots.push(index.merkle_root());
+
+        let new_len = index.len();
+        for old_len in 0..=new_len {
+            let proof = index.get_consistency_proof(old_len);
+            proof
+                .validate(
+                    roots[old_len as usize],
+                    old_len,
+                    index.merkle_root(),
+                    new_len,
+                )
+                .unwrap();
+
+            let json = to_string(&proof).unwrap();
+            let restored: ListConsistencyProof = from_str(&json).unwrap();
+            assert_eq!(restored, proof);
+        }
+    }
+}
+
+fn invalid_consistency_proofs(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(random_values(6));
+    let old_root = index.merkle_root();
+    index.extend(random_values(5));
+    let new_root = index.merkle_root();
+
+    let proof = index.get_consistency_proof(6);
+    proof.validate(old_root, 6, new_root, 11).unwrap();
+    match proof.validate(new_root, 11, old_root, 6) {
+        Err(ListProofError::InvalidLength) => {}
+        res => panic!("Unexpected validation result: {:?}", res),
+    }
+    match proof.validate(old_root, 5, new_root, 11) {
+        Err(ListProofError::UnexpectedHashCount) => {}
+        res => panic!("Unexpected validation result: {:?}", res),
+    }
+    match proof.validate(new_root, 6, new_root, 11) {
+        Err(ListProofError::UnmatchedRootHash) => {}
+        res => panic!("Unexpected validation result: {:?}", res),
+    }
+
+    // Changing an element of the old list breaks the consistency.
+    index.set(2, vec![1, 2, 3]);
+    let proof = index.get_consistency_proof(6);
+    match proof.validate(old_root, 6, index.merkle_root(), 11) {
+        Err(ListProofError::UnmatchedRootHash) => {}
+        res => panic!("Unexpected validation result: {:?}", res),
+    }
+}
+
+fn consistency_proof_illegal_length(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(random_values(3));
+    index.get_consistency_proof(4);
+}
+
 #[derive(Serialize)]
 struct ProofInfo<'a, V: Serialize> {
     merkle_root: Hash,
@@ -664,6 +734,31 @@ mod memorydb_tests {
         let db2 = create_database(path2);
         super::same_merkle_root(db1, db2);
     }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    fn test_invalid_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::invalid_consistency_proofs(db);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
 }
 
 mod rocksdb_tests {
@@ -779,6 +874,31 @@ mod rocksdb_tests {
         let db2 = create_database(path2);
         super::same_merkle_root(db1, db2);
     }
+
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    fn test_invalid_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::invalid_consistency_proofs(db);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
 }
 
 mod root_hash_tests {
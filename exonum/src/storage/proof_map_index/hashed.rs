@@ -0,0 +1,550 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A variant of `ProofMapIndex` with keys of arbitrary length.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::{borrow::Borrow, fmt, marker::PhantomData, ops::Deref};
+
+use super::{
+    key::{BitsRange, LEAF_KEY_PREFIX},
+    proof::{create_multiproof, create_proof},
+    MapProof, ProofMapIndex, ProofMapIndexValues, ProofMapKey, ProofPath,
+};
+use crate::crypto::{hash, Hash};
+use crate::storage::{
+    base_index::{BaseIndex, BaseIndexIter},
+    indexes_metadata::IndexType,
+    Fork, Snapshot, StorageKey, StorageValue,
+};
+
+/// Prefix of the storage keys under which the original keys of the map entries are stored.
+const KEY_ENTRY_PREFIX: u8 = 2;
+
+/// A wrapper for a storage key that uses the hash of the key as its `ProofMapKey`.
+///
+/// The hash is calculated over the binary representation of the key produced by its
+/// [`StorageKey`] implementation. `Hashed` keys are used in the proofs created
+/// by [`HashedProofMapIndex`]; the wrapper is serialized in the same way as the wrapped key.
+///
+/// [`StorageKey`]: ../trait.StorageKey.html
+/// [`HashedProofMapIndex`]: struct.HashedProofMapIndex.html
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hashed<K>(K);
+
+impl<K> Hashed<K> {
+    /// Returns the wrapped key.
+    pub fn into_inner(self) -> K {
+        self.0
+    }
+}
+
+impl<K> From<K> for Hashed<K> {
+    fn from(key: K) -> Self {
+        Hashed(key)
+    }
+}
+
+impl<K> Deref for Hashed<K> {
+    type Target = K;
+
+    fn deref(&self) -> &K {
+        &self.0
+    }
+}
+
+impl<K: StorageKey> ProofMapKey for Hashed<K> {
+    type Output = Hash;
+
+    fn write_key(&self, buffer: &mut [u8]) {
+        hash_key(&self.0).write(buffer);
+    }
+
+    fn read_key(buffer: &[u8]) -> Hash {
+        <Hash as StorageKey>::read(buffer)
+    }
+}
+
+impl<K: Serialize> Serialize for Hashed<K> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, K: Deserialize<'de>> Deserialize<'de> for Hashed<K> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        K::deserialize(deserializer).map(Hashed)
+    }
+}
+
+fn hash_key<K: StorageKey + ?Sized>(key: &K) -> Hash {
+    hash(&key_to_bytes(key))
+}
+
+fn key_to_bytes<K: StorageKey + ?Sized>(key: &K) -> Vec<u8> {
+    let mut buffer = vec![0; key.size()];
+    key.write(&mut buffer);
+    buffer
+}
+
+/// Returns the storage key under which the original key of the leaf is stored.
+fn key_entry(leaf: &ProofPath) -> Vec<u8> {
+    let mut entry = leaf.as_bytes().to_vec();
+    entry[0] = KEY_ENTRY_PREFIX;
+    entry
+}
+
+/// A variant of [`ProofMapIndex`] that supports keys of arbitrary length.
+///
+/// The keys of `HashedProofMapIndex` may be of any type implementing the [`StorageKey`] trait.
+/// The Merkle Patricia tree of the index uses the hashes of the keys as [`ProofPath`]s,
+/// while the original keys are stored alongside the values. Thus, unlike [`HashedKey`]s
+/// in `ProofMapIndex`, the iterators of this index return the original keys, and
+/// the proofs contain the original keys wrapped in [`Hashed`].
+///
+/// The Merkle root of the index is equal to the root of a `ProofMapIndex` with the hashes
+/// of the keys as its keys.
+///
+/// [`ProofMapIndex`]: struct.ProofMapIndex.html
+/// [`StorageKey`]: ../trait.StorageKey.html
+/// [`ProofPath`]: struct.ProofPath.html
+/// [`HashedKey`]: trait.HashedKey.html
+/// [`Hashed`]: struct.Hashed.html
+pub struct HashedProofMapIndex<T, K, V> {
+    inner: ProofMapIndex<T, Hash, V>,
+    _k: PhantomData<K>,
+}
+
+/// An iterator over the entries of a `HashedProofMapIndex`.
+///
+/// This struct is created by the [`iter`] method on [`HashedProofMapIndex`].
+/// See its documentation for details.
+///
+/// [`iter`]: struct.HashedProofMapIndex.html#method.iter
+/// [`HashedProofMapIndex`]: struct.HashedProofMapIndex.html
+#[derive(Debug)]
+pub struct HashedProofMapIndexIter<'a, K, V> {
+    leaves: BaseIndexIter<'a, ProofPath, V>,
+    keys: HashedProofMapIndexKeys<'a, K>,
+}
+
+/// An iterator over the keys of a `HashedProofMapIndex`.
+///
+/// This struct is created by the [`keys`] method on [`HashedProofMapIndex`].
+/// See its documentation for details.
+///
+/// [`keys`]: struct.HashedProofMapIndex.html#method.keys
+/// [`HashedProofMapIndex`]: struct.HashedProofMapIndex.html
+#[derive(Debug)]
+pub struct HashedProofMapIndexKeys<'a, K> {
+    base_iter: BaseIndexIter<'a, Vec<u8>, Vec<u8>>,
+    _k: PhantomData<K>,
+}
+
+impl<T, K, V> HashedProofMapIndex<T, K, V>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> = HashedProofMapIndex::new(name, &snapshot);
+    ///
+    /// let mut fork = db.fork();
+    /// let mut mut_index: HashedProofMapIndex<_, String, u8> =
+    ///     HashedProofMapIndex::new(name, &mut fork);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        Self::from_base(BaseIndex::new(index_name, IndexType::HashedProofMap, view))
+    }
+
+    /// Creates a new index representation based on the name, common prefix of its keys
+    /// and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let index_id = vec![123];
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> =
+    ///     HashedProofMapIndex::new_in_family(name, &index_id, &snapshot);
+    /// ```
+    pub fn new_in_family<S, I>(family_name: S, index_id: &I, view: T) -> Self
+    where
+        I: StorageKey,
+        I: ?Sized,
+        S: AsRef<str>,
+    {
+        Self::from_base(BaseIndex::new_in_family(
+            family_name,
+            index_id,
+            IndexType::HashedProofMap,
+            view,
+        ))
+    }
+
+    /// Creates a new index representation over the given base index.
+    pub(crate) fn from_base(base: BaseIndex<T>) -> Self {
+        Self {
+            inner: ProofMapIndex::from_base(base),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns the root hash of the proof map or default hash value if it is empty.
+    /// The default hash consists solely of zeroes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    /// assert_eq!(Hash::default(), index.merkle_root());
+    ///
+    /// index.put(&"key".to_owned(), 100);
+    /// assert_ne!(Hash::default(), index.merkle_root());
+    /// ```
+    pub fn merkle_root(&self) -> Hash {
+        self.inner.merkle_root()
+    }
+
+    /// Returns a value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    ///
+    /// let key = "key".to_owned();
+    /// assert_eq!(None, index.get(&key));
+    ///
+    /// index.put(&key, 2);
+    /// assert_eq!(Some(2), index.get(&key));
+    /// ```
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.inner.get(&hash_key(key))
+    }
+
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    ///
+    /// let key = "key".to_owned();
+    /// assert!(!index.contains(&key));
+    ///
+    /// index.put(&key, 2);
+    /// assert!(index.contains(&key));
+    /// ```
+    pub fn contains(&self, key: &K) -> bool {
+        self.inner.contains(&hash_key(key))
+    }
+
+    /// Returns the proof of existence or non-existence for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    /// index.put(&"key".to_owned(), 2);
+    ///
+    /// let proof = index.get_proof("key".to_owned());
+    /// let checked_proof = proof.check().unwrap();
+    /// assert_eq!(checked_proof.merkle_root(), index.merkle_root());
+    /// let (key, value) = checked_proof.entries().next().unwrap();
+    /// assert_eq!((key.as_str(), *value), ("key", 2));
+    /// ```
+    pub fn get_proof(&self, key: K) -> MapProof<Hashed<K>, V> {
+        create_proof(Hashed(key), self.inner.get_root_node(), |path| {
+            self.inner.get_node_unchecked(path)
+        })
+    }
+
+    /// Returns the combined proof of existence or non-existence for the multiple specified keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> = HashedProofMapIndex::new("name", &snapshot);
+    ///
+    /// let proof = index.get_multiproof(vec!["foo".to_owned(), "bar".to_owned()]);
+    /// ```
+    pub fn get_multiproof<KI>(&self, keys: KI) -> MapProof<Hashed<K>, V>
+    where
+        KI: IntoIterator<Item = K>,
+    {
+        create_multiproof(
+            keys.into_iter().map(Hashed),
+            self.inner.get_root_node(),
+            |path| self.inner.get_node_unchecked(path),
+        )
+    }
+
+    /// Returns an iterator over the entries of the map in the order of the hashes of the keys.
+    /// The iterator element type is `(K::Owned, V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> = HashedProofMapIndex::new("name", &snapshot);
+    ///
+    /// for (key, value) in index.iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn iter(&self) -> HashedProofMapIndexIter<K, V> {
+        HashedProofMapIndexIter {
+            leaves: self.inner.base.iter(&LEAF_KEY_PREFIX),
+            keys: self.keys(),
+        }
+    }
+
+    /// Returns an iterator over the keys of the map in the order of their hashes.
+    /// The iterator element type is `K::Owned`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> = HashedProofMapIndex::new("name", &snapshot);
+    ///
+    /// for key in index.keys() {
+    ///     println!("{}", key);
+    /// }
+    /// ```
+    pub fn keys(&self) -> HashedProofMapIndexKeys<K> {
+        HashedProofMapIndexKeys {
+            base_iter: self.inner.base.iter(&KEY_ENTRY_PREFIX),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the values of the map in the order of the hashes of the keys.
+    /// The iterator element type is `V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: HashedProofMapIndex<_, String, u8> = HashedProofMapIndex::new("name", &snapshot);
+    ///
+    /// for value in index.values() {
+    ///     println!("{}", value);
+    /// }
+    /// ```
+    pub fn values(&self) -> ProofMapIndexValues<V> {
+        self.inner.values()
+    }
+}
+
+impl<'a, K, V> HashedProofMapIndex<&'a mut Fork, K, V>
+where
+    K: StorageKey,
+    V: StorageValue,
+{
+    /// Inserts the key-value pair into the proof map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    ///
+    /// index.put(&"key".to_owned(), 2);
+    /// assert_eq!(index.keys().collect::<Vec<_>>(), vec!["key".to_owned()]);
+    /// ```
+    pub fn put(&mut self, key: &K, value: V) {
+        let key_hash = hash_key(key);
+        self.inner.put(&key_hash, value);
+        self.inner
+            .base
+            .put(&key_entry(&ProofPath::new(&key_hash)), key_to_bytes(key));
+    }
+
+    /// Removes a key from the proof map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    ///
+    /// let key = "key".to_owned();
+    /// index.put(&key, 2);
+    /// assert!(index.contains(&key));
+    ///
+    /// index.remove(&key);
+    /// assert!(!index.contains(&key));
+    /// ```
+    pub fn remove(&mut self, key: &K) {
+        let key_hash = hash_key(key);
+        self.inner.remove(&key_hash);
+        self.inner
+            .base
+            .remove(&key_entry(&ProofPath::new(&key_hash)));
+    }
+
+    /// Clears the proof map, removing all entries.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, HashedProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = HashedProofMapIndex::new("name", &mut fork);
+    ///
+    /// let key = "key".to_owned();
+    /// index.put(&key, 2);
+    /// assert!(index.contains(&key));
+    ///
+    /// index.clear();
+    /// assert!(!index.contains(&key));
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+}
+
+impl<'a, T, K, V> ::std::iter::IntoIterator for &'a HashedProofMapIndex<T, K, V>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+    V: StorageValue,
+{
+    type Item = (K::Owned, V);
+    type IntoIter = HashedProofMapIndexIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> Iterator for HashedProofMapIndexIter<'a, K, V>
+where
+    K: StorageKey,
+    V: StorageValue,
+{
+    type Item = (K::Owned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.leaves.next()?;
+        let key = self.keys.next()?;
+        debug_assert_eq!(path.raw_key(), hash_key::<K>(key.borrow()).as_ref());
+        Some((key, value))
+    }
+}
+
+impl<'a, K> Iterator for HashedProofMapIndexKeys<'a, K>
+where
+    K: StorageKey,
+{
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.base_iter.next().map(|(_, key)| K::read(&key))
+    }
+}
+
+impl<T, K, V> fmt::Debug for HashedProofMapIndex<T, K, V>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey,
+    V: StorageValue + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashedProofMapIndex")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
@@ -15,8 +15,9 @@
 //! An implementation of a Merkelized version of a map (Merkle Patricia tree).
 
 pub use self::{
+    hashed::{Hashed, HashedProofMapIndex, HashedProofMapIndexIter, HashedProofMapIndexKeys},
     key::{HashedKey, ProofMapKey, ProofPath, KEY_SIZE as PROOF_MAP_KEY_SIZE},
-    proof::{CheckedMapProof, MapProof, MapProofError},
+    proof::{CheckedMapProof, CheckedMapRangeProof, MapProof, MapProofError, MapRangeProof},
 };
 
 use std::{fmt, marker::PhantomData};
@@ -24,7 +25,7 @@ use std::{fmt, marker::PhantomData};
 use self::{
     key::{BitsRange, ChildKind, LEAF_KEY_PREFIX},
     node::{BranchNode, Node},
-    proof::{create_multiproof, create_proof},
+    proof::{create_multiproof, create_proof, create_range_proof},
 };
 use super::{
     base_index::{BaseIndex, BaseIndexIter},
@@ -33,6 +34,7 @@ use super::{
 };
 use crate::crypto::{CryptoHash, Hash, HashStream};
 
+mod hashed;
 mod key;
 mod node;
 mod proof;
@@ -48,11 +50,13 @@ mod tests;
 ///
 /// **The size of the proof map keys must be exactly 32 bytes and the keys must have a uniform
 /// distribution.** Usually, [`Hash`] and [`PublicKey`] are used as types of proof map keys.
+/// Keys of arbitrary length are supported by [`HashedProofMapIndex`].
 ///
 /// [`ProofMapKey`]: trait.ProofMapKey.html
 /// [`StorageValue`]: ../trait.StorageValue.html
 /// [`Hash`]: ../../../exonum_crypto/struct.Hash.html
 /// [`PublicKey`]: ../../../exonum_crypto/struct.PublicKey.html
+/// [`HashedProofMapIndex`]: struct.HashedProofMapIndex.html
 pub struct ProofMapIndex<T, K, V> {
     base: BaseIndex<T>,
     _k: PhantomData<K>,
@@ -191,6 +195,16 @@ where
         }
     }
 
+    /// Creates a new index representation over the given base index. This allows
+    /// reusing the Merkle Patricia tree in indexes of other types.
+    pub(crate) fn from_base(base: BaseIndex<T>) -> Self {
+        Self {
+            base,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
     fn get_root_path(&self) -> Option<ProofPath> {
         self.base
             .iter::<_, ProofPath, _>(&())
@@ -334,6 +348,34 @@ where
         })
     }
 
+    /// Returns the proof of all the entries of the map with keys in the range `[from, to]`.
+    /// The range is defined by the [`ProofPath`]s of the bounds, i.e., in the order of
+    /// the leaves of the Merkle Patricia tree; the proof asserts that the map contains
+    /// no other entries within the range.
+    ///
+    /// [`ProofPath`]: struct.ProofPath.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, [u8; 32], u8> = ProofMapIndex::new("index", &snapshot);
+    ///
+    /// let proof = index.get_range_proof(&[0; 32], &[255; 32]);
+    /// assert_eq!(proof.check().unwrap().entries().count(), 0);
+    /// ```
+    pub fn get_range_proof(&self, from: &K, to: &K) -> MapRangeProof<K::Output, V> {
+        create_range_proof::<K, V, _>(
+            ProofPath::new(from),
+            ProofPath::new(to),
+            self.get_root_node(),
+            |path| self.get_node_unchecked(path),
+        )
+    }
+
     /// Returns an iterator over the entries of the map in ascending order. The iterator element
     /// type is `(K::Output, V)`.
     ///
@@ -16,14 +16,21 @@
 // ECR-1771 for the details.
 #![allow(bare_trait_objects)]
 
+use protobuf::RepeatedField;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use std::{borrow::Cow, panic};
+
 use super::{
-    key::{BitsRange, ChildKind, ProofMapKey, ProofPath, KEY_SIZE},
+    key::{
+        BitsRange, ChildKind, ProofMapKey, ProofPath, BRANCH_KEY_PREFIX, KEY_SIZE, LEAF_KEY_PREFIX,
+        PROOF_PATH_KIND_POS, PROOF_PATH_LEN_POS, PROOF_PATH_SIZE,
+    },
     node::{BranchNode, Node},
 };
 use crate::crypto::{CryptoHash, Hash, HashStream};
-use crate::storage::StorageValue;
+use crate::proto::{self, ProtobufConvert};
+use crate::storage::{StorageKey, StorageValue};
 
 // Expected size of the proof, in number of hashed entries.
 const DEFAULT_PROOF_CAPACITY: usize = 8;
@@ -122,6 +129,18 @@ pub enum MapProofError {
     /// Entries in the proof are not ordered by increasing path.
     #[fail(display = "invalid path ordering")]
     InvalidOrdering(ProofPath, ProofPath),
+
+    /// Bounds of the range proof are not leaf paths or are not ordered.
+    #[fail(display = "invalid range bounds in proof")]
+    InvalidRange(ProofPath, ProofPath),
+
+    /// An entry of the range proof lies outside of the proven range.
+    #[fail(display = "entry outside of the proven range")]
+    EntryOutOfRange(ProofPath),
+
+    /// A proof entry of the range proof hides entries from the proven range.
+    #[fail(display = "proof entry intersects with the proven range")]
+    ProofEntryInRange(ProofPath),
 }
 
 // Used instead of `(ProofPath, Hash)` only for the purpose of clearer (de)serialization.
@@ -275,6 +294,57 @@ pub struct CheckedMapProof<K, V> {
     hash: Hash,
 }
 
+/// Checks that entries in proof are in increasing order and do not embed each other.
+fn check_proof_ordering(proof: &[MapProofEntry]) -> Result<(), MapProofError> {
+    use self::MapProofError::*;
+    use std::cmp::Ordering;
+
+    for w in proof.windows(2) {
+        let (prev_path, path) = (&w[0].path, &w[1].path);
+        match prev_path.partial_cmp(path) {
+            Some(Ordering::Less) => {
+                if path.starts_with(prev_path) {
+                    return Err(EmbeddedPaths {
+                        prefix: *prev_path,
+                        path: *path,
+                    });
+                }
+            }
+            Some(Ordering::Equal) => {
+                return Err(DuplicatePath(*path));
+            }
+            Some(Ordering::Greater) => {
+                return Err(InvalidOrdering(*prev_path, *path));
+            }
+            None => unreachable!("Incomparable keys in proof"),
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the proof entries by path and computes the root hash of the tree backing them.
+fn collect_sorted(mut proof: Vec<MapProofEntry>) -> Result<Hash, MapProofError> {
+    // Rust docs state that in the case `proof` is almost sorted (which is the case for
+    // proofs returned by `ProofMapIndex`), the sort is performed very quickly.
+    proof.sort_unstable_by(|x, y| {
+        x.path.partial_cmp(&y.path).expect(
+            "Incorrectly formed paths supplied to MapProof; \
+             paths should have `start` field set to 0",
+        )
+    });
+
+    // This check is required as duplicate paths can be introduced by entries
+    // (further, it's generally possible that two different entry keys lead to the same
+    // `ProofPath`).
+    for window in proof.windows(2) {
+        if window[0].path == window[1].path {
+            return Err(MapProofError::DuplicatePath(window[0].path));
+        }
+    }
+
+    collect(&proof)
+}
+
 /// Computes the root hash of the Merkle Patricia tree backing the specified entries
 /// in the map view.
 ///
@@ -481,29 +551,8 @@ where
 {
     fn precheck(&self) -> Result<(), MapProofError> {
         use self::MapProofError::*;
-        use std::cmp::Ordering;
 
-        // Check that entries in proof are in increasing order
-        for w in self.proof.windows(2) {
-            let (prev_path, path) = (&w[0].path, &w[1].path);
-            match prev_path.partial_cmp(path) {
-                Some(Ordering::Less) => {
-                    if path.starts_with(prev_path) {
-                        return Err(EmbeddedPaths {
-                            prefix: *prev_path,
-                            path: *path,
-                        });
-                    }
-                }
-                Some(Ordering::Equal) => {
-                    return Err(DuplicatePath(*path));
-                }
-                Some(Ordering::Greater) => {
-                    return Err(InvalidOrdering(*prev_path, *path));
-                }
-                None => unreachable!("Incomparable keys in proof"),
-            }
-        }
+        check_proof_ordering(&self.proof)?;
 
         // Check that no entry has a prefix among the paths in the proof entries.
         // In order to do this, it suffices to locate the closest smaller path in the proof entries
@@ -569,26 +618,8 @@ where
                 hash: v.hash(),
             })
         }));
-        // Rust docs state that in the case `self.proof` and `self.entries` are sorted
-        // (which is the case for `MapProof`s returned by `ProofMapIndex.get_proof()`),
-        // the sort is performed very quickly.
-        proof.sort_unstable_by(|x, y| {
-            x.path.partial_cmp(&y.path).expect(
-                "Incorrectly formed paths supplied to MapProof; \
-                 paths should have `start` field set to 0",
-            )
-        });
-
-        // This check is required as duplicate paths can be introduced by entries
-        // (further, it's generally possible that two different entry keys lead to the same
-        // `ProofPath`).
-        for window in proof.windows(2) {
-            if window[0].path == window[1].path {
-                return Err(MapProofError::DuplicatePath(window[0].path));
-            }
-        }
 
-        collect(&proof).map(|h| CheckedMapProof {
+        collect_sorted(proof).map(|h| CheckedMapProof {
             entries: entries.into_iter().map(OptionalEntry::into).collect(),
             hash: h,
         })
@@ -910,3 +941,389 @@ where
             .create(),
     }
 }
+
+// Used instead of `(K, V)` only for the purpose of clearer (de)serialization.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RangeEntry<K, V> {
+    key: K,
+    value: V,
+}
+
+/// View of a `ProofMapIndex` consisting of all its entries with paths in a certain range,
+/// coupled with a proof, which jointly allow restoring the `merkle_root()` of the index.
+/// Unlike [`MapProof`], the range proof asserts that the map contains no other entries
+/// within the range.
+///
+/// The range is defined in terms of [`ProofPath`]s of the keys, i.e., in the order of leaves
+/// in the Merkle Patricia tree. Note that for the keys implementing [`HashedKey`], the paths
+/// are derived from the key hashes, and for `Hash` and `PublicKey` keys the bits of each byte
+/// are compared starting from the least significant one.
+///
+/// # Workflow
+///
+/// You can create `MapRangeProof`s with the [`get_range_proof()`] method of `ProofMapIndex`.
+/// Proofs can be verified with the help of [`check()`].
+///
+/// ```
+/// # use exonum::storage::{Database, MemoryDB, ProofMapIndex};
+/// # use exonum::storage::proof_map_index::ProofPath;
+/// # use exonum::crypto::hash;
+/// let mut fork = { let db = MemoryDB::new(); db.fork() };
+/// let mut map = ProofMapIndex::new("index", &mut fork);
+/// let (h1, h2, h3) = (hash(&[1]), hash(&[2]), hash(&[3]));
+/// map.put(&h1, 100u32);
+/// map.put(&h2, 200u32);
+/// map.put(&h3, 300u32);
+///
+/// let mut keys = vec![h1, h2, h3];
+/// keys.sort_by(|x, y| {
+///     ProofPath::new(x).partial_cmp(&ProofPath::new(y)).unwrap()
+/// });
+/// let proof = map.get_range_proof(&keys[0], &keys[1]);
+/// let checked_proof = proof.check().unwrap();
+/// assert_eq!(checked_proof.merkle_root(), map.merkle_root());
+/// assert_eq!(
+///     checked_proof.entries().map(|(key, _)| *key).collect::<Vec<_>>(),
+///     keys[0..2].to_vec()
+/// );
+/// ```
+///
+/// # JSON serialization
+///
+/// `MapRangeProof` is serialized to JSON as an object with the following fields:
+///
+/// - `from` and `to` are the [`ProofPath`]s of the inclusive bounds of the range.
+/// - `entries` is an array of `{ "key": K, "value": V }` objects for all key-value pairs
+///   of the map within the range, sorted by increasing path.
+/// - `proof` is an array of `{ "path": ProofPath, "hash": Hash }` objects, each of which
+///   lies outside of the range.
+///
+/// # Protobuf serialization
+///
+/// `MapRangeProof` implements [`ProtobufConvert`] with the `MapRangeProof` message from
+/// `storage.proto`. Paths are represented by their storage keys, keys are written with
+/// [`ProofMapKey::write_key()`] and values are represented by their binary form. Conversion
+/// from the Protobuf message fails if a value cannot be decoded from its binary form.
+///
+/// [`MapProof`]: struct.MapProof.html
+/// [`ProofPath`]: struct.ProofPath.html
+/// [`HashedKey`]: trait.HashedKey.html
+/// [`get_range_proof()`]: struct.ProofMapIndex.html#method.get_range_proof
+/// [`check()`]: #method.check
+/// [`ProtobufConvert`]: ../../proto/trait.ProtobufConvert.html
+/// [`ProofMapKey::write_key()`]: trait.ProofMapKey.html#tymethod.write_key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapRangeProof<K, V> {
+    from: ProofPath,
+    to: ProofPath,
+    entries: Vec<RangeEntry<K, V>>,
+    proof: Vec<MapProofEntry>,
+}
+
+/// Version of `MapRangeProof` obtained after verification.
+///
+/// See [`MapRangeProof`] for an example of usage.
+///
+/// [`MapRangeProof`]: struct.MapRangeProof.html#workflow
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckedMapRangeProof<K, V> {
+    from: ProofPath,
+    to: ProofPath,
+    entries: Vec<(K, V)>,
+    hash: Hash,
+}
+
+/// Checks whether all the leaves in the subtree with the given path lie before `bound`.
+fn is_subtree_before(path: &ProofPath, bound: &ProofPath) -> bool {
+    !bound.starts_with(path) && path < bound
+}
+
+/// Checks whether all the leaves in the subtree with the given path lie after `bound`.
+fn is_subtree_after(path: &ProofPath, bound: &ProofPath) -> bool {
+    !bound.starts_with(path) && path > bound
+}
+
+impl<K, V> MapRangeProof<K, V> {
+    /// Returns the paths of the lower and upper inclusive bounds of the range.
+    pub fn bounds(&self) -> (ProofPath, ProofPath) {
+        (self.from, self.to)
+    }
+
+    /// Provides access to the proof part of the view. Useful mainly for debug purposes.
+    pub fn proof_unchecked(&self) -> Vec<(ProofPath, Hash)> {
+        self.proof.iter().map(|e| (e.path, e.hash)).collect()
+    }
+
+    /// Retrieves references to entries in the proof.
+    /// This method does not perform any integrity checks of the proof.
+    pub fn entries_unchecked(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|e| (&e.key, &e.value))
+    }
+}
+
+impl<K, V> MapRangeProof<K, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+{
+    /// Consumes this proof producing a `CheckedMapRangeProof` structure.
+    ///
+    /// Fails if the proof is malformed, if any entry lies outside of the range or if any
+    /// proof entry may contain entries from the range.
+    pub fn check(self) -> Result<CheckedMapRangeProof<K, V>, MapProofError> {
+        use self::MapProofError::*;
+
+        let (from, to) = (self.from, self.to);
+        if !from.is_leaf() || !to.is_leaf() || from > to {
+            return Err(InvalidRange(from, to));
+        }
+
+        check_proof_ordering(&self.proof)?;
+        for entry in &self.proof {
+            if !is_subtree_before(&entry.path, &from) && !is_subtree_after(&entry.path, &to) {
+                return Err(ProofEntryInRange(entry.path));
+            }
+        }
+
+        let mut proof = self.proof;
+        for entry in &self.entries {
+            let path = ProofPath::new(&entry.key);
+            if path < from || path > to {
+                return Err(EntryOutOfRange(path));
+            }
+            proof.push(MapProofEntry {
+                path,
+                hash: entry.value.hash(),
+            });
+        }
+
+        collect_sorted(proof).map(|hash| CheckedMapRangeProof {
+            from,
+            to,
+            entries: self
+                .entries
+                .into_iter()
+                .map(|entry| (entry.key, entry.value))
+                .collect(),
+            hash,
+        })
+    }
+}
+
+impl<K, V> CheckedMapRangeProof<K, V> {
+    /// Returns the paths of the lower and upper inclusive bounds of the range.
+    pub fn bounds(&self) -> (ProofPath, ProofPath) {
+        (self.from, self.to)
+    }
+
+    /// Retrieves references to all key-value pairs of the map within the range.
+    pub fn entries(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries
+            .iter()
+            .map(|&(ref key, ref value)| (key, value))
+    }
+
+    /// Returns a hash of the map that this proof is constructed for.
+    pub fn merkle_root(&self) -> Hash {
+        self.hash
+    }
+}
+
+/// Adds the subtree with the given path either as a proof entry, if it lies outside
+/// of the range, or as the entries it contains.
+fn add_range_subtree<K, V, F>(
+    proof: &mut MapRangeProof<K::Output, V>,
+    path: ProofPath,
+    hash: Hash,
+    lookup: &F,
+) where
+    K: ProofMapKey,
+    V: StorageValue,
+    F: Fn(&ProofPath) -> Node<V>,
+{
+    if is_subtree_before(&path, &proof.from) || is_subtree_after(&path, &proof.to) {
+        proof.proof.push(MapProofEntry { path, hash });
+        return;
+    }
+
+    match lookup(&path) {
+        Node::Leaf(value) => proof.entries.push(RangeEntry {
+            key: K::read_key(path.raw_key()),
+            value,
+        }),
+        Node::Branch(branch) => add_range_branch::<K, V, F>(proof, &branch, lookup),
+    }
+}
+
+/// Adds both children of the branch into the range proof.
+fn add_range_branch<K, V, F>(
+    proof: &mut MapRangeProof<K::Output, V>,
+    branch: &BranchNode,
+    lookup: &F,
+) where
+    K: ProofMapKey,
+    V: StorageValue,
+    F: Fn(&ProofPath) -> Node<V>,
+{
+    for &kind in &[ChildKind::Left, ChildKind::Right] {
+        add_range_subtree::<K, V, F>(
+            proof,
+            branch.child_path(kind),
+            *branch.child_hash(kind),
+            lookup,
+        );
+    }
+}
+
+/// Creates a proof for all the entries with paths in the range `[from, to]`.
+pub fn create_range_proof<K, V, F>(
+    from: ProofPath,
+    to: ProofPath,
+    root_node: Option<(ProofPath, Node<V>)>,
+    lookup: F,
+) -> MapRangeProof<K::Output, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+    F: Fn(&ProofPath) -> Node<V>,
+{
+    let mut proof = MapRangeProof {
+        from,
+        to,
+        entries: Vec::new(),
+        proof: Vec::new(),
+    };
+
+    match root_node {
+        // Children of the root branch are always added, since a single branch node
+        // is not a valid proof.
+        Some((_, Node::Branch(root_branch))) => {
+            add_range_branch::<K, V, F>(&mut proof, &root_branch, &lookup);
+        }
+
+        Some((root_path, Node::Leaf(root_value))) => {
+            if root_path < from || root_path > to {
+                proof.proof.push(MapProofEntry {
+                    path: root_path,
+                    hash: root_value.hash(),
+                });
+            } else {
+                proof.entries.push(RangeEntry {
+                    key: K::read_key(root_path.raw_key()),
+                    value: root_value,
+                });
+            }
+        }
+
+        None => {}
+    }
+
+    proof
+}
+
+fn path_to_pb(path: &ProofPath) -> Vec<u8> {
+    let mut buffer = vec![0; PROOF_PATH_SIZE];
+    path.write(&mut buffer);
+    buffer
+}
+
+fn path_from_pb(buffer: &[u8]) -> Result<ProofPath, failure::Error> {
+    ensure!(buffer.len() == PROOF_PATH_SIZE, "Wrong ProofPath size");
+    match buffer[PROOF_PATH_KIND_POS] {
+        LEAF_KEY_PREFIX => ensure!(
+            buffer[PROOF_PATH_LEN_POS] == 0,
+            "Leaf ProofPath with non-zero length"
+        ),
+        BRANCH_KEY_PREFIX => {}
+        kind => bail!("Unknown ProofPath kind {}", kind),
+    }
+    Ok(ProofPath::read(buffer))
+}
+
+fn value_from_pb<V: StorageValue + Clone>(buffer: Vec<u8>) -> Result<V, failure::Error> {
+    // `StorageValue::from_bytes` panics on malformed input, which should be reported
+    // as an error for proofs received from other nodes.
+    let value = panic::catch_unwind(|| V::from_bytes(Cow::Borrowed(&buffer)))
+        .map_err(|_| format_err!("Malformed map value"))?;
+    ensure!(
+        value.clone().into_bytes() == buffer,
+        "Map value is not in the canonical binary form"
+    );
+    Ok(value)
+}
+
+impl ProtobufConvert for MapProofEntry {
+    type ProtoStruct = proto::schema::storage::MapProofEntry;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        let mut pb = proto::schema::storage::MapProofEntry::new();
+        pb.set_path(path_to_pb(&self.path));
+        pb.set_hash(self.hash.to_pb());
+        pb
+    }
+
+    fn from_pb(mut pb: Self::ProtoStruct) -> Result<Self, failure::Error> {
+        Ok(Self {
+            path: path_from_pb(pb.get_path())?,
+            hash: Hash::from_pb(pb.take_hash())?,
+        })
+    }
+}
+
+impl<K, V> ProtobufConvert for MapRangeProof<K, V>
+where
+    K: ProofMapKey<Output = K>,
+    V: StorageValue + Clone,
+{
+    type ProtoStruct = proto::schema::storage::MapRangeProof;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut key = vec![0; KEY_SIZE];
+                entry.key.write_key(&mut key);
+                let mut pb = proto::schema::storage::MapRangeProofEntry::new();
+                pb.set_key(key);
+                pb.set_value(entry.value.clone().into_bytes());
+                pb
+            })
+            .collect();
+
+        let mut pb = proto::schema::storage::MapRangeProof::new();
+        pb.set_from(path_to_pb(&self.from));
+        pb.set_to(path_to_pb(&self.to));
+        pb.set_entries(RepeatedField::from_vec(entries));
+        pb.set_proof(RepeatedField::from_vec(
+            self.proof.iter().map(ProtobufConvert::to_pb).collect(),
+        ));
+        pb
+    }
+
+    fn from_pb(mut pb: Self::ProtoStruct) -> Result<Self, failure::Error> {
+        let entries = pb
+            .take_entries()
+            .into_iter()
+            .map(|mut entry| {
+                ensure!(entry.get_key().len() == KEY_SIZE, "Wrong key size");
+                Ok(RangeEntry {
+                    key: K::read_key(entry.get_key()),
+                    value: value_from_pb(entry.take_value())?,
+                })
+            })
+            .collect::<Result<_, failure::Error>>()?;
+        let proof = pb
+            .take_proof()
+            .into_iter()
+            .map(ProtobufConvert::from_pb)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            from: path_from_pb(pb.get_from())?,
+            to: path_from_pb(pb.get_to())?,
+            entries,
+            proof,
+        })
+    }
+}
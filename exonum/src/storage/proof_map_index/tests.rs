@@ -26,10 +26,11 @@ use super::{
     key::{BitsRange, ChildKind, KEY_SIZE, LEAF_KEY_PREFIX},
     node::BranchNode,
     proof::MapProofBuilder,
-    HashedKey, MapProof, MapProofError, ProofMapIndex, ProofMapKey, ProofPath,
+    Hashed, HashedKey, HashedProofMapIndex, MapProof, MapProofError, MapRangeProof, ProofMapIndex,
+    ProofMapKey, ProofPath,
 };
 use crate::crypto::{hash, CryptoHash, Hash, HashStream};
-use crate::proto;
+use crate::proto::{self, ProtobufConvert};
 use crate::storage::{Database, Fork, StorageValue};
 
 const IDX_NAME: &'static str = "idx_name";
@@ -563,6 +564,159 @@ fn test_invalid_map_proofs() {
     }
 }
 
+#[test]
+fn test_invalid_map_range_proofs() {
+    use self::MapProofError::*;
+
+    // The least significant bit of the first byte is compared first.
+    let (low, high) = (ProofPath::new(&[2; 32]), ProofPath::new(&[1; 32]));
+    let h = hash(&vec![1]);
+
+    let json = json!({ "from": high, "to": low, "entries": [], "proof": [] });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        InvalidRange(..) => {}
+        e => panic!("expected invalid range error, got {}", e),
+    }
+
+    let json = json!({ "from": low.prefix(10), "to": high, "entries": [], "proof": [] });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        InvalidRange(..) => {}
+        e => panic!("expected invalid range error, got {}", e),
+    }
+
+    let key = [3_u8; 32];
+    let json = json!({
+        "from": low,
+        "to": high,
+        "entries": [ { "key": key, "value": [1, 2, 3] } ],
+        "proof": [],
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        EntryOutOfRange(..) => {}
+        e => panic!("expected entry out of range error, got {}", e),
+    }
+
+    // Paths of `[2; 32]` and `[1; 32]` have no common prefix, so the subtree
+    // with the path `0` contains a part of the range.
+    let json = json!({
+        "from": low,
+        "to": high,
+        "entries": [],
+        "proof": [ { "path": "0", "hash": h }, { "path": "1", "hash": h } ],
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        ProofEntryInRange(..) => {}
+        e => panic!("expected proof entry in range error, got {}", e),
+    }
+}
+
+#[test]
+fn test_map_range_proof_with_malformed_values_from_pb() {
+    let key = [1_u8; 32];
+    let path = ProofPath::new(&key);
+    let raw_proof = |value: Vec<u8>| {
+        let json = json!({
+            "from": path,
+            "to": path,
+            "entries": [ { "key": key, "value": value } ],
+            "proof": [],
+        });
+        let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+        proof.to_pb()
+    };
+
+    assert!(MapRangeProof::<[u8; 32], u64>::from_pb(raw_proof(vec![1; 8])).is_ok());
+    assert!(MapRangeProof::<[u8; 32], u64>::from_pb(raw_proof(vec![1, 2, 3])).is_err());
+    assert!(MapRangeProof::<[u8; 32], bool>::from_pb(raw_proof(vec![2])).is_err());
+}
+
+fn build_range_proofs_for_data<K, V>(db: &Box<dyn Database>, data: Vec<(K, V)>, bounds: &[K])
+where
+    K: ProofMapKey<Output = K> + Copy + PartialEq + Debug + Serialize + DeserializeOwned,
+    V: StorageValue + Clone + PartialEq + Debug + Serialize + DeserializeOwned,
+{
+    let mut storage = db.fork();
+    let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+    for &(ref key, ref value) in &data {
+        table.put(key, value.clone());
+    }
+    let root = table.merkle_root();
+
+    let mut sorted_data = data;
+    sorted_data.sort_by(|x, y| {
+        ProofPath::new(&x.0)
+            .partial_cmp(&ProofPath::new(&y.0))
+            .unwrap()
+    });
+
+    for window in bounds.windows(2) {
+        let (from, to) = if ProofPath::new(&window[0]) <= ProofPath::new(&window[1]) {
+            (window[0], window[1])
+        } else {
+            (window[1], window[0])
+        };
+        let (from_path, to_path) = (ProofPath::new(&from), ProofPath::new(&to));
+        let expected_entries = sorted_data
+            .iter()
+            .filter(|(key, _)| {
+                let path = ProofPath::new(key);
+                from_path <= path && path <= to_path
+            })
+            .collect::<Vec<_>>();
+
+        let proof = table.get_range_proof(&from, &to);
+        assert_eq!(proof.bounds(), (from_path, to_path));
+
+        let json = serde_json::to_value(&proof).unwrap();
+        let deserialized: MapRangeProof<K, V> = serde_json::from_value(json).unwrap();
+        let pb = ProtobufConvert::from_pb(proof.to_pb()).unwrap();
+        for proof in vec![proof, deserialized, pb] {
+            let checked_proof = proof.check().unwrap();
+            assert_eq!(checked_proof.merkle_root(), root);
+            let entries = checked_proof
+                .entries()
+                .map(|(key, value)| (*key, value.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                entries.iter().collect::<Vec<_>>(),
+                expected_entries,
+                "Unexpected entries for the range {:?}..={:?}",
+                from,
+                to
+            );
+        }
+    }
+}
+
+fn fuzz_insert_build_range_proofs(db: Box<dyn Database>) {
+    let mut rng = XorShiftRng::from_seed(rand::random());
+    let batch_sizes = vec![0, 1, 2, 3, 127, 256];
+
+    for batch_size in batch_sizes {
+        let data = generate_random_data_keys(batch_size, &mut rng);
+        // Bounds include both existing and random keys, as well as the extreme keys.
+        let mut bounds = vec![[0; KEY_SIZE], [255; KEY_SIZE]];
+        bounds.extend(data.iter().take(8).map(|(key, _)| *key));
+        bounds.extend(
+            generate_random_data_keys(8, &mut rng)
+                .into_iter()
+                .map(|(key, _)| key),
+        );
+        bounds.push([0; KEY_SIZE]);
+        if let Some((key, _)) = data.first() {
+            // Range consisting of a single existing key.
+            bounds.push(*key);
+            bounds.push(*key);
+        }
+
+        build_range_proofs_for_data(&db, data, &bounds);
+    }
+}
+
 fn build_proof_in_empty_tree(db: Box<dyn Database>) {
     let mut storage = db.fork();
     let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
@@ -1329,6 +1483,80 @@ fn tree_with_hashed_key(db: Box<dyn Database>) {
     );
 }
 
+fn hashed_proof_map_index(db: Box<dyn Database>) {
+    let data: Vec<(String, u64)> = (0..50).map(|i| (format!("key #{}", i), i)).collect();
+
+    let mut fork = db.fork();
+    {
+        let mut table = HashedProofMapIndex::new(IDX_NAME, &mut fork);
+        for (key, value) in &data {
+            table.put(key, *value);
+        }
+        table.put(&"removed key".to_owned(), 100);
+        table.remove(&"removed key".to_owned());
+    }
+    {
+        let mut reference_table = ProofMapIndex::new("reference", &mut fork);
+        for (key, value) in &data {
+            reference_table.put(&hash(key.as_bytes()), *value);
+        }
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let reference_root = ProofMapIndex::<_, Hash, u64>::new("reference", &snapshot).merkle_root();
+    let table = HashedProofMapIndex::new(IDX_NAME, &snapshot);
+    assert_eq!(table.merkle_root(), reference_root);
+    assert_eq!(table.get(&"key #3".to_owned()), Some(3));
+    assert!(table.contains(&"key #49".to_owned()));
+    assert!(!table.contains(&"removed key".to_owned()));
+
+    let keys: HashSet<_> = table.keys().collect();
+    assert_eq!(
+        keys,
+        data.iter()
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>()
+    );
+    let entries: Vec<_> = table.iter().collect();
+    assert_eq!(entries.len(), data.len());
+    for (key, value) in &entries {
+        assert_eq!(table.get(key), Some(*value));
+    }
+    let values: Vec<_> = table.values().collect();
+    assert_eq!(values, entries.iter().map(|(_, v)| *v).collect::<Vec<_>>());
+
+    let proof = table.get_proof("key #7".to_owned());
+    let json = serde_json::to_value(&proof).unwrap();
+    let proof: MapProof<Hashed<String>, u64> = serde_json::from_value(json).unwrap();
+    let checked_proof = proof.check().unwrap();
+    assert_eq!(checked_proof.merkle_root(), reference_root);
+    assert_eq!(
+        checked_proof
+            .entries()
+            .map(|(k, v)| (k.as_str(), *v))
+            .collect::<Vec<_>>(),
+        vec![("key #7", 7)]
+    );
+
+    let proof = table.get_multiproof(vec!["key #1".to_owned(), "missing key".to_owned()]);
+    let checked_proof = proof.check().unwrap();
+    assert_eq!(checked_proof.merkle_root(), reference_root);
+    assert_eq!(
+        checked_proof
+            .missing_keys()
+            .map(|k| k.as_str())
+            .collect::<Vec<_>>(),
+        vec!["missing key"]
+    );
+
+    let mut fork = db.fork();
+    let mut table = HashedProofMapIndex::<_, String, u64>::new(IDX_NAME, &mut fork);
+    table.clear();
+    assert_eq!(table.merkle_root(), Hash::zero());
+    assert_eq!(table.keys().count(), 0);
+}
+
 macro_rules! test_on_db {
     {$test_name:ident, $fn_name:ident} => {
         #[test]
@@ -1382,11 +1610,13 @@ macro_rules! common_tests {
         }
         test_on_db!{test_fuzz_insert_build_proofs, fuzz_insert_build_proofs}
         test_on_db!{test_fuzz_insert_build_multiproofs, fuzz_insert_build_multiproofs}
+        test_on_db!{test_fuzz_insert_build_range_proofs, fuzz_insert_build_range_proofs}
         test_on_db!{test_fuzz_delete_build_proofs, fuzz_delete_build_proofs}
         test_on_2dbs!{test_fuzz_delete, fuzz_delete}
         test_on_db!{test_fuzz_insert_after_delete, fuzz_insert_after_delete}
         test_on_db!{test_iter, iter}
         test_on_db!{test_tree_with_hashed_key, tree_with_hashed_key}
+        test_on_db!{test_hashed_proof_map_index, hashed_proof_map_index}
     };
 }
 
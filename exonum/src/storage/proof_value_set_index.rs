@@ -0,0 +1,658 @@
+// Copyright 2019 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a Merkelized set of items that utilize the `StorageValue` trait.
+//!
+//! `ProofValueSetIndex` implements a set on top of a Merkle Patricia tree, storing
+//! an element as a value and using its hash as a key. The given section contains methods
+//! related to `ProofValueSetIndex` and iterators over the items of this set.
+
+use std::fmt;
+
+use super::{
+    base_index::BaseIndex,
+    indexes_metadata::IndexType,
+    proof_map_index::{ProofMapIndexIter, ProofMapIndexKeys},
+    Fork, MapProof, ProofMapIndex, Snapshot, StorageKey, StorageValue,
+};
+use crate::crypto::Hash;
+
+/// A Merkelized set of value items.
+///
+/// `ProofValueSetIndex` implements a set, storing an element as a value in the leaves
+/// of a Merkle Patricia tree and using its hash as a key. The set provides proofs
+/// of membership and non-membership for its elements.
+/// `ProofValueSetIndex` requires that elements should implement the [`StorageValue`] trait.
+///
+/// The proofs are [`MapProof`]s with the hashes of the elements as keys and the elements
+/// as values.
+///
+/// [`StorageValue`]: ../trait.StorageValue.html
+/// [`MapProof`]: ../proof_map_index/struct.MapProof.html
+pub struct ProofValueSetIndex<T, V> {
+    inner: ProofMapIndex<T, Hash, V>,
+}
+
+/// Returns an iterator over the items of a `ProofValueSetIndex`.
+///
+/// This struct is created by the [`iter`] method on [`ProofValueSetIndex`].
+/// See its documentation for details.
+///
+/// [`iter`]: struct.ProofValueSetIndex.html#method.iter
+/// [`ProofValueSetIndex`]: struct.ProofValueSetIndex.html
+#[derive(Debug)]
+pub struct ProofValueSetIndexIter<'a, V> {
+    inner: ProofMapIndexIter<'a, Hash, V>,
+}
+
+/// Returns an iterator over the hashes of items of a `ProofValueSetIndex`.
+///
+/// This struct is created by the [`hashes`] method on [`ProofValueSetIndex`].
+/// See its documentation for details.
+///
+/// [`hashes`]: struct.ProofValueSetIndex.html#method.hashes
+/// [`ProofValueSetIndex`]: struct.ProofValueSetIndex.html
+#[derive(Debug)]
+pub struct ProofValueSetIndexHashes<'a> {
+    inner: ProofMapIndexKeys<'a, Hash>,
+}
+
+impl<T, V> ProofValueSetIndex<T, V>
+where
+    T: AsRef<dyn Snapshot>,
+    V: StorageValue,
+{
+    /// Creates a new index representation based on the name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofValueSetIndex<_, u8> = ProofValueSetIndex::new(name, &snapshot);
+    /// ```
+    pub fn new<S: AsRef<str>>(index_name: S, view: T) -> Self {
+        Self {
+            inner: ProofMapIndex::from_base(BaseIndex::new(
+                index_name,
+                IndexType::ProofValueSet,
+                view,
+            )),
+        }
+    }
+
+    /// Creates a new index representation based on the name, index ID in family
+    /// and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let name = "name";
+    /// let index_id = vec![123];
+    /// let index: ProofValueSetIndex<_, u8> =
+    ///     ProofValueSetIndex::new_in_family(name, &index_id, &snapshot);
+    /// ```
+    pub fn new_in_family<S, I>(family_name: S, index_id: &I, view: T) -> Self
+    where
+        I: StorageKey,
+        I: ?Sized,
+        S: AsRef<str>,
+    {
+        Self {
+            inner: ProofMapIndex::from_base(BaseIndex::new_in_family(
+                family_name,
+                index_id,
+                IndexType::ProofValueSet,
+                view,
+            )),
+        }
+    }
+
+    /// Returns `true` if the set contains the indicated value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    /// assert!(!index.contains(&1));
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    /// ```
+    pub fn contains(&self, item: &V) -> bool {
+        self.contains_by_hash(&item.hash())
+    }
+
+    /// Returns `true` if the set contains a value with the specified hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    /// use exonum::crypto;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// let data = vec![1, 2, 3];
+    /// let data_hash = crypto::hash(&data);
+    /// assert!(!index.contains_by_hash(&data_hash));
+    ///
+    /// index.insert(data);
+    /// assert!(index.contains_by_hash(&data_hash));
+    /// ```
+    pub fn contains_by_hash(&self, hash: &Hash) -> bool {
+        self.inner.contains(hash)
+    }
+
+    /// Returns the Merkle root hash of the set or the default hash value if it is empty.
+    /// The default hash consists solely of zeroes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    /// assert_eq!(Hash::default(), index.merkle_root());
+    ///
+    /// index.insert(1);
+    /// assert_ne!(Hash::default(), index.merkle_root());
+    /// ```
+    pub fn merkle_root(&self) -> Hash {
+        self.inner.merkle_root()
+    }
+
+    /// Returns the proof of membership or non-membership for a value with the specified hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    /// use exonum::crypto;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// let data = vec![1, 2, 3];
+    /// let data_hash = crypto::hash(&data);
+    /// index.insert(data.clone());
+    ///
+    /// let proof = index.get_proof(data_hash).check().unwrap();
+    /// assert_eq!(proof.merkle_root(), index.merkle_root());
+    /// assert_eq!(proof.entries().collect::<Vec<_>>(), vec![(&data_hash, &data)]);
+    /// ```
+    pub fn get_proof(&self, hash: Hash) -> MapProof<Hash, V> {
+        self.inner.get_proof(hash)
+    }
+
+    /// Returns the combined proof of membership or non-membership for the values
+    /// with the specified hashes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    /// use exonum::crypto::{self, Hash};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofValueSetIndex<_, u8> = ProofValueSetIndex::new(name, &snapshot);
+    ///
+    /// let proof = index.get_multiproof(vec![Hash::zero(), crypto::hash(&[1])]);
+    /// ```
+    pub fn get_multiproof<KI>(&self, hashes: KI) -> MapProof<Hash, V>
+    where
+        KI: IntoIterator<Item = Hash>,
+    {
+        self.inner.get_multiproof(hashes)
+    }
+
+    /// Returns an iterator visiting all elements in ascending order of their hashes.
+    /// The iterator element type is (Hash, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofValueSetIndex<_, u8> = ProofValueSetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn iter(&self) -> ProofValueSetIndexIter<V> {
+        ProofValueSetIndexIter {
+            inner: self.inner.iter(),
+        }
+    }
+
+    /// Returns an iterator visiting hashes of all elements in ascending order.
+    /// The iterator element type is Hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofValueSetIndex<_, u8> = ProofValueSetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.hashes() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn hashes(&self) -> ProofValueSetIndexHashes {
+        ProofValueSetIndexHashes {
+            inner: self.inner.keys(),
+        }
+    }
+}
+
+impl<'a, V> ProofValueSetIndex<&'a mut Fork, V>
+where
+    V: StorageValue,
+{
+    /// Adds a value to the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    /// ```
+    pub fn insert(&mut self, item: V) {
+        self.inner.put(&item.hash(), item)
+    }
+
+    /// Removes a value from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    ///
+    /// index.remove(&1);
+    /// assert!(!index.contains(&1));
+    /// ```
+    pub fn remove(&mut self, item: &V) {
+        self.remove_by_hash(&item.hash())
+    }
+
+    /// Removes a value corresponding to the specified hash from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    /// use exonum::crypto;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// let data = vec![1, 2, 3];
+    /// let data_hash = crypto::hash(&data);
+    /// index.insert(data);
+    /// assert!(index.contains_by_hash(&data_hash));
+    ///
+    /// index.remove_by_hash(&data_hash);
+    /// assert!(!index.contains_by_hash(&data_hash));
+    /// ```
+    pub fn remove_by_hash(&mut self, hash: &Hash) {
+        self.inner.remove(hash)
+    }
+
+    /// Clears the set, removing all values.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofValueSetIndex::new(name, &mut fork);
+    ///
+    /// index.insert(1);
+    /// assert!(index.contains(&1));
+    ///
+    /// index.clear();
+    /// assert!(!index.contains(&1));
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+}
+
+impl<'a, T, V> ::std::iter::IntoIterator for &'a ProofValueSetIndex<T, V>
+where
+    T: AsRef<dyn Snapshot>,
+    V: StorageValue,
+{
+    type Item = (Hash, V);
+    type IntoIter = ProofValueSetIndexIter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> Iterator for ProofValueSetIndexIter<'a, V>
+where
+    V: StorageValue,
+{
+    type Item = (Hash, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> Iterator for ProofValueSetIndexHashes<'a> {
+    type Item = Hash;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<T, V> fmt::Debug for ProofValueSetIndex<T, V>
+where
+    T: AsRef<dyn Snapshot>,
+    V: StorageValue + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProofValueSetIndex")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::super::{Database, MemoryDB, UniqueHash, ValueSetIndex};
+    use super::*;
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    fn items() -> Vec<String> {
+        (0..10).map(|i| format!("item #{}", i)).collect()
+    }
+
+    fn is_valid(proof: &MapProof<Hash, String>, root: Hash) -> bool {
+        proof
+            .clone()
+            .check()
+            .map_or(false, |proof| proof.merkle_root() == root)
+    }
+
+    #[test]
+    fn proof_value_set_methods() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        assert_eq!(index.merkle_root(), Hash::zero());
+
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+        assert!(index.contains(&items[0]));
+        assert!(index.contains_by_hash(&items[1].hash()));
+        assert!(!index.contains(&"missing".to_owned()));
+        assert_ne!(index.merkle_root(), Hash::zero());
+
+        let mut stored_items: Vec<_> = index.iter().map(|(_, item)| item).collect();
+        stored_items.sort();
+        assert_eq!(stored_items, items);
+        let mut stored_hashes: Vec<_> = index.hashes().collect();
+        stored_hashes.sort();
+        let mut hashes: Vec<_> = items.iter().map(UniqueHash::hash).collect();
+        hashes.sort();
+        assert_eq!(stored_hashes, hashes);
+
+        index.remove(&items[0]);
+        index.remove_by_hash(&items[1].hash());
+        assert!(!index.contains(&items[0]));
+        assert!(!index.contains(&items[1]));
+        assert_eq!(index.iter().count(), items.len() - 2);
+
+        index.clear();
+        assert_eq!(index.iter().count(), 0);
+        assert_eq!(index.merkle_root(), Hash::zero());
+    }
+
+    #[test]
+    fn membership_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+
+        for item in &items {
+            let proof = index.get_proof(item.hash()).check().unwrap();
+            assert_eq!(proof.merkle_root(), index.merkle_root());
+            assert_eq!(
+                proof.entries().collect::<Vec<_>>(),
+                vec![(&item.hash(), item)]
+            );
+            assert_eq!(proof.missing_keys().count(), 0);
+        }
+
+        let proof = index.get_multiproof(items.iter().map(UniqueHash::hash));
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof: MapProof<Hash, String> = serde_json::from_str(&json).unwrap();
+        let proof = proof.check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        let mut proven_items: Vec<_> = proof.entries().map(|(_, item)| item.clone()).collect();
+        proven_items.sort();
+        assert_eq!(proven_items, items);
+    }
+
+    #[test]
+    fn non_membership_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items[..5] {
+            index.insert(item.clone());
+        }
+
+        for item in &items[5..] {
+            let proof = index.get_proof(item.hash()).check().unwrap();
+            assert_eq!(proof.merkle_root(), index.merkle_root());
+            assert_eq!(proof.entries().count(), 0);
+            assert_eq!(proof.missing_keys().collect::<Vec<_>>(), vec![&item.hash()]);
+        }
+
+        let proof = index
+            .get_multiproof(vec![items[0].hash(), items[7].hash()])
+            .check()
+            .unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        assert_eq!(
+            proof.entries().collect::<Vec<_>>(),
+            vec![(&items[0].hash(), &items[0])]
+        );
+        assert_eq!(
+            proof.missing_keys().collect::<Vec<_>>(),
+            vec![&items[7].hash()]
+        );
+    }
+
+    #[test]
+    fn proofs_in_empty_set() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let index: ProofValueSetIndex<_, String> = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+
+        let item = "missing".to_owned();
+        let proof = index.get_proof(item.hash());
+        assert_eq!(proof.proof_unchecked(), vec![]);
+        let proof = proof.check().unwrap();
+        assert_eq!(proof.merkle_root(), Hash::zero());
+        assert_eq!(proof.missing_keys().collect::<Vec<_>>(), vec![&item.hash()]);
+
+        let proof = index
+            .get_multiproof(items().iter().map(UniqueHash::hash))
+            .check()
+            .unwrap();
+        assert_eq!(proof.merkle_root(), Hash::zero());
+        assert_eq!(proof.missing_keys().count(), items().len());
+    }
+
+    #[test]
+    fn proofs_after_remove() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+        let initial_root = index.merkle_root();
+
+        index.remove(&items[3]);
+        assert_ne!(index.merkle_root(), initial_root);
+        let proof = index.get_proof(items[3].hash()).check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        assert_eq!(
+            proof.missing_keys().collect::<Vec<_>>(),
+            vec![&items[3].hash()]
+        );
+        let proof = index.get_proof(items[4].hash()).check().unwrap();
+        assert_eq!(proof.merkle_root(), index.merkle_root());
+        assert_eq!(proof.entries().count(), 1);
+
+        // Inserting the removed item back restores the previous state.
+        index.insert(items[3].clone());
+        assert_eq!(index.merkle_root(), initial_root);
+    }
+
+    #[test]
+    fn tampered_proofs() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let mut index = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        let items = items();
+        for item in &items {
+            index.insert(item.clone());
+        }
+        let root = index.merkle_root();
+
+        let proof = index.get_proof(items[0].hash());
+        assert!(is_valid(&proof, root));
+        let json = serde_json::to_value(&proof).unwrap();
+
+        let mut tampered = json.clone();
+        tampered["entries"][0]["value"] = Value::from("forged item");
+        let tampered: MapProof<Hash, String> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+
+        let mut tampered = json.clone();
+        tampered["proof"][0]["hash"] = serde_json::to_value(Hash::zero()).unwrap();
+        let tampered: MapProof<Hash, String> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+
+        // Proof of absence for an item of the set.
+        let mut tampered = json;
+        tampered["entries"][0] = json!({ "missing": items[0].hash() });
+        let tampered: MapProof<Hash, String> = serde_json::from_value(tampered).unwrap();
+        assert!(!is_valid(&tampered, root));
+    }
+
+    #[test]
+    #[should_panic(expected = "initially created with type ValueSet")]
+    fn value_set_as_proof_value_set() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut index = ValueSetIndex::new(INDEX_NAME, &mut fork);
+            index.insert(1_u8);
+        }
+        let index: ProofValueSetIndex<_, u8> = ProofValueSetIndex::new(INDEX_NAME, &mut fork);
+        index.contains(&1);
+    }
+}
@@ -23,9 +23,13 @@ pub use crate::rocksdb::{
 use std::{error::Error, fmt, iter::Peekable, mem, path::Path, sync::Arc};
 
 use crate::rocksdb::{
-    self, utils::get_cf_names, DBIterator, Options as RocksDbOptions, WriteBatch,
+    self, checkpoint::Checkpoint, utils::get_cf_names, ColumnFamilyDescriptor, DBCompressionType,
+    DBIterator, Options as RocksDbOptions, WriteBatch,
+};
+use crate::storage::{
+    self, db::Change, CompressionType, Database, DbOptions, IndexOptions, Iter, Iterator, Patch,
+    Snapshot,
 };
-use crate::storage::{self, db::Change, Database, DbOptions, Iter, Iterator, Patch, Snapshot};
 
 impl From<rocksdb::Error> for storage::Error {
     fn from(err: rocksdb::Error) -> Self {
@@ -41,17 +45,99 @@ impl From<rocksdb::Error> for storage::Error {
 /// use different databases.
 pub struct RocksDB {
     db: Arc<rocksdb::DB>,
+    options: DbOptions,
+    shared_table_options: Option<SharedTableOptions>,
 }
 
+/// Table options with the block cache shared by all indexes, which do not override
+/// the block cache size or bloom filters.
+struct SharedTableOptions(RocksBlockOptions);
+
+// `RocksBlockOptions` is a handle to the native table options, so it is neither `Send`
+// nor `Sync`. The handle is owned by the wrapper and is never mutated after creation;
+// it is only read by `set_block_based_table_factory`, which copies the options into
+// a new table factory. The copy shares the block cache by a reference-counted pointer
+// with atomic counters, and the cache itself is thread-safe in `RocksDB`. Hence,
+// the options can be moved to and copied from any thread.
+unsafe impl Send for SharedTableOptions {}
+unsafe impl Sync for SharedTableOptions {}
+
 impl DbOptions {
-    fn to_rocksdb(&self) -> RocksDbOptions {
+    fn to_rocksdb(&self, shared_table_options: Option<&SharedTableOptions>) -> RocksDbOptions {
         let mut defaults = RocksDbOptions::default();
         defaults.create_if_missing(self.create_if_missing);
         defaults.set_max_open_files(self.max_open_files.unwrap_or(-1));
+        self.indexes.apply_to(&mut defaults);
+        if let Some(table_options) = shared_table_options {
+            defaults.set_block_based_table_factory(&table_options.0);
+        }
+        defaults
+    }
+
+    fn to_rocksdb_cf(
+        &self,
+        name: &str,
+        shared_table_options: Option<&SharedTableOptions>,
+    ) -> RocksDbOptions {
+        let index_options = self.index_options(name);
+        let mut defaults = RocksDbOptions::default();
+        index_options.apply_to(&mut defaults);
+        if index_options.block_cache_size == self.indexes.block_cache_size
+            && index_options.bloom_filter_bits == self.indexes.bloom_filter_bits
+        {
+            // Table options are copied together with the pointer to the block cache,
+            // so all column families configured from the same options share the cache.
+            if let Some(table_options) = shared_table_options {
+                defaults.set_block_based_table_factory(&table_options.0);
+            }
+        } else if let Some(table_options) = index_options.table_options() {
+            defaults.set_block_based_table_factory(&table_options);
+        }
         defaults
     }
 }
 
+impl IndexOptions {
+    fn apply_to(&self, options: &mut RocksDbOptions) {
+        if let Some(compression) = self.compression {
+            options.set_compression_type(compression.into());
+        }
+        if let Some(write_buffer_size) = self.write_buffer_size {
+            options.set_write_buffer_size(write_buffer_size);
+        }
+    }
+
+    // Creates table options with a new block cache, if the cache size or bloom filters
+    // are specified.
+    fn table_options(&self) -> Option<RocksBlockOptions> {
+        if self.block_cache_size.is_none() && self.bloom_filter_bits.is_none() {
+            return None;
+        }
+        let mut table_options = RocksBlockOptions::default();
+        if let Some(block_cache_size) = self.block_cache_size {
+            table_options.set_lru_cache(block_cache_size);
+        }
+        if let Some(bloom_filter_bits) = self.bloom_filter_bits {
+            table_options.set_bloom_filter(bloom_filter_bits, false);
+        }
+        Some(table_options)
+    }
+}
+
+impl From<CompressionType> for DBCompressionType {
+    fn from(compression: CompressionType) -> Self {
+        match compression {
+            CompressionType::None => DBCompressionType::None,
+            CompressionType::Snappy => DBCompressionType::Snappy,
+            CompressionType::Zlib => DBCompressionType::Zlib,
+            CompressionType::Bz2 => DBCompressionType::Bz2,
+            CompressionType::Lz4 => DBCompressionType::Lz4,
+            CompressionType::Lz4hc => DBCompressionType::Lz4hc,
+            CompressionType::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
 /// A snapshot of a `RocksDB`.
 pub struct RocksDBSnapshot {
     snapshot: rocksdb::Snapshot<'static>,
@@ -71,16 +157,32 @@ impl RocksDB {
     /// If the database does not exist at the indicated path and the option
     /// `create_if_missing` is switched on in `DbOptions`, a new database will
     /// be created at the indicated path.
+    ///
+    /// Column families of the indexes are opened with the tuning options specified
+    /// for these indexes in `DbOptions`.
     pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> storage::Result<Self> {
+        let shared_table_options = options.indexes.table_options().map(SharedTableOptions);
         let db = {
+            let db_options = options.to_rocksdb(shared_table_options.as_ref());
             if let Ok(names) = get_cf_names(&path) {
-                let cf_names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
-                rocksdb::DB::open_cf(&options.to_rocksdb(), path, cf_names.as_ref())?
+                let descriptors = names
+                    .into_iter()
+                    .map(|name| {
+                        let cf_options =
+                            options.to_rocksdb_cf(&name, shared_table_options.as_ref());
+                        ColumnFamilyDescriptor::new(name, cf_options)
+                    })
+                    .collect::<Vec<_>>();
+                rocksdb::DB::open_cf_descriptors(&db_options, path, descriptors)?
             } else {
-                rocksdb::DB::open(&options.to_rocksdb(), path)?
+                rocksdb::DB::open(&db_options, path)?
             }
         };
-        Ok(Self { db: Arc::new(db) })
+        Ok(Self {
+            db: Arc::new(db),
+            options: options.clone(),
+            shared_table_options,
+        })
     }
 
     fn do_merge(&self, patch: Patch, w_opts: &RocksDBWriteOptions) -> storage::Result<()> {
@@ -88,10 +190,12 @@ impl RocksDB {
         for (cf_name, changes) in patch {
             let cf = match self.db.cf_handle(&cf_name) {
                 Some(cf) => cf,
-                None => self
-                    .db
-                    .create_cf(&cf_name, &DbOptions::default().to_rocksdb())
-                    .unwrap(),
+                None => {
+                    let cf_options = self
+                        .options
+                        .to_rocksdb_cf(&cf_name, self.shared_table_options.as_ref());
+                    self.db.create_cf(&cf_name, &cf_options).unwrap()
+                }
             };
             for (key, change) in changes {
                 match change {
@@ -122,6 +226,11 @@ impl Database for RocksDB {
         w_opts.set_sync(true);
         self.do_merge(patch, &w_opts)
     }
+
+    fn create_checkpoint(&self, path: &Path) -> storage::Result<()> {
+        let checkpoint = Checkpoint::new(&self.db)?;
+        checkpoint.create_checkpoint(path).map_err(Into::into)
+    }
 }
 
 impl Snapshot for RocksDBSnapshot {
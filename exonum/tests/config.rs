@@ -119,6 +119,8 @@ fn generate_template(folder: &str) {
         &full_tmp_name(GENERATED_TEMPLATE, folder),
         "--validators-count",
         "1",
+        "--genesis-time",
+        "2019-01-01T00:00:00Z",
     ]));
 }
 
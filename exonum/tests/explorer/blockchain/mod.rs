@@ -28,6 +28,7 @@ use exonum::{
 };
 
 pub const SERVICE_ID: u16 = 0;
+pub const WALLET_CREATED_TOPIC: &str = "wallet_created";
 
 mod proto;
 
@@ -72,7 +73,9 @@ pub enum ExplorerTransactions {
 }
 
 impl Transaction for CreateWallet {
-    fn execute(&self, _: TransactionContext) -> ExecutionResult {
+    fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
+        // The event is discarded if the execution fails.
+        context.emit_event(WALLET_CREATED_TOPIC, self);
         if self.name.starts_with("Al") {
             Ok(())
         } else {
@@ -172,6 +175,7 @@ pub fn create_block(blockchain: &mut Blockchain, transactions: Vec<Signed<RawTra
             Round::first(),
             &blockchain.last_hash(),
             &tx_hashes,
+            &[],
         ),
         consensus_public_key,
         &consensus_secret_key,
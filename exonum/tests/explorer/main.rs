@@ -25,6 +25,10 @@ extern crate serde_derive;
 extern crate pretty_assertions;
 
 use exonum::{
+    api::{
+        node::public::explorer::{BlocksQuery, EventsQuery, ExplorerApi, TransactionQuery},
+        Error as ApiError, ServiceApiState,
+    },
     blockchain::{Schema, TransactionErrorType, TransactionSet, TxLocation},
     crypto::{self, Hash},
     explorer::*,
@@ -34,6 +38,7 @@ use exonum::{
 
 use crate::blockchain::{
     create_block, create_blockchain, CreateWallet, ExplorerTransactions, Transfer, SERVICE_ID,
+    WALLET_CREATED_TOPIC,
 };
 
 mod blockchain;
@@ -169,6 +174,108 @@ fn test_explorer_basics() {
     );
 }
 
+#[test]
+fn test_explorer_events() {
+    let mut blockchain = create_blockchain();
+
+    let (pk_alice, key_alice) = crypto::gen_keypair();
+    let (pk_bob, key_bob) = crypto::gen_keypair();
+    let payload_alice = CreateWallet::new(&pk_alice, "Alice");
+    let tx_alice =
+        Message::sign_transaction(payload_alice.clone(), SERVICE_ID, pk_alice, &key_alice);
+    let payload_bob = CreateWallet::new(&pk_bob, "Bob");
+    let tx_bob = Message::sign_transaction(payload_bob, SERVICE_ID, pk_bob, &key_bob);
+    create_block(&mut blockchain, vec![tx_alice.clone(), tx_bob.clone()]);
+
+    let explorer = BlockchainExplorer::new(&blockchain);
+    let events = explorer.block_events(Height(1));
+    assert_eq!(events.len(), 1);
+    let info = &events[0];
+    assert_eq!(info.height, Height(1));
+    assert_eq!(info.position, 0);
+    assert_eq!(info.event.tx_hash(), &tx_alice.hash());
+    assert_eq!(info.event.service_id(), SERVICE_ID);
+    assert_eq!(info.event.topic(), WALLET_CREATED_TOPIC);
+    let payload: CreateWallet = info.event.payload_as().unwrap();
+    assert_eq!(payload.name, payload_alice.name);
+
+    assert_eq!(explorer.transaction_events(&tx_alice.hash()), events);
+    // Events of the failed transaction are not stored.
+    assert!(explorer.transaction_events(&tx_bob.hash()).is_empty());
+
+    let header = explorer.block(Height(1)).unwrap().into_header();
+    let schema = Schema::new(blockchain.snapshot());
+    assert_eq!(
+        header.events_hash(),
+        &schema.block_events(Height(1)).merkle_root()
+    );
+    assert_ne!(header.events_hash(), &Hash::zero());
+}
+
+#[test]
+fn test_explorer_api_at_height() {
+    let mut blockchain = create_blockchain();
+    blockchain.set_archive(true);
+
+    let txs = ["Alice", "Alex"]
+        .iter()
+        .map(|name| {
+            let (pk, key) = crypto::gen_keypair();
+            Message::sign_transaction(CreateWallet::new(&pk, name), SERVICE_ID, pk, &key)
+        })
+        .collect::<Vec<_>>();
+    for tx in &txs {
+        create_block(&mut blockchain, vec![tx.clone()]);
+    }
+    let state = ServiceApiState::new(blockchain.clone());
+
+    let blocks = |height| {
+        let query = BlocksQuery {
+            count: 10,
+            height,
+            ..Default::default()
+        };
+        ExplorerApi::blocks(&state, query).unwrap().blocks.len()
+    };
+    assert_eq!(blocks(None), 3);
+    assert_eq!(blocks(Some(Height(1))), 2);
+
+    let is_committed = |hash, height| {
+        let query = TransactionQuery {
+            hash,
+            height: Some(height),
+        };
+        ExplorerApi::transaction_info(&state, query)
+            .ok()
+            .map_or(false, |info| info.is_committed())
+    };
+    assert!(is_committed(txs[0].hash(), Height(1)));
+    assert!(!is_committed(txs[1].hash(), Height(1)));
+    assert!(is_committed(txs[1].hash(), Height(2)));
+
+    let events = |height| {
+        let query = EventsQuery {
+            height,
+            ..Default::default()
+        };
+        ExplorerApi::events(&state, query).unwrap()
+    };
+    assert_eq!(events(None).len(), 2);
+    let events = events(Some(Height(1)));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].event.tx_hash(), &txs[0].hash());
+
+    // The state above the latest height is not available.
+    let query = TransactionQuery {
+        hash: txs[0].hash(),
+        height: Some(Height(3)),
+    };
+    match ExplorerApi::transaction_info(&state, query) {
+        Err(ApiError::NotFound(_)) => {}
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
 #[test]
 fn test_explorer_pool_transaction() {
     let mut blockchain = create_blockchain();
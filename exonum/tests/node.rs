@@ -14,6 +14,8 @@
 
 // This is a regression test for exonum node.
 
+use actix::System;
+use actix_web::{client, HttpMessage};
 use futures::{sync::oneshot, Future, IntoFuture};
 use serde_json::Value;
 use tokio::util::FutureExt;
@@ -28,9 +30,9 @@ use std::{
 use exonum::{
     blockchain::{Service, ServiceContext, Transaction},
     crypto::Hash,
-    helpers,
+    helpers::{self, Height, ValidatorId},
     messages::RawTransaction,
-    node::{ApiSender, ExternalMessage, Node},
+    node::{ApiSender, ConsensusStateInfo, ExternalMessage, Node},
     storage::{Database, Fork, MemoryDB, Snapshot},
 };
 
@@ -156,3 +158,45 @@ fn test_node_restart_regression() {
     start_node(node_cfg, db, Arc::clone(&init_times));
     assert_eq!(*init_times.lock().unwrap(), 1);
 }
+
+#[test]
+fn test_consensus_state_endpoint() {
+    let mut node_cfg = helpers::generate_testnet_config(1, 16_400)[0].clone();
+    let api_address = "127.0.0.1:16401".parse().unwrap();
+    node_cfg.api.private_api_address = Some(api_address);
+
+    let node = Node::new(MemoryDB::new(), vec![], node_cfg, None);
+    let api_tx = node.channel();
+    let node_thread = thread::spawn(move || {
+        node.run().unwrap();
+    });
+
+    let url = format!("http://{}/api/system/v1/consensus_state", api_address);
+    let mut system = System::new("consensus-state");
+    // Wait until the API server is started and the node commits a block.
+    let mut info = None;
+    for _ in 0..100 {
+        let request = client::get(&url).finish().unwrap();
+        let response = system.block_on(
+            request
+                .send()
+                .from_err::<actix_web::Error>()
+                .and_then(|response| response.json::<ConsensusStateInfo>().from_err()),
+        );
+        match response {
+            Ok(state) if state.height > Height(1) => {
+                info = Some(state);
+                break;
+            }
+            _ => thread::sleep(Duration::from_millis(100)),
+        }
+    }
+    let info = info.expect("Node has not sent its consensus state");
+    assert_eq!(info.leader, ValidatorId(0));
+    assert_eq!(info.validators.len(), 1);
+
+    api_tx
+        .send_external_message(ExternalMessage::Shutdown)
+        .unwrap();
+    node_thread.join().unwrap();
+}
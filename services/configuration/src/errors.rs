@@ -61,6 +61,17 @@ pub enum ErrorCode {
     /// Specific for `Propose`.
     InvalidMajorityCount = 34,
 
+    /// The configuration removes a service which is active in the actual configuration.
+    ///
+    /// Specific for `Propose`.
+    ServiceRemoved = 35,
+
+    /// The configuration specifies a schema version of a service which the service
+    /// does not support or cannot be migrated to.
+    ///
+    /// Specific for `Propose`.
+    UnsupportedServiceVersion = 36,
+
     /// The transaction references an unknown configuration.
     ///
     /// Specific for `Vote`.
@@ -105,6 +116,12 @@ pub(crate) enum Error {
         proposed: usize,
     },
 
+    #[fail(display = "Service {} cannot be deactivated", _0)]
+    ServiceRemoved(String),
+
+    #[fail(display = "Unsupported service schema version: {}", _0)]
+    UnsupportedServiceVersion(String),
+
     #[fail(display = "Does not reference known config with hash {:?}", _0)]
     UnknownConfigRef(Hash),
 
@@ -124,6 +141,8 @@ impl Error {
             AlreadyProposed(..) => ErrorCode::AlreadyProposed,
             InvalidConfig(..) => ErrorCode::InvalidConfig,
             InvalidMajorityCount { .. } => ErrorCode::InvalidMajorityCount,
+            ServiceRemoved(..) => ErrorCode::ServiceRemoved,
+            UnsupportedServiceVersion(..) => ErrorCode::UnsupportedServiceVersion,
             UnknownConfigRef(..) => ErrorCode::UnknownConfigRef,
             AlreadyVoted => ErrorCode::AlreadyVoted,
         }
@@ -332,6 +332,53 @@ fn test_discard_proposes_with_too_small_majority_count() {
     assert!(testkit.find_propose(new_cfg.hash()).is_none());
 }
 
+#[test]
+fn test_discard_proposes_removing_services() {
+    let mut testkit: TestKit = TestKit::configuration_default();
+
+    let new_cfg = {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(5));
+        let mut cfg = cfg.stored_configuration().clone();
+        cfg.services.remove(SERVICE_NAME);
+        cfg
+    };
+
+    let propose_tx = new_tx_config_propose(&testkit.network().validators()[1], new_cfg.clone());
+    testkit.create_block_with_transactions(txvec![propose_tx]);
+    assert!(testkit.find_propose(new_cfg.hash()).is_none());
+}
+
+#[test]
+fn test_discard_proposes_with_unsupported_service_versions() {
+    let mut testkit: TestKit = TestKit::configuration_default();
+
+    let proposal = |name: &str, version| {
+        let mut cfg = testkit.configuration_change_proposal();
+        cfg.set_actual_from(Height(5));
+        let mut cfg = cfg.stored_configuration().clone();
+        cfg.service_versions.insert(name.to_owned(), version);
+        cfg
+    };
+    // The service has no migrations to the version 1.
+    let unsupported_cfg = proposal(SERVICE_NAME, 1);
+    let unknown_service_cfg = proposal("unknown", 0);
+    let supported_cfg = proposal(SERVICE_NAME, 0);
+
+    let propose_txs = [&unsupported_cfg, &unknown_service_cfg]
+        .iter()
+        .map(|cfg| new_tx_config_propose(&testkit.network().validators()[1], (*cfg).clone()))
+        .collect::<Vec<_>>();
+    testkit.create_block_with_transactions(propose_txs);
+    assert!(testkit.find_propose(unsupported_cfg.hash()).is_none());
+    assert!(testkit.find_propose(unknown_service_cfg.hash()).is_none());
+
+    let propose_tx =
+        new_tx_config_propose(&testkit.network().validators()[1], supported_cfg.clone());
+    testkit.create_block_with_transactions(txvec![propose_tx]);
+    assert!(testkit.find_propose(supported_cfg.hash()).is_some());
+}
+
 #[test]
 fn test_discard_propose_for_same_cfg() {
     let mut testkit: TestKit = TestKit::configuration_default();
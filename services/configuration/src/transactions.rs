@@ -231,6 +231,14 @@ impl Propose {
             return Err(ActivationInPast(current_height));
         }
 
+        if let Some(name) = actual_config
+            .services
+            .keys()
+            .find(|name| !candidate.services.contains_key(*name))
+        {
+            return Err(ServiceRemoved(name.clone()));
+        }
+
         let config: ConfigurationServiceConfig = get_service_config(candidate);
 
         if let Some(proposed_majority_count) = config.majority_count.map(|count| count as usize) {
@@ -291,13 +299,20 @@ impl Propose {
 impl Transaction for Propose {
     fn execute(&self, mut context: TransactionContext) -> ExecutionResult {
         let author = context.author();
-        let fork = context.fork();
-        let (cfg, cfg_hash) = self.precheck(fork.as_ref(), author).map_err(|err| {
-            error!("Discarding propose {:?}: {}", self, err);
-            err
-        })?;
+        let (cfg, cfg_hash) = self
+            .precheck(context.fork().as_ref(), author)
+            .and_then(|(cfg, cfg_hash)| {
+                context
+                    .check_service_versions(&cfg)
+                    .map_err(|e| ServiceError::UnsupportedServiceVersion(e.to_string()))?;
+                Ok((cfg, cfg_hash))
+            })
+            .map_err(|err| {
+                error!("Discarding propose {:?}: {}", self, err);
+                err
+            })?;
 
-        self.save(fork, &cfg, cfg_hash);
+        self.save(context.fork(), &cfg, cfg_hash);
         trace!("Put propose {:?} to config_proposes table", self);
         Ok(())
     }
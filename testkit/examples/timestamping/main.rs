@@ -125,7 +125,7 @@ fn main() {
     assert_eq!(blocks_range.blocks.len(), 2);
 
     api.public(ApiKind::Explorer)
-        .query(&TransactionQuery { hash: tx1.hash() })
+        .query(&TransactionQuery::new(tx1.hash()))
         .get::<serde_json::Value>("v1/transactions")
         .unwrap();
 }
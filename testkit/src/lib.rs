@@ -176,7 +176,7 @@ use exonum::{
         backends::actix::{ApiRuntimeConfig, SystemRuntimeConfig},
         ApiAccess,
     },
-    blockchain::{Blockchain, Schema as CoreSchema, Service, StoredConfiguration},
+    blockchain::{block_time, Blockchain, Schema as CoreSchema, Service, StoredConfiguration},
     crypto::{self, Hash},
     explorer::{BlockWithTransactions, BlockchainExplorer},
     helpers::{Height, ValidatorId},
@@ -443,6 +443,7 @@ impl TestKit {
                         ExternalMessage::PeerAdd(_)
                         | ExternalMessage::Enable(_)
                         | ExternalMessage::Rebroadcast
+                        | ExternalMessage::ConsensusState(_)
                         | ExternalMessage::Shutdown => { /* Ignored */ }
                     }
                 }
@@ -616,10 +617,17 @@ impl TestKit {
         let last_hash = self.last_block_hash();
 
         let config_patch = self.update_configuration(new_block_height);
+        let snapshot = self.blockchain.snapshot();
+        let prev_precommits: Vec<_> = CoreSchema::new(&snapshot)
+            .precommits(&last_hash)
+            .iter()
+            .collect();
+        let time = block_time(&prev_precommits, self.blockchain.last_block().time());
+
         let (block_hash, patch) = {
             let validator_id = self.leader().validator_id().unwrap();
             self.blockchain
-                .create_patch(validator_id, new_block_height, tx_hashes)
+                .create_patch_with_time(validator_id, new_block_height, tx_hashes, time)
         };
 
         let patch = if let Some(config_patch) = config_patch {
@@ -631,9 +639,9 @@ impl TestKit {
             patch
         };
 
-        let propose = self
-            .leader()
-            .create_propose(new_block_height, &last_hash, tx_hashes);
+        let propose =
+            self.leader()
+                .create_propose(new_block_height, &last_hash, tx_hashes, &prev_precommits);
         let precommits: Vec<_> = self
             .network()
             .validators()
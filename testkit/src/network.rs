@@ -169,6 +169,7 @@ impl TestNode {
         height: Height,
         last_hash: &crypto::Hash,
         tx_hashes: &[crypto::Hash],
+        prev_precommits: &[Signed<Precommit>],
     ) -> Signed<Propose> {
         Message::concrete(
             Propose::new(
@@ -178,6 +179,7 @@ impl TestNode {
                 Round::first(),
                 last_hash,
                 tx_hashes,
+                prev_precommits,
             ),
             self.consensus_public_key,
             &self.consensus_secret_key,
@@ -340,6 +342,14 @@ impl TestNetworkConfiguration {
         self.stored_configuration.services.insert(id.into(), value);
     }
 
+    /// Sets the schema version of the data of the service with the given identifier,
+    /// which is reached by the service migrations when the configuration becomes actual.
+    pub fn set_service_version(&mut self, id: &str, version: u32) {
+        self.stored_configuration
+            .service_versions
+            .insert(id.into(), version);
+    }
+
     /// Returns the resulting exonum blockchain configuration.
     pub fn stored_configuration(&self) -> &StoredConfiguration {
         &self.stored_configuration